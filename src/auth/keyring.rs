@@ -0,0 +1,306 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Duration;
+use jsonwebtoken::{
+    encode,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header,
+};
+use rand::{distributions::Alphanumeric, Rng};
+use ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use rocket::serde::{json, Serialize};
+use rsa::{
+    pkcs1::{EncodeRsaPrivateKey, LineEnding},
+    traits::PublicKeyParts,
+    RsaPrivateKey,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set,
+};
+
+use crate::entities::{prelude::SigningKey, sea_orm_active_enums::SigningAlgorithm, signing_key};
+
+/// New keys are published this long before they start signing, so that
+/// verifiers caching the key set know them in time.
+const PUBLISH_AHEAD_HOURS: i64 = 24;
+/// Retired keys stay published and accepted this long, which is more than
+/// any token they signed lives.
+const OVERLAP_HOURS: i64 = 24;
+/// How often the keyring rotates and picks up keys created by other
+/// instances.
+const REFRESH_SECONDS: u64 = 60 * 60;
+const RSA_BITS: usize = 2048;
+
+#[derive(Debug)]
+pub struct KeyringError(pub String);
+
+impl From<DbErr> for KeyringError {
+    fn from(err: DbErr) -> Self {
+        KeyringError(err.to_string())
+    }
+}
+
+struct Key {
+    kid: String,
+    algorithm: Algorithm,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    jwk: Jwk,
+    activates_at: DateTimeUtc,
+    expires_at: DateTimeUtc,
+}
+
+impl Key {
+    fn load(k: signing_key::Model) -> Result<Self, KeyringError> {
+        let err = |e: jsonwebtoken::errors::Error| KeyringError(e.to_string());
+
+        let (algorithm, encoding) = match k.algorithm {
+            SigningAlgorithm::RS256 => (
+                Algorithm::RS256,
+                EncodingKey::from_rsa_pem(k.private_key.as_bytes()).map_err(err)?,
+            ),
+            SigningAlgorithm::EdDSA => (
+                Algorithm::EdDSA,
+                EncodingKey::from_ed_pem(k.private_key.as_bytes()).map_err(err)?,
+            ),
+        };
+
+        let jwk: Jwk = json::from_str(&k.public_jwk).map_err(|e| KeyringError(e.to_string()))?;
+
+        Ok(Self {
+            kid: k.kid,
+            algorithm,
+            encoding,
+            decoding: DecodingKey::from_jwk(&jwk).map_err(err)?,
+            jwk,
+            activates_at: k.activates_at,
+            expires_at: k.expires_at,
+        })
+    }
+}
+
+/// A private key in PEM and the public half as JWK parameters.
+fn generate(algorithm: SigningAlgorithm) -> Result<(String, AlgorithmParameters), KeyringError> {
+    match algorithm {
+        SigningAlgorithm::RS256 => {
+            let key = RsaPrivateKey::new(&mut rand::thread_rng(), RSA_BITS)
+                .map_err(|e| KeyringError(e.to_string()))?;
+            let pem = key
+                .to_pkcs1_pem(LineEnding::LF)
+                .map_err(|e| KeyringError(e.to_string()))?;
+
+            Ok((
+                pem.to_string(),
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+                    e: URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+                }),
+            ))
+        }
+        SigningAlgorithm::EdDSA => {
+            let der = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|_| KeyringError("Could not generate an Ed25519 key".to_string()))?;
+            let pair = Ed25519KeyPair::from_pkcs8(der.as_ref())
+                .map_err(|e| KeyringError(e.to_string()))?;
+
+            Ok((
+                pem::encode(&pem::Pem::new("PRIVATE KEY", der.as_ref())),
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(pair.public_key().as_ref()),
+                }),
+            ))
+        }
+    }
+}
+
+/// The keys session tokens are signed with. Keys are kept in the database so
+/// every instance signs with the same ones, and rotate on a schedule: each
+/// signs for the rotation period, is published ahead of it and accepted for
+/// a while after.
+#[derive(Clone)]
+pub struct Keyring {
+    algorithm: SigningAlgorithm,
+    rotation: Duration,
+    keys: Arc<RwLock<Vec<Key>>>,
+}
+
+impl Keyring {
+    pub fn new(algorithm: SigningAlgorithm, rotation_days: i64) -> Self {
+        Self {
+            algorithm,
+            rotation: Duration::days(rotation_days),
+            keys: Arc::new(RwLock::new(vec![])),
+        }
+    }
+
+    /// Drops expired keys, creates the next key when the current one is
+    /// about to retire, and reloads the keys from the database.
+    pub async fn rotate(&self, db: &DatabaseConnection) -> Result<(), KeyringError> {
+        let now = DateTimeUtc::from(SystemTime::now());
+
+        SigningKey::delete_many()
+            .filter(signing_key::Column::ExpiresAt.lt(now))
+            .exec(db)
+            .await?;
+
+        let latest = SigningKey::find()
+            .order_by_desc(signing_key::Column::ActivatesAt)
+            .one(db)
+            .await?;
+
+        match latest {
+            None => self.create(db, now).await?,
+            Some(k) if k.retires_at - now < Duration::hours(PUBLISH_AHEAD_HOURS) => {
+                self.create(db, k.retires_at.max(now)).await?
+            }
+            Some(_) => {}
+        }
+
+        let mut keys = vec![];
+
+        for k in SigningKey::find()
+            .filter(signing_key::Column::ExpiresAt.gt(now))
+            .all(db)
+            .await?
+        {
+            let kid = k.kid.to_owned();
+
+            match Key::load(k) {
+                Ok(key) => keys.push(key),
+                Err(e) => eprintln!("Could not load signing key {}: {}", kid, e.0),
+            }
+        }
+
+        *self.keys.write().unwrap() = keys;
+
+        Ok(())
+    }
+
+    async fn create(
+        &self,
+        db: &DatabaseConnection,
+        activates_at: DateTimeUtc,
+    ) -> Result<(), KeyringError> {
+        let algorithm = self.algorithm;
+
+        // RSA key generation takes a while.
+        let (private_key, parameters) =
+            rocket::tokio::task::spawn_blocking(move || generate(algorithm))
+                .await
+                .map_err(|e| KeyringError(e.to_string()))??;
+
+        let kid = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect::<String>();
+
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(match algorithm {
+                    SigningAlgorithm::RS256 => KeyAlgorithm::RS256,
+                    SigningAlgorithm::EdDSA => KeyAlgorithm::EdDSA,
+                }),
+                key_id: Some(kid.to_owned()),
+                ..Default::default()
+            },
+            algorithm: parameters,
+        };
+
+        let retires_at = activates_at + self.rotation;
+
+        signing_key::ActiveModel {
+            kid: Set(kid),
+            algorithm: Set(algorithm),
+            private_key: Set(private_key),
+            public_jwk: Set(json::to_string(&jwk).map_err(|e| KeyringError(e.to_string()))?),
+            activates_at: Set(activates_at),
+            retires_at: Set(retires_at),
+            expires_at: Set(retires_at + Duration::hours(OVERLAP_HOURS)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Rotates in the background for as long as the server runs.
+    pub fn spawn_rotation(&self, db: DatabaseConnection) {
+        let keyring = self.clone();
+
+        rocket::tokio::spawn(async move {
+            let mut interval =
+                rocket::tokio::time::interval(std::time::Duration::from_secs(REFRESH_SECONDS));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = keyring.rotate(&db).await {
+                    eprintln!("Could not rotate signing keys: {}", e.0);
+                }
+            }
+        });
+    }
+
+    /// Signs with the most recently activated key. A key past its rotation
+    /// keeps signing if no successor could be created.
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, KeyringError> {
+        let now = DateTimeUtc::from(SystemTime::now());
+        let keys = self.keys.read().unwrap();
+
+        let key = keys
+            .iter()
+            .filter(|k| k.activates_at <= now)
+            .max_by_key(|k| k.activates_at)
+            .ok_or_else(|| KeyringError("No signing key is active".to_string()))?;
+
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.to_owned());
+
+        encode(&header, claims, &key.encoding).map_err(|e| KeyringError(e.to_string()))
+    }
+
+    /// The key to verify tokens signed with `kid`, unless it has expired.
+    pub fn verifier(&self, kid: &str) -> Option<(Algorithm, DecodingKey)> {
+        let now = DateTimeUtc::from(SystemTime::now());
+
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .find(|k| k.kid == kid && k.expires_at > now)
+            .map(|k| (k.algorithm, k.decoding.clone()))
+    }
+
+    /// Every key that is, was recently, or is about to be signing.
+    pub fn jwks(&self) -> JwkSet {
+        let now = DateTimeUtc::from(SystemTime::now());
+
+        JwkSet {
+            keys: self
+                .keys
+                .read()
+                .unwrap()
+                .iter()
+                .filter(|k| k.expires_at > now)
+                .map(|k| k.jwk.clone())
+                .collect(),
+        }
+    }
+}
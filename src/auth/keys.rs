@@ -0,0 +1,27 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+pub const KEY_PREFIX: &str = "bk_";
+
+/// A random alphanumeric string, for secrets handed out to clients.
+pub fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// A new personal API key and the part of it shown to identify the key later.
+pub fn generate() -> (String, String) {
+    let key = format!("{}{}", KEY_PREFIX, random_string(40));
+    let prefix = key[..KEY_PREFIX.len() + 6].to_string();
+
+    (key, prefix)
+}
+
+/// Keys are long and random, so a fast unsalted hash is enough and lets the
+/// key be looked up by its hash.
+pub fn hash(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
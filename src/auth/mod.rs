@@ -1,46 +1,534 @@
-use jsonwebtoken::{decode, DecodingKey, Validation};
+pub mod keyring;
+pub mod keys;
+pub mod two_factor;
 
-use rocket::http::Status;
+use std::{fmt, time::SystemTime};
+
+use jsonwebtoken::{
+    decode, decode_header, encode, errors::ErrorKind, DecodingKey, EncodingKey, Header, Validation,
+};
+
+use rand::{distributions::Alphanumeric, Rng};
+
+use rocket::http::{Cookie, CookieJar, Method, SameSite, Status};
+use rocket::outcome::try_outcome;
 use rocket::request::{self, FromRequest, Outcome, Request};
 use rocket::serde::{Deserialize, Serialize};
+use rocket::time::Duration as CookieDuration;
 
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, Set,
+};
+
+use crate::entities::{
+    api_key,
+    prelude::{ApiKey, Session, User},
+    sea_orm_active_enums::{ApiKeyScope, UserRole},
+    session, user,
+};
 use crate::AppConfig;
 
+use keyring::{Keyring, KeyringError};
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(crate = "rocket::serde")]
 pub struct Claims {
     pub sub: u32,
-    pub role: String,
+    pub role: UserRole,
+    pub iss: String,
+    pub aud: String,
     pub exp: u64,
+    /// The user's session version when the token was issued. Bumping the
+    /// version revokes every token issued before.
+    #[serde(default)]
+    pub ver: i32,
+    /// The session the token belongs to. Revoking the session revokes the
+    /// token.
+    #[serde(default)]
+    pub sid: i32,
+}
+
+/// How long session tokens are valid, in seconds.
+pub const SESSION_TTL: u64 = 4 * 60 * 60;
+
+/// A session token for `user` in `session`. Session tokens are signed with
+/// the [`Keyring`], so other services can verify them with the published
+/// keys alone.
+pub fn issue_session_token(
+    config: &AppConfig,
+    keyring: &Keyring,
+    user: &user::Model,
+    session: &session::Model,
+) -> Result<String, KeyringError> {
+    let claims = Claims {
+        sub: user.id as u32,
+        role: user.role,
+        iss: config.jwt_issuer.to_owned(),
+        aud: config.jwt_audience.to_owned(),
+        exp: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + SESSION_TTL,
+        ver: user.session_version,
+        sid: session.id,
+    };
+
+    keyring.sign(&claims)
+}
+
+/// Claims of single-purpose tokens, such as those sent by email or the
+/// challenge between the two steps of sign-in. They are bound to the user's
+/// email address, so they stop working once it changes. Only this service
+/// reads them, so they are signed with the shared secret.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ActionClaims {
+    pub sub: u32,
+    pub email: String,
+    pub purpose: String,
+    pub exp: u64,
+    /// The user's session version, for tokens that must be single-use.
+    #[serde(default)]
+    pub ver: i32,
+    /// The address an email change was requested to, which the token
+    /// proves the user controls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_email: Option<String>,
+}
+
+fn encode_action_token(
+    config: &AppConfig,
+    user: &user::Model,
+    purpose: &str,
+    ttl: u64,
+    new_email: Option<String>,
+) -> String {
+    let claims = ActionClaims {
+        sub: user.id as u32,
+        email: user.email.to_owned(),
+        purpose: purpose.to_string(),
+        ver: user.session_version,
+        exp: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + ttl,
+        new_email,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+pub fn issue_action_token(
+    config: &AppConfig,
+    user: &user::Model,
+    purpose: &str,
+    ttl: u64,
+) -> String {
+    encode_action_token(config, user, purpose, ttl, None)
+}
+
+/// A token confirming that `user` may change their address to `new_email`,
+/// to be sent to the new address.
+pub fn issue_email_change_token(
+    config: &AppConfig,
+    user: &user::Model,
+    purpose: &str,
+    ttl: u64,
+    new_email: &str,
+) -> String {
+    encode_action_token(config, user, purpose, ttl, Some(new_email.to_string()))
+}
+
+/// Returns the claims of `token` if it is valid, unexpired and was issued
+/// for `purpose`.
+pub fn decode_action_token(config: &AppConfig, token: &str, purpose: &str) -> Option<ActionClaims> {
+    let claims = decode::<ActionClaims>(
+        token,
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
+        &Validation::new(jsonwebtoken::Algorithm::HS256),
+    )
+    .ok()?
+    .claims;
+
+    (claims.purpose == purpose).then_some(claims)
+}
+
+/// How often the last use of an API key or session is recorded, in seconds.
+const LAST_USE_RESOLUTION: i64 = 60;
+
+pub const SESSION_COOKIE: &str = "session";
+/// Readable by scripts, which echo it in the `x-csrf-token` header.
+pub const CSRF_COOKIE: &str = "csrf_token";
+
+/// Stores a session token in an HttpOnly cookie for browser clients, along
+/// with a fresh CSRF token.
+pub fn set_session_cookies(cookies: &CookieJar<'_>, token: &str) {
+    let csrf_token = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect::<String>();
+
+    cookies.add(
+        Cookie::build((SESSION_COOKIE, token.to_string()))
+            .path("/")
+            .http_only(true)
+            .secure(true)
+            .same_site(SameSite::Strict)
+            .max_age(CookieDuration::seconds(SESSION_TTL as i64)),
+    );
+    cookies.add(
+        Cookie::build((CSRF_COOKIE, csrf_token))
+            .path("/")
+            .secure(true)
+            .same_site(SameSite::Strict)
+            .max_age(CookieDuration::seconds(SESSION_TTL as i64)),
+    );
+}
+
+pub fn remove_session_cookies(cookies: &CookieJar<'_>) {
+    cookies.remove(Cookie::build(SESSION_COOKIE).path("/"));
+    cookies.remove(Cookie::build(CSRF_COOKIE).path("/"));
+}
+
+/// Why a request could not be authenticated or authorized. The guards keep
+/// it in the request's local cache for the error catchers to report.
+#[derive(Debug, Clone)]
+pub enum AuthError {
+    Missing,
+    Malformed,
+    Expired,
+    BadSignature,
+    WrongAudience,
+    Revoked,
+    InvalidApiKey,
+    Csrf,
+    InsufficientScope,
+    AdminRequired,
+    Database(String),
+}
+
+impl AuthError {
+    fn status(&self) -> Status {
+        match self {
+            Self::Missing
+            | Self::Malformed
+            | Self::Expired
+            | Self::BadSignature
+            | Self::WrongAudience
+            | Self::Revoked
+            | Self::InvalidApiKey => Status::Unauthorized,
+            Self::Csrf | Self::InsufficientScope | Self::AdminRequired => Status::Forbidden,
+            Self::Database(_) => Status::InternalServerError,
+        }
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "Authentication token missing"),
+            Self::Malformed => write!(f, "Authentication token is malformed"),
+            Self::Expired => write!(f, "Authentication token has expired"),
+            Self::BadSignature => write!(f, "Authentication token has an invalid signature"),
+            Self::WrongAudience => {
+                write!(f, "Authentication token was not issued for this service")
+            }
+            Self::Revoked => write!(f, "Session has been revoked"),
+            Self::InvalidApiKey => write!(f, "Invalid API key"),
+            Self::Csrf => write!(f, "Missing or invalid CSRF token"),
+            Self::InsufficientScope => {
+                write!(f, "The API key's scope does not allow this request")
+            }
+            Self::AdminRequired => write!(f, "Admin role required"),
+            Self::Database(e) => write!(f, "{}", e),
+        }
+    }
 }
 
+/// The reason the request was refused, if a guard refused it.
+pub fn auth_error<'r>(req: &'r Request<'_>) -> &'r Option<AuthError> {
+    req.local_cache(|| None::<AuthError>)
+}
+
+fn fail<T>(req: &Request<'_>, error: AuthError) -> request::Outcome<T, AuthError> {
+    let status = error.status();
+    req.local_cache(|| Some(error.clone()));
+
+    Outcome::Error((status, error))
+}
+
+/// The signed-in user. Requests authenticate with a session token or a
+/// personal API key, sent as `Authorization: Bearer`, in the legacy `token`
+/// or `x-api-key` headers, or, when enabled, with the session cookie.
+///
+/// API keys other than admin-scoped ones only pass this guard for reads;
+/// [`CatalogueEditor`] lets catalogue-write keys through for catalogue changes.
 pub struct AuthenticatedUser {
     pub id: u32,
+    pub role: UserRole,
+    /// The scope of the API key used, or `None` for a session token.
+    pub key_scope: Option<ApiKeyScope>,
+    /// The session of the token used, or `None` for an API key.
+    pub session_id: Option<i32>,
 }
 
-#[rocket::async_trait]
-impl<'r> FromRequest<'r> for AuthenticatedUser {
-    type Error = String;
+type AuthOutcome = request::Outcome<AuthenticatedUser, AuthError>;
 
-    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        if let Some(token) = req.headers().get_one("token") {
-            let config = req.rocket().state::<AppConfig>().unwrap();
-
-            let data = decode::<Claims>(
-                token,
-                &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
-                &Validation::new(jsonwebtoken::Algorithm::HS256),
-            );
-
-            let claims = match data {
-                Ok(p) => p.claims,
-                Err(_) => {
-                    return Outcome::Error((Status::Unauthorized, "Invalid token".to_string()))
+async fn authenticate_session(req: &Request<'_>, token: &str) -> AuthOutcome {
+    let config = req.rocket().state::<AppConfig>().unwrap();
+    let keyring = req.rocket().state::<Keyring>().unwrap();
+
+    let kid = match decode_header(token) {
+        Ok(header) => header.kid,
+        Err(_) => return fail(req, AuthError::Malformed),
+    };
+
+    // Tokens without a known key were not signed by us, or so long ago that
+    // the key has expired.
+    let (algorithm, key) = match kid.and_then(|kid| keyring.verifier(&kid)) {
+        Some(k) => k,
+        None => return fail(req, AuthError::BadSignature),
+    };
+
+    let mut validation = Validation::new(algorithm);
+    validation.set_issuer(&[&config.jwt_issuer]);
+    validation.set_audience(&[&config.jwt_audience]);
+
+    let claims = match decode::<Claims>(token, &key, &validation) {
+        Ok(p) => p.claims,
+        Err(e) => {
+            let error = match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::Expired,
+                ErrorKind::InvalidSignature | ErrorKind::InvalidAlgorithm => {
+                    AuthError::BadSignature
                 }
+                ErrorKind::InvalidIssuer | ErrorKind::InvalidAudience => AuthError::WrongAudience,
+                _ => AuthError::Malformed,
             };
-            Outcome::Success(AuthenticatedUser { id: claims.sub })
+
+            return fail(req, error);
+        }
+    };
+
+    let db = req.rocket().state::<DatabaseConnection>().unwrap();
+
+    let found = Session::find_by_id(claims.sid)
+        .filter(session::Column::UserId.eq(claims.sub as i32))
+        .filter(session::Column::RevokedAt.is_null())
+        .find_also_related(User)
+        .one(db)
+        .await;
+
+    let (session, user) = match found {
+        Ok(Some((s, Some(u)))) if u.session_version == claims.ver => (s, u),
+        Ok(_) => return fail(req, AuthError::Revoked),
+        Err(e) => return fail(req, AuthError::Database(e.to_string())),
+    };
+
+    let now = DateTimeUtc::from(SystemTime::now());
+    let session_id = session.id;
+
+    if session
+        .last_seen_at
+        .is_none_or(|at| (now - at).num_seconds() >= LAST_USE_RESOLUTION)
+    {
+        let mut session: session::ActiveModel = session.into();
+        session.last_seen_at = Set(Some(now));
+
+        if let Err(e) = session.update(db).await {
+            return fail(req, AuthError::Database(e.to_string()));
+        }
+    }
+
+    Outcome::Success(AuthenticatedUser {
+        id: claims.sub,
+        role: user.role,
+        key_scope: None,
+        session_id: Some(session_id),
+    })
+}
+
+async fn authenticate_api_key(req: &Request<'_>, key: &str) -> AuthOutcome {
+    let db = req.rocket().state::<DatabaseConnection>().unwrap();
+
+    let found = ApiKey::find()
+        .filter(api_key::Column::KeyHash.eq(keys::hash(key)))
+        .filter(api_key::Column::RevokedAt.is_null())
+        .find_also_related(User)
+        .one(db)
+        .await;
+
+    let (key, user) = match found {
+        Ok(Some((k, Some(u)))) => (k, u),
+        Ok(_) => return fail(req, AuthError::InvalidApiKey),
+        Err(e) => return fail(req, AuthError::Database(e.to_string())),
+    };
+
+    let now = DateTimeUtc::from(SystemTime::now());
+    let scope = key.scope;
+
+    if key
+        .last_used_at
+        .is_none_or(|at| (now - at).num_seconds() >= LAST_USE_RESOLUTION)
+    {
+        let mut key: api_key::ActiveModel = key.into();
+        key.last_used_at = Set(Some(now));
+
+        if let Err(e) = key.update(db).await {
+            return fail(req, AuthError::Database(e.to_string()));
+        }
+    }
+
+    Outcome::Success(AuthenticatedUser {
+        id: user.id as u32,
+        role: user.role,
+        key_scope: Some(scope),
+        session_id: None,
+    })
+}
+
+fn is_read(req: &Request<'_>) -> bool {
+    matches!(req.method(), Method::Get | Method::Head | Method::Options)
+}
+
+/// Cookies are sent by the browser on their own, so requests that change
+/// anything must prove they come from our client by echoing the CSRF cookie.
+fn check_csrf(req: &Request<'_>) -> bool {
+    if is_read(req) {
+        return true;
+    }
+
+    match (
+        req.cookies().get(CSRF_COOKIE),
+        req.headers().get_one("x-csrf-token"),
+    ) {
+        (Some(cookie), Some(header)) => !header.is_empty() && cookie.value() == header,
+        _ => false,
+    }
+}
+
+/// Authenticates the request without looking at what it is for.
+async fn authenticate(req: &Request<'_>) -> AuthOutcome {
+    let config = req.rocket().state::<AppConfig>().unwrap();
+
+    if let Some(authorization) = req.headers().get_one("Authorization") {
+        let credential = match authorization.split_once(' ') {
+            Some((scheme, credential)) if scheme.eq_ignore_ascii_case("bearer") => {
+                credential.trim()
+            }
+            _ => return fail(req, AuthError::Malformed),
+        };
+
+        if credential.starts_with(keys::KEY_PREFIX) {
+            authenticate_api_key(req, credential).await
         } else {
-            Outcome::Error((Status::Unauthorized, "token absent".to_string()))
+            authenticate_session(req, credential).await
+        }
+    } else if let Some(token) = req.headers().get_one("token") {
+        authenticate_session(req, token).await
+    } else if let Some(key) = req.headers().get_one("x-api-key") {
+        authenticate_api_key(req, key).await
+    } else if let Some(cookie) = req
+        .cookies()
+        .get(SESSION_COOKIE)
+        .filter(|_| config.session_cookie)
+    {
+        if !check_csrf(req) {
+            return fail(req, AuthError::Csrf);
+        }
+
+        authenticate_session(req, cookie.value()).await
+    } else {
+        fail(req, AuthError::Missing)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuthenticatedUser {
+    type Error = AuthError;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let user = try_outcome!(authenticate(req).await);
+
+        match user.key_scope {
+            Some(ApiKeyScope::ReadOnly | ApiKeyScope::CatalogueWrite) if !is_read(req) => {
+                fail(req, AuthError::InsufficientScope)
+            }
+            _ => Outcome::Success(user),
         }
     }
 }
+
+/// The signed-in user, or `None` for a request without any credentials.
+/// Credentials that are sent but rejected fail the request rather than
+/// falling back to an anonymous one.
+pub struct MaybeUser(pub Option<AuthenticatedUser>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MaybeUser {
+    type Error = AuthError;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match AuthenticatedUser::from_request(req).await {
+            Outcome::Success(user) => Outcome::Success(MaybeUser(Some(user))),
+            Outcome::Error((_, AuthError::Missing)) => Outcome::Success(MaybeUser(None)),
+            Outcome::Error(e) => Outcome::Error(e),
+            Outcome::Forward(status) => Outcome::Forward(status),
+        }
+    }
+}
+
+/// A user allowed to change the catalogue: any signed-in user, through a
+/// session or an API key with the catalogue-write or admin scope.
+pub struct CatalogueEditor {
+    pub id: u32,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CatalogueEditor {
+    type Error = AuthError;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let user = try_outcome!(authenticate(req).await);
+
+        if user.key_scope == Some(ApiKeyScope::ReadOnly) {
+            return fail(req, AuthError::InsufficientScope);
+        }
+
+        Outcome::Success(CatalogueEditor { id: user.id })
+    }
+}
+
+/// An authenticated user with the admin role. Roles are granted directly in
+/// the `user` table. API keys need the admin scope.
+pub struct AdminUser {
+    pub id: u32,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminUser {
+    type Error = AuthError;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let user = try_outcome!(authenticate(req).await);
+
+        if user.role != UserRole::Admin {
+            return fail(req, AuthError::AdminRequired);
+        }
+
+        if user.key_scope.is_some_and(|s| s != ApiKeyScope::Admin) {
+            return fail(req, AuthError::InsufficientScope);
+        }
+
+        Outcome::Success(AdminUser { id: user.id })
+    }
+}
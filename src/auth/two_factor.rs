@@ -0,0 +1,82 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use qrcode::{render::svg, QrCode};
+use rand::{distributions::Alphanumeric, Rng};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const ISSUER: &str = "Bookstore";
+const RECOVERY_CODES: usize = 10;
+
+/// A random 160-bit secret, base32 encoded as authenticator apps expect.
+pub fn new_secret() -> String {
+    let bytes = rand::thread_rng().gen::<[u8; 20]>();
+
+    match Secret::Raw(bytes.to_vec()).to_encoded() {
+        Secret::Encoded(s) => s,
+        Secret::Raw(_) => unreachable!(),
+    }
+}
+
+/// Six-digit codes over 30 second steps, accepting one step of clock skew.
+pub fn totp(secret: &str, email: &str) -> Option<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        bytes,
+        Some(ISSUER.to_string()),
+        email.to_string(),
+    )
+    .ok()
+}
+
+/// The time step `code` belongs to, if it is valid now and later than
+/// `last_step`. Each code is accepted once: callers store the step and pass
+/// it back as `last_step`, so a code seen in transit cannot be replayed.
+pub fn check(secret: &str, email: &str, code: &str, last_step: Option<i64>) -> Option<i64> {
+    let mut totp = totp(secret, email)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let current = (now / totp.step) as i64;
+    let skew = i64::from(totp.skew);
+
+    // Steps are compared one at a time so the replay check sees which step
+    // matched, not just that one did.
+    totp.skew = 0;
+
+    (current - skew..=current + skew)
+        .filter(|&step| last_step.is_none_or(|last| step > last))
+        .find(|&step| totp.check(code.trim(), step as u64 * totp.step))
+}
+
+/// The enrolment QR code as an SVG document.
+pub fn qr_svg(uri: &str) -> Option<String> {
+    let code = QrCode::new(uri.as_bytes()).ok()?;
+
+    Some(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+/// Fresh recovery codes, formatted `xxxxx-xxxxx` for readability.
+pub fn recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES)
+        .map(|_| {
+            let code = rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(10)
+                .map(|c| char::from(c).to_ascii_lowercase())
+                .collect::<String>();
+
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+/// Recovery codes are compared without the separator or case.
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
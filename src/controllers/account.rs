@@ -0,0 +1,435 @@
+use std::time::SystemTime;
+
+use bcrypt::{hash, verify, DEFAULT_COST};
+use rocket::{
+    http::{ContentType, CookieJar, Header, Status},
+    serde::{json::Json, Deserialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Expr},
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+
+use super::{invoice, ErrorResponse, GenericResponse, Response, SuccessResponse};
+use crate::{
+    auth::{
+        decode_action_token, issue_email_change_token, keys, remove_session_cookies,
+        AuthenticatedUser,
+    },
+    entities::{
+        api_key, author, book, cart, hold, imprint, loan, oauth_authorization_code, oauth_consent,
+        prelude::*,
+        publisher, reading_list, recovery_code,
+        sea_orm_active_enums::{HoldStatus, UserRole},
+        series, session, user, work,
+    },
+    exports,
+    mail::{Email, Mailer},
+    storage::BlobStorage,
+    AppConfig,
+};
+
+const CHANGE_EMAIL: &str = "change-email";
+const CHANGE_EMAIL_TTL: u64 = 24 * 60 * 60;
+
+fn unprocessable(message: &str) -> ErrorResponse {
+    ErrorResponse((
+        Status::UnprocessableEntity,
+        Json(GenericResponse {
+            message: message.to_string(),
+        }),
+    ))
+}
+
+/// Changing the address or deleting the account must be done by the user
+/// themselves, not by a script holding one of their API keys.
+fn require_session(user: &AuthenticatedUser) -> Result<(), ErrorResponse> {
+    match user.key_scope {
+        Some(_) => Err(ErrorResponse((
+            Status::Forbidden,
+            Json(GenericResponse {
+                message: "This requires signing in with a password".to_string(),
+            }),
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn check_current_password(user: &user::Model, password: &str) -> Result<(), ErrorResponse> {
+    match verify(password, &user.password).unwrap_or(false) {
+        true => Ok(()),
+        false => Err(ErrorResponse((
+            Status::Forbidden,
+            Json(GenericResponse {
+                message: "The current password is incorrect".to_string(),
+            }),
+        ))),
+    }
+}
+
+async fn send_notice(mailer: &dyn Mailer, email: Email) {
+    if let Err(e) = mailer.send(email).await {
+        eprintln!("Could not send account notice: {}", e.0);
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqChangeEmail {
+    email: String,
+    password: String,
+}
+
+/// Emails a confirmation token to the new address. The address only changes
+/// once the token comes back, so it is verified from the start.
+#[post("/me/email", data = "<req_change_email>")]
+pub async fn change_email(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    mailer: &State<Box<dyn Mailer>>,
+    user: AuthenticatedUser,
+    req_change_email: Json<ReqChangeEmail>,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    require_session(&user)?;
+
+    let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
+
+    check_current_password(&user, &req_change_email.password)?;
+
+    let email = req_change_email.email.trim();
+
+    if email.parse::<lettre::Address>().is_err() {
+        return Err(unprocessable("email must be a valid email address"));
+    }
+
+    if email == user.email {
+        return Err(unprocessable("That is already your email address"));
+    }
+
+    if User::find()
+        .filter(user::Column::Email.eq(email))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        return Err(unprocessable("An account exists with that email"));
+    }
+
+    let token = issue_email_change_token(config, &user, CHANGE_EMAIL, CHANGE_EMAIL_TTL, email);
+
+    mailer
+        .send(Email {
+            to: email.to_string(),
+            subject: "Confirm your new email address".to_string(),
+            body: format!(
+                "Someone asked to use this address for their bookstore account.\n\n\
+                 If it was you, use this token within 24 hours to confirm the change:\n\n\
+                 {}\n\n\
+                 Otherwise, you can ignore this email.\n",
+                token
+            ),
+        })
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "A confirmation email is on its way to the new address".to_string(),
+        }),
+    )))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqConfirmEmail {
+    token: String,
+}
+
+/// Does not require signing in, since the token arrives by email. The old
+/// address is told about the change.
+#[post("/me/email/confirm", data = "<req_confirm_email>")]
+pub async fn confirm_email(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    mailer: &State<Box<dyn Mailer>>,
+    req_confirm_email: Json<ReqConfirmEmail>,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let invalid = || unprocessable("The confirmation token is invalid or has expired");
+
+    let claims =
+        decode_action_token(config, &req_confirm_email.token, CHANGE_EMAIL).ok_or_else(invalid)?;
+    let new_email = claims.new_email.ok_or_else(invalid)?;
+
+    // Bound to the old address, so the token stops working once used.
+    let user = User::find_by_id(claims.sub as i32)
+        .one(db)
+        .await?
+        .filter(|u| u.email == claims.email && u.session_version == claims.ver)
+        .ok_or_else(invalid)?;
+
+    if User::find()
+        .filter(user::Column::Email.eq(&new_email))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        return Err(unprocessable("An account exists with that email"));
+    }
+
+    let old_email = user.email.to_owned();
+    let now = DateTimeUtc::from(SystemTime::now());
+
+    let mut user: user::ActiveModel = user.into();
+    user.email = Set(new_email.to_owned());
+    user.email_verified_at = Set(Some(now));
+    user.updated_at = Set(Some(now));
+    user.update(db).await?;
+
+    send_notice(
+        mailer.inner().as_ref(),
+        Email {
+            to: old_email,
+            subject: "Your email address has changed".to_string(),
+            body: format!(
+                "The email address of your bookstore account is now {}.\n\n\
+                 If you didn't make this change, please contact us right away.\n",
+                new_email
+            ),
+        },
+    )
+    .await;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Email address changed".to_string(),
+        }),
+    )))
+}
+
+#[derive(Responder)]
+pub struct ResExport(Vec<u8>, ContentType, Header<'static>);
+
+/// Everything stored about the user, as a tar archive of JSON files.
+#[get("/me/export")]
+pub async fn export(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<ResExport> {
+    let db = db as &DatabaseConnection;
+
+    require_session(&user)?;
+
+    let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
+
+    let archive = exports::render(db, &user).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        ResExport(
+            archive,
+            ContentType::new("application", "x-tar"),
+            Header::new(
+                "Content-Disposition",
+                format!("attachment; filename=\"bookstore-export-{}.tar\"", user.id),
+            ),
+        ),
+    )))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqDeleteAccount {
+    password: String,
+}
+
+/// Deletes the account. Orders, invoices and reviews are kept for the
+/// bookstore's records but no longer identify the user, catalogue records are
+/// handed to the longest-serving admin, and everything else is removed. Open
+/// loans and holds must be settled first.
+#[delete("/me", data = "<req_delete_account>")]
+pub async fn delete_account(
+    db: &State<DatabaseConnection>,
+    storage: &State<Box<dyn BlobStorage>>,
+    mailer: &State<Box<dyn Mailer>>,
+    cookies: &CookieJar<'_>,
+    user: AuthenticatedUser,
+    req_delete_account: Json<ReqDeleteAccount>,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    require_session(&user)?;
+
+    let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
+
+    check_current_password(&user, &req_delete_account.password)?;
+
+    let conflict = |message: &str| {
+        ErrorResponse((
+            Status::Conflict,
+            Json(GenericResponse {
+                message: message.to_string(),
+            }),
+        ))
+    };
+
+    let open_loans = Loan::find()
+        .filter(loan::Column::UserId.eq(user.id))
+        .filter(loan::Column::ReturnedAt.is_null())
+        .one(db)
+        .await?;
+
+    let open_holds = Hold::find()
+        .filter(hold::Column::UserId.eq(user.id))
+        .filter(hold::Column::Status.is_in([HoldStatus::Waiting, HoldStatus::Ready]))
+        .one(db)
+        .await?;
+
+    if open_loans.is_some() || open_holds.is_some() {
+        return Err(conflict(
+            "Return your loans and cancel your holds before deleting your account",
+        ));
+    }
+
+    let admin = User::find()
+        .filter(user::Column::Role.eq(UserRole::Admin))
+        .filter(user::Column::DeletedAt.is_null())
+        .filter(user::Column::Id.ne(user.id))
+        .order_by_asc(user::Column::Id)
+        .one(db)
+        .await?;
+
+    if user.role == UserRole::Admin && admin.is_none() {
+        return Err(conflict(
+            "Make someone else an admin before deleting the last admin account",
+        ));
+    }
+
+    let email = user.email.to_owned();
+    let now = DateTimeUtc::from(SystemTime::now());
+
+    let txn = db.begin().await?;
+
+    if let Some(admin) = admin {
+        Author::update_many()
+            .col_expr(author::Column::UserId, Expr::value(admin.id))
+            .filter(author::Column::UserId.eq(user.id))
+            .exec(&txn)
+            .await?;
+        Publisher::update_many()
+            .col_expr(publisher::Column::UserId, Expr::value(admin.id))
+            .filter(publisher::Column::UserId.eq(user.id))
+            .exec(&txn)
+            .await?;
+        Imprint::update_many()
+            .col_expr(imprint::Column::UserId, Expr::value(admin.id))
+            .filter(imprint::Column::UserId.eq(user.id))
+            .exec(&txn)
+            .await?;
+        Series::update_many()
+            .col_expr(series::Column::UserId, Expr::value(admin.id))
+            .filter(series::Column::UserId.eq(user.id))
+            .exec(&txn)
+            .await?;
+        Work::update_many()
+            .col_expr(work::Column::UserId, Expr::value(admin.id))
+            .filter(work::Column::UserId.eq(user.id))
+            .exec(&txn)
+            .await?;
+        Book::update_many()
+            .col_expr(book::Column::UserId, Expr::value(admin.id))
+            .filter(book::Column::UserId.eq(user.id))
+            .exec(&txn)
+            .await?;
+    }
+
+    Session::delete_many()
+        .filter(session::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+    ApiKey::delete_many()
+        .filter(api_key::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+    RecoveryCode::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+    Cart::delete_many()
+        .filter(cart::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+    ReadingList::delete_many()
+        .filter(reading_list::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+    Hold::delete_many()
+        .filter(hold::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+    OauthConsent::delete_many()
+        .filter(oauth_consent::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+    OauthAuthorizationCode::delete_many()
+        .filter(oauth_authorization_code::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+
+    // The row stays for the orders, reviews and loans that point to it, but
+    // can no longer be signed in to or traced back to the user.
+    let session_version = user.session_version + 1;
+    let mut user: user::ActiveModel = user.into();
+    user.email = Set(format!("deleted-{}@invalid", user.id.as_ref()));
+    user.password = Set(hash(keys::random_string(40), DEFAULT_COST).unwrap());
+    user.firstname = Set(None);
+    user.lastname = Set(None);
+    user.role = Set(UserRole::User);
+    user.email_verified_at = Set(None);
+    user.verification_sent_at = Set(None);
+    user.session_version = Set(session_version);
+    user.totp_secret = Set(None);
+    user.totp_enabled_at = Set(None);
+    user.totp_last_step = Set(None);
+    user.deleted_at = Set(Some(now));
+    user.updated_at = Set(Some(now));
+    let user = user.update(&txn).await?;
+
+    // Issued invoices are kept for accounting, but are rendered again from
+    // the anonymized row. Storage failures roll the deletion back so it can
+    // be retried rather than leaving the old documents behind.
+    invoice::redact(&txn, storage.inner().as_ref(), &user).await?;
+
+    txn.commit().await?;
+
+    remove_session_cookies(cookies);
+
+    send_notice(
+        mailer.inner().as_ref(),
+        Email {
+            to: email,
+            subject: "Your account has been deleted".to_string(),
+            body: "Your bookstore account has been deleted, as you asked.\n\n\
+                   We kept your orders, invoices and reviews for our records, but \
+                   they no longer carry your name or email address. Thanks for \
+                   reading with us.\n"
+                .to_string(),
+        },
+    )
+    .await;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Account deleted".to_string(),
+        }),
+    )))
+}
@@ -0,0 +1,195 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, Set,
+};
+
+use crate::{
+    auth::{keys, AuthenticatedUser},
+    entities::{
+        api_key,
+        prelude::*,
+        sea_orm_active_enums::{ApiKeyScope, UserRole},
+    },
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResApiKey {
+    id: i32,
+    name: String,
+    scope: ApiKeyScope,
+    /// The first characters of the key, to tell keys apart.
+    prefix: String,
+    last_used_at: Option<DateTimeUtc>,
+    revoked_at: Option<DateTimeUtc>,
+    created_at: Option<DateTimeUtc>,
+}
+
+impl From<api_key::Model> for ResApiKey {
+    fn from(k: api_key::Model) -> Self {
+        Self {
+            id: k.id,
+            name: k.name,
+            scope: k.scope,
+            prefix: k.prefix,
+            last_used_at: k.last_used_at,
+            revoked_at: k.revoked_at,
+            created_at: k.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResApiKeyList {
+    total: usize,
+    api_keys: Vec<ResApiKey>,
+}
+
+/// The only response that includes the key itself.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResNewApiKey {
+    key: String,
+    #[serde(flatten)]
+    api_key: ResApiKey,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqApiKey {
+    name: String,
+    scope: ApiKeyScope,
+}
+
+/// API keys of the current user, revoked ones included.
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<ResApiKeyList>> {
+    let db = db as &DatabaseConnection;
+
+    let api_keys = ApiKey::find()
+        .filter(api_key::Column::UserId.eq(user.id as i32))
+        .order_by_desc(api_key::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(ResApiKey::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResApiKeyList {
+            total: api_keys.len(),
+            api_keys,
+        }),
+    )))
+}
+
+/// Keys can only be created from a signed-in session, and only admins can
+/// create admin-scoped keys, which act with all the rights of their owner.
+#[post("/", data = "<req_api_key>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    req_api_key: Json<ReqApiKey>,
+) -> Response<Json<ResNewApiKey>> {
+    let db = db as &DatabaseConnection;
+
+    let unprocessable = |message: &str| {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: message.to_string(),
+            }),
+        ))
+    };
+
+    if user.key_scope.is_some() {
+        return Err(ErrorResponse((
+            Status::Forbidden,
+            Json(GenericResponse {
+                message: "API keys cannot create other API keys".to_string(),
+            }),
+        )));
+    }
+
+    let name = req_api_key.name.trim();
+
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(unprocessable("name must be between 1 and 100 characters"));
+    }
+
+    if req_api_key.scope == ApiKeyScope::Admin && user.role != UserRole::Admin {
+        return Err(unprocessable("Only admins can create admin-scoped keys"));
+    }
+
+    let (key, prefix) = keys::generate();
+
+    let api_key = api_key::ActiveModel {
+        user_id: Set(user.id as i32),
+        name: Set(name.to_string()),
+        scope: Set(req_api_key.scope),
+        prefix: Set(prefix),
+        key_hash: Set(keys::hash(&key)),
+        created_at: Set(Some(DateTimeUtc::from(SystemTime::now()))),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResNewApiKey {
+            key,
+            api_key: ResApiKey::from(api_key),
+        }),
+    )))
+}
+
+#[delete("/<id>")]
+pub async fn revoke(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResApiKey>> {
+    let db = db as &DatabaseConnection;
+
+    let api_key = ApiKey::find_by_id(id)
+        .filter(api_key::Column::UserId.eq(user.id as i32))
+        .filter(api_key::Column::RevokedAt.is_null())
+        .one(db)
+        .await?;
+
+    let api_key = match api_key {
+        Some(k) => k,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find an active API key with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    let mut api_key: api_key::ActiveModel = api_key.into();
+    api_key.revoked_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    let api_key = api_key.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResApiKey::from(api_key)),
+    )))
+}
@@ -1,19 +1,29 @@
-use std::time::SystemTime;
+use std::{net::IpAddr, time::SystemTime};
 
 use bcrypt::{hash, verify, DEFAULT_COST};
+use chrono::Duration;
 
-use jsonwebtoken::{encode, EncodingKey, Header};
 use rocket::{
-    http::Status,
+    http::{CookieJar, Status},
+    request::{self, FromRequest, Outcome, Request},
     serde::{json::Json, Deserialize, Serialize},
     State,
 };
-use sea_orm::DatabaseConnection;
+use sea_orm::{prelude::DateTimeUtc, DatabaseConnection};
 
-use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+use super::{
+    cart::{merge_guest_cart, CartToken},
+    lockout::{self, SignInSubjects},
+    session::revoke_sessions,
+    ErrorResponse, GenericResponse, Response, SuccessResponse,
+};
 use crate::{
-    auth::{AuthenticatedUser, Claims},
-    entities::{prelude::*, user},
+    auth::{
+        decode_action_token, issue_action_token, issue_session_token, keyring::Keyring,
+        remove_session_cookies, set_session_cookies, AuthenticatedUser, SESSION_TTL,
+    },
+    entities::{prelude::*, sea_orm_active_enums::UserRole, session, user},
+    mail::{Email, Mailer},
     AppConfig,
 };
 
@@ -24,68 +34,187 @@ use sea_orm::*;
 pub struct ReqSignIn {
     email: String,
     password: String,
+    /// A name for the session, such as "Work laptop", shown in the session
+    /// list.
+    device_name: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Responder)]
+/// Accounts with two-factor authentication get a `challenge_token` instead
+/// of a session token, to be exchanged at `/auth/sign-in/2fa`.
+#[derive(Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ResSignIn {
-    token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    challenge_token: Option<String>,
+}
+
+/// Starts sessions: records the session, issues its token and, when cookie
+/// transport is enabled, sets the session cookie.
+pub struct SessionIssuer<'r> {
+    db: &'r DatabaseConnection,
+    config: &'r AppConfig,
+    keyring: &'r Keyring,
+    cookies: &'r CookieJar<'r>,
+    ip_address: Option<IpAddr>,
+    user_agent: Option<&'r str>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SessionIssuer<'r> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(SessionIssuer {
+            db: req.rocket().state::<DatabaseConnection>().unwrap(),
+            config: req.rocket().state::<AppConfig>().unwrap(),
+            keyring: req.rocket().state::<Keyring>().unwrap(),
+            cookies: req.cookies(),
+            ip_address: req.client_ip(),
+            user_agent: req.headers().get_one("User-Agent"),
+        })
+    }
+}
+
+impl SessionIssuer<'_> {
+    pub async fn start(
+        &self,
+        user: &user::Model,
+        device_name: Option<&str>,
+    ) -> Result<ResSignIn, ErrorResponse> {
+        let truncate = |s: &str, len: usize| s.trim().chars().take(len).collect::<String>();
+        let now = DateTimeUtc::from(SystemTime::now());
+
+        let session = session::ActiveModel {
+            user_id: Set(user.id),
+            device_name: Set(device_name
+                .map(|n| truncate(n, 100))
+                .filter(|n| !n.is_empty())),
+            ip_address: Set(self.ip_address.map(|ip| ip.to_string())),
+            user_agent: Set(self.user_agent.map(|ua| truncate(ua, 255))),
+            expires_at: Set(now + Duration::seconds(SESSION_TTL as i64)),
+            last_seen_at: Set(Some(now)),
+            created_at: Set(Some(now)),
+            ..Default::default()
+        }
+        .insert(self.db)
+        .await?;
+
+        let token = issue_session_token(self.config, self.keyring, user, &session)?;
+
+        if self.config.session_cookie {
+            set_session_cookies(self.cookies, &token);
+        }
+
+        Ok(ResSignIn {
+            token: Some(token),
+            challenge_token: None,
+        })
+    }
 }
 
+pub const SIGN_IN_CHALLENGE: &str = "sign-in-2fa";
+const CHALLENGE_TTL: u64 = 5 * 60;
+
+/// Failed attempts are throttled per account and per client address, see
+/// [`lockout`].
 #[post("/sign-in", data = "<req_sign_in>")]
 pub async fn sign_in(
     db: &State<DatabaseConnection>,
     config: &State<AppConfig>,
+    sessions: SessionIssuer<'_>,
+    mailer: &State<Box<dyn Mailer>>,
+    client_ip: Option<IpAddr>,
+    cart_token: CartToken,
     req_sign_in: Json<ReqSignIn>,
 ) -> Response<Json<ResSignIn>> {
     let db = db as &DatabaseConnection;
     let config = config as &AppConfig;
 
-    let user: user::Model = match User::find()
-        .filter(user::Column::Email.eq(&req_sign_in.email))
+    let subjects = SignInSubjects::new(&req_sign_in.email, client_ip);
+
+    lockout::check(db, &subjects).await?;
+
+    let user = User::find()
+        .filter(user::Column::Email.eq(req_sign_in.email.trim()))
         .one(db)
-        .await?
-    {
-        Some(u) => u,
-        None => {
+        .await?;
+
+    let user = match user {
+        Some(u) if verify(&req_sign_in.password, &u.password).unwrap_or(false) => u,
+        user => {
+            lockout::record_failure(db, mailer.inner().as_ref(), &subjects, user.as_ref()).await?;
+
             return Err(ErrorResponse((
                 Status::Unauthorized,
                 Json(GenericResponse {
                     message: "Invalid credentials".to_string(),
                 }),
-            )))
+            )));
         }
     };
 
-    if !verify(&req_sign_in.password, &user.password).unwrap() {
+    if config.require_verified_email && user.email_verified_at.is_none() {
         return Err(ErrorResponse((
-            Status::Unauthorized,
+            Status::Forbidden,
             Json(GenericResponse {
-                message: "Invalid credentials".to_string(),
+                message: "Please verify your email address before signing in".to_string(),
             }),
         )));
     }
 
-    let exp_time = 4 * 60 * 60;
+    if user.totp_enabled_at.is_some() {
+        return Ok(SuccessResponse((
+            Status::Ok,
+            Json(ResSignIn {
+                token: None,
+                challenge_token: Some(issue_action_token(
+                    config,
+                    &user,
+                    SIGN_IN_CHALLENGE,
+                    CHALLENGE_TTL,
+                )),
+            }),
+        )));
+    }
 
-    let claims = Claims {
-        sub: user.id as u32,
-        role: "user".to_string(),
-        exp: SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + exp_time,
-    };
+    lockout::clear(db, &subjects).await?;
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
-    )
-    .unwrap();
+    merge_guest_cart(db, user.id, &cart_token).await?;
 
-    Ok(SuccessResponse((Status::Ok, Json(ResSignIn { token }))))
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(
+            sessions
+                .start(&user, req_sign_in.device_name.as_deref())
+                .await?,
+        ),
+    )))
+}
+
+/// Revokes the current session, if the request has one, and clears the
+/// session cookie.
+#[post("/sign-out")]
+pub async fn sign_out(
+    db: &State<DatabaseConnection>,
+    cookies: &CookieJar<'_>,
+    user: Option<AuthenticatedUser>,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    if let Some(id) = user.and_then(|u| u.session_id) {
+        revoke_sessions(db, session::Column::Id.eq(id)).await?;
+    }
+
+    remove_session_cookies(cookies);
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Signed out".to_string(),
+        }),
+    )))
 }
 
 #[derive(Deserialize)]
@@ -97,15 +226,62 @@ pub struct ReqSignUp {
     lastname: Option<String>,
 }
 
+const VERIFY_EMAIL: &str = "verify-email";
+const VERIFICATION_TTL: u64 = 24 * 60 * 60;
+const RESEND_INTERVAL: i64 = 60;
+
+/// Emails `user` a token to verify their address and records when it was sent.
+async fn send_verification(
+    db: &DatabaseConnection,
+    mailer: &dyn Mailer,
+    config: &AppConfig,
+    user: user::Model,
+) -> Result<(), ErrorResponse> {
+    let token = issue_action_token(config, &user, VERIFY_EMAIL, VERIFICATION_TTL);
+
+    mailer
+        .send(Email {
+            to: user.email.to_owned(),
+            subject: "Verify your email address".to_string(),
+            body: format!(
+                "Welcome to the bookstore!\n\n\
+                 Use this token to verify your email address within 24 hours:\n\n\
+                 {}\n",
+                token
+            ),
+        })
+        .await?;
+
+    let mut user: user::ActiveModel = user.into();
+    user.verification_sent_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    user.update(db).await?;
+
+    Ok(())
+}
+
 #[post("/sign-up", data = "<req_sign_up>")]
 pub async fn sign_up(
     db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    mailer: &State<Box<dyn Mailer>>,
     req_sign_up: Json<ReqSignUp>,
 ) -> Response<String> {
     let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let email = req_sign_up.email.trim();
+
+    if email.parse::<lettre::Address>().is_err() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "email must be a valid email address".to_string(),
+            }),
+        )));
+    }
 
     if User::find()
-        .filter(user::Column::Email.eq(&req_sign_up.email))
+        .filter(user::Column::Email.eq(email))
         .one(db)
         .await?
         .is_some()
@@ -120,22 +296,131 @@ pub async fn sign_up(
         )));
     }
 
-    User::insert(user::ActiveModel {
-        email: Set(req_sign_up.email.to_owned()),
-        password: Set(hash(req_sign_up.password.to_owned(), DEFAULT_COST).unwrap()),
+    let user = user::ActiveModel {
+        email: Set(email.to_string()),
+        password: Set(hash(&req_sign_up.password, DEFAULT_COST).unwrap()),
         firstname: Set(req_sign_up.firstname.to_owned()),
         lastname: Set(req_sign_up.lastname.to_owned()),
+        role: Set(UserRole::User),
         ..Default::default()
-    })
-    .exec(db)
+    }
+    .insert(db)
     .await?;
 
+    // The account exists either way; the email can be sent again later.
+    if let Err(ErrorResponse((_, e))) =
+        send_verification(db, mailer.inner().as_ref(), config, user).await
+    {
+        eprintln!("Could not send verification email: {}", e.message);
+    }
+
     Ok(SuccessResponse((
         Status::Created,
         "Account created".to_string(),
     )))
 }
 
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqVerifyEmail {
+    token: String,
+}
+
+#[post("/verify-email", data = "<req_verify_email>")]
+pub async fn verify_email(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    req_verify_email: Json<ReqVerifyEmail>,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let invalid = || {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "The verification token is invalid or has expired".to_string(),
+            }),
+        ))
+    };
+
+    let claims =
+        decode_action_token(config, &req_verify_email.token, VERIFY_EMAIL).ok_or_else(invalid)?;
+
+    let user = User::find_by_id(claims.sub as i32)
+        .one(db)
+        .await?
+        .filter(|u| u.email == claims.email)
+        .ok_or_else(invalid)?;
+
+    if user.email_verified_at.is_none() {
+        let mut user: user::ActiveModel = user.into();
+        user.email_verified_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+        user.update(db).await?;
+    }
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Email address verified".to_string(),
+        }),
+    )))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqResendVerification {
+    email: String,
+}
+
+/// Does not require signing in, since sign-in may be blocked until the
+/// address is verified.
+#[post("/resend-verification", data = "<req_resend>")]
+pub async fn resend_verification(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    mailer: &State<Box<dyn Mailer>>,
+    req_resend: Json<ReqResendVerification>,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let user = User::find()
+        .filter(user::Column::Email.eq(req_resend.email.trim()))
+        .filter(user::Column::EmailVerifiedAt.is_null())
+        .one(db)
+        .await?;
+
+    if let Some(user) = user {
+        let now = DateTimeUtc::from(SystemTime::now());
+
+        if user
+            .verification_sent_at
+            .is_some_and(|at| (now - at).num_seconds() < RESEND_INTERVAL)
+        {
+            return Err(ErrorResponse((
+                Status::TooManyRequests,
+                Json(GenericResponse {
+                    message: format!(
+                        "Please wait {} seconds before requesting another email",
+                        RESEND_INTERVAL
+                    ),
+                }),
+            )));
+        }
+
+        send_verification(db, mailer.inner().as_ref(), config, user).await?;
+    }
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "If the address belongs to an unverified account, a new email is on its way"
+                .to_string(),
+        }),
+    )))
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ResMe {
@@ -143,6 +428,23 @@ pub struct ResMe {
     email: String,
     firstname: Option<String>,
     lastname: Option<String>,
+    role: UserRole,
+    email_verified_at: Option<DateTimeUtc>,
+    two_factor_enabled: bool,
+}
+
+impl From<user::Model> for ResMe {
+    fn from(user: user::Model) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            firstname: user.firstname,
+            lastname: user.lastname,
+            role: user.role,
+            email_verified_at: user.email_verified_at,
+            two_factor_enabled: user.totp_enabled_at.is_some(),
+        }
+    }
 }
 
 #[get("/me")]
@@ -151,13 +453,232 @@ pub async fn me(db: &State<DatabaseConnection>, user: AuthenticatedUser) -> Resp
 
     let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
 
+    Ok(SuccessResponse((Status::Ok, Json(ResMe::from(user)))))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqUpdateMe {
+    firstname: Option<String>,
+    lastname: Option<String>,
+}
+
+/// Replaces the user's names. Blank names are cleared. The email address is
+/// changed through `/auth/me/email` instead.
+#[put("/me", data = "<req_update_me>")]
+pub async fn update_me(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    req_update_me: Json<ReqUpdateMe>,
+) -> Response<Json<ResMe>> {
+    let db = db as &DatabaseConnection;
+
+    let name = |value: &Option<String>| -> Result<Option<String>, ErrorResponse> {
+        match value.as_deref().map(str::trim) {
+            Some(v) if v.chars().count() > 100 => Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: "Names must be at most 100 characters".to_string(),
+                }),
+            ))),
+            Some(v) if !v.is_empty() => Ok(Some(v.to_string())),
+            _ => Ok(None),
+        }
+    };
+
+    let firstname = name(&req_update_me.firstname)?;
+    let lastname = name(&req_update_me.lastname)?;
+
+    let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
+
+    let mut user: user::ActiveModel = user.into();
+    user.firstname = Set(firstname);
+    user.lastname = Set(lastname);
+    user.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    let user = user.update(db).await?;
+
+    Ok(SuccessResponse((Status::Ok, Json(ResMe::from(user)))))
+}
+
+const RESET_PASSWORD: &str = "reset-password";
+const RESET_TTL: u64 = 60 * 60;
+
+fn check_password(password: &str) -> Result<(), ErrorResponse> {
+    if password.chars().count() < 8 {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "password must be at least 8 characters".to_string(),
+            }),
+        )));
+    }
+
+    Ok(())
+}
+
+/// Stores the new password, bumps the session version and revokes every
+/// session, which signs the user out everywhere and invalidates outstanding
+/// reset tokens.
+async fn set_password(
+    db: &DatabaseConnection,
+    user: user::Model,
+    password: &str,
+) -> Result<user::Model, DbErr> {
+    let session_version = user.session_version + 1;
+
+    revoke_sessions(db, session::Column::UserId.eq(user.id)).await?;
+
+    let mut user: user::ActiveModel = user.into();
+    user.password = Set(hash(password, DEFAULT_COST).unwrap());
+    user.session_version = Set(session_version);
+    user.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    user.update(db).await
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqForgotPassword {
+    email: String,
+}
+
+/// Answers the same whether or not the account exists.
+#[post("/forgot-password", data = "<req_forgot_password>")]
+pub async fn forgot_password(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    mailer: &State<Box<dyn Mailer>>,
+    req_forgot_password: Json<ReqForgotPassword>,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let user = User::find()
+        .filter(user::Column::Email.eq(req_forgot_password.email.trim()))
+        .one(db)
+        .await?;
+
+    if let Some(user) = user {
+        let token = issue_action_token(config, &user, RESET_PASSWORD, RESET_TTL);
+
+        // A failed send is only logged; an error here would reveal that the
+        // address has an account.
+        if let Err(e) = mailer
+            .send(Email {
+                to: user.email.to_owned(),
+                subject: "Reset your password".to_string(),
+                body: format!(
+                    "Someone asked to reset the password of your bookstore account.\n\n\
+                     Use this token within an hour to choose a new password:\n\n\
+                     {}\n\n\
+                     If this wasn't you, you can ignore this email.\n",
+                    token
+                ),
+            })
+            .await
+        {
+            eprintln!("Could not send password reset email: {}", e.0);
+        }
+    }
+
     Ok(SuccessResponse((
         Status::Ok,
-        Json(ResMe {
-            id: user.id,
-            email: user.email,
-            firstname: user.firstname,
-            lastname: user.lastname,
+        Json(GenericResponse {
+            message: "If an account exists for that address, a reset email is on its way"
+                .to_string(),
+        }),
+    )))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqResetPassword {
+    token: String,
+    password: String,
+}
+
+#[post("/reset-password", data = "<req_reset_password>")]
+pub async fn reset_password(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    req_reset_password: Json<ReqResetPassword>,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let invalid = || {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "The reset token is invalid or has expired".to_string(),
+            }),
+        ))
+    };
+
+    let claims = decode_action_token(config, &req_reset_password.token, RESET_PASSWORD)
+        .ok_or_else(invalid)?;
+
+    let user = User::find_by_id(claims.sub as i32)
+        .one(db)
+        .await?
+        .filter(|u| u.email == claims.email && u.session_version == claims.ver)
+        .ok_or_else(invalid)?;
+
+    check_password(&req_reset_password.password)?;
+
+    set_password(db, user, &req_reset_password.password).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Password changed, please sign in again".to_string(),
         }),
     )))
 }
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqChangePassword {
+    current_password: String,
+    new_password: String,
+}
+
+/// Signs the user out of every other session and returns a token for a new
+/// session on this device.
+#[put("/password", data = "<req_change_password>")]
+pub async fn change_password(
+    db: &State<DatabaseConnection>,
+    sessions: SessionIssuer<'_>,
+    user: AuthenticatedUser,
+    req_change_password: Json<ReqChangePassword>,
+) -> Response<Json<ResSignIn>> {
+    let db = db as &DatabaseConnection;
+
+    let device_name = match user.session_id {
+        Some(id) => Session::find_by_id(id)
+            .one(db)
+            .await?
+            .and_then(|s| s.device_name),
+        None => None,
+    };
+
+    let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
+
+    if !verify(&req_change_password.current_password, &user.password).unwrap_or(false) {
+        return Err(ErrorResponse((
+            Status::Forbidden,
+            Json(GenericResponse {
+                message: "The current password is incorrect".to_string(),
+            }),
+        )));
+    }
+
+    check_password(&req_change_password.new_password)?;
+
+    let user = set_password(db, user, &req_change_password.new_password).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(sessions.start(&user, device_name.as_deref()).await?),
+    )))
+}
@@ -6,13 +6,13 @@ use rocket::{
     State,
 };
 use sea_orm::{
-    prelude::DateTimeUtc, ActiveModelTrait, DatabaseConnection, EntityTrait, ModelTrait,
-    QueryOrder, Set,
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
 };
 
 use crate::{
-    auth::AuthenticatedUser,
-    entities::{author, prelude::*},
+    auth::{AuthenticatedUser, CatalogueEditor},
+    entities::{author, prelude::*, promotion},
 };
 
 use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
@@ -79,7 +79,7 @@ pub async fn index(
 #[post("/", data = "<req_author>")]
 pub async fn create(
     db: &State<DatabaseConnection>,
-    user: AuthenticatedUser,
+    user: CatalogueEditor,
     req_author: Json<ReqAuthor>,
 ) -> Response<Json<ResAuthor>> {
     let db = db as &DatabaseConnection;
@@ -136,7 +136,7 @@ pub async fn show(
 #[put("/<id>", data = "<req_author>")]
 pub async fn update(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    _editor: CatalogueEditor,
     id: i32,
     req_author: Json<ReqAuthor>,
 ) -> Response<Json<ResAuthor>> {
@@ -172,7 +172,7 @@ pub async fn update(
 #[delete("/<id>")]
 pub async fn delete(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    _editor: CatalogueEditor,
     id: i32,
 ) -> Response<Json<GenericResponse>> {
     let db = db as &DatabaseConnection;
@@ -191,6 +191,20 @@ pub async fn delete(
         }
     };
 
+    if Promotion::find()
+        .filter(promotion::Column::AuthorId.eq(id))
+        .count(db)
+        .await?
+        > 0
+    {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Author is used by a promotion".to_string(),
+            }),
+        )));
+    }
+
     author.delete(db).await?;
 
     Ok(SuccessResponse((
@@ -6,25 +6,148 @@ use rocket::{
     State,
 };
 use sea_orm::{
-    prelude::DateTimeUtc, ActiveModelTrait, DatabaseConnection, EntityTrait, ModelTrait,
-    QueryOrder, Set,
+    prelude::{DateTimeUtc, Decimal},
+    sea_query::{Expr, Query},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    JoinType, LoaderTrait, ModelTrait, Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait, Set, TransactionTrait,
 };
 
 use crate::{
-    auth::AuthenticatedUser,
-    entities::{book, prelude::*},
+    auth::{AuthenticatedUser, CatalogueEditor},
+    dates::PublicationDate,
+    entities::{
+        book, book_genre, book_tag, genre, prelude::*, price, sea_orm_active_enums::BookFormat,
+        stock_movement, tag, work,
+    },
 };
 
-use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+use super::{
+    genre::subtree_ids,
+    price::{current_price_expr, normalize_currency, Amount, ResPrice},
+    review::ratings,
+    tag::find_or_create,
+    ErrorResponse, GenericResponse, Response, SuccessResponse,
+};
 
 #[derive(Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ResBook {
     id: i32,
     title: String,
-    year: String,
+    published: Option<PublicationDate>,
+    /// The old free-text year when it could not be read as a publication
+    /// date. Cleared the next time the book is updated.
+    legacy_year: Option<String>,
     cover: String,
     author_id: i32,
+    work_id: i32,
+    format: Option<BookFormat>,
+    isbn: Option<String>,
+    page_count: Option<i32>,
+    duration_minutes: Option<i32>,
+    language: Option<String>,
+    publisher_id: Option<i32>,
+    imprint_id: Option<i32>,
+    genre_ids: Vec<i32>,
+    tags: Vec<String>,
+    series: Option<ResBookSeries>,
+    prices: Vec<ResPrice>,
+    average_rating: Option<Decimal>,
+    review_count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResBookSeries {
+    id: i32,
+    position: Decimal,
+    previous: Option<ResBookRef>,
+    next: Option<ResBookRef>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct ResBookRef {
+    id: i32,
+    title: String,
+}
+
+impl ResBook {
+    /// Builds responses for `books` together with their related records.
+    pub async fn load(
+        db: &DatabaseConnection,
+        books: Vec<book::Model>,
+    ) -> Result<Vec<Self>, DbErr> {
+        let genres = books.load_many_to_many(Genre, BookGenre, db).await?;
+        let tags = books.load_many_to_many(Tag, BookTag, db).await?;
+        let prices = books.load_many(Price, db).await?;
+        let ratings = ratings(db, books.iter().map(|b| b.id).collect()).await?;
+
+        let mut series_ids = books.iter().filter_map(|b| b.series_id).collect::<Vec<_>>();
+        series_ids.sort_unstable();
+        series_ids.dedup();
+
+        let siblings = if series_ids.is_empty() {
+            vec![]
+        } else {
+            Book::find()
+                .filter(book::Column::SeriesId.is_in(series_ids))
+                .filter(book::Column::SeriesPosition.is_not_null())
+                .order_by_asc(book::Column::SeriesPosition)
+                .all(db)
+                .await?
+        };
+
+        let mut res = books.iter().map(Self::from).collect::<Vec<_>>();
+
+        for (i, (r, b)) in res.iter_mut().zip(&books).enumerate() {
+            r.genre_ids = genres[i].iter().map(|g| g.id).collect();
+            r.tags = tags[i].iter().map(|t| t.name.to_owned()).collect();
+            r.prices = prices[i].iter().map(ResPrice::from).collect();
+            r.series = ResBookSeries::find(b, &siblings);
+
+            if let Some(rating) = ratings.iter().find(|r| r.book_id == b.id) {
+                r.average_rating = Some(rating.average);
+                r.review_count = rating.count;
+            }
+        }
+
+        Ok(res)
+    }
+
+    pub async fn load_one(db: &DatabaseConnection, book: book::Model) -> Result<Self, DbErr> {
+        Ok(Self::load(db, vec![book]).await?.remove(0))
+    }
+}
+
+impl ResBookSeries {
+    /// Locates `book` among `siblings`, which must be ordered by position.
+    fn find(book: &book::Model, siblings: &[book::Model]) -> Option<Self> {
+        let series_id = book.series_id?;
+        let position = book.series_position?;
+
+        let in_series = siblings
+            .iter()
+            .filter(|s| s.series_id == Some(series_id))
+            .collect::<Vec<_>>();
+        let index = in_series.iter().position(|s| s.id == book.id)?;
+
+        let to_ref = |b: &&book::Model| ResBookRef {
+            id: b.id,
+            title: b.title.to_owned(),
+        };
+
+        Some(Self {
+            id: series_id,
+            position,
+            previous: index
+                .checked_sub(1)
+                .and_then(|i| in_series.get(i))
+                .map(to_ref),
+            next: in_series.get(index + 1).map(to_ref),
+        })
+    }
 }
 
 impl From<&book::Model> for ResBook {
@@ -32,9 +155,24 @@ impl From<&book::Model> for ResBook {
         Self {
             id: b.id,
             title: b.title.to_owned(),
-            year: b.year.to_owned(),
+            published: PublicationDate::from_columns(b.published_on, b.published_precision),
+            legacy_year: b.legacy_year.to_owned(),
             cover: b.cover.to_owned(),
             author_id: b.author_id,
+            work_id: b.work_id,
+            format: b.format,
+            isbn: b.isbn.to_owned(),
+            page_count: b.page_count,
+            duration_minutes: b.duration_minutes,
+            language: b.language.to_owned(),
+            publisher_id: b.publisher_id,
+            imprint_id: b.imprint_id,
+            genre_ids: vec![],
+            tags: vec![],
+            series: None,
+            prices: vec![],
+            average_rating: None,
+            review_count: 0,
         }
     }
 }
@@ -42,33 +180,455 @@ impl From<&book::Model> for ResBook {
 #[derive(Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ResBookList {
-    total: usize,
-    books: Vec<ResBook>,
+    pub total: usize,
+    pub books: Vec<ResBook>,
 }
 
 #[derive(Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ReqBook {
     author_id: i32,
+    work_id: Option<i32>,
+    format: Option<BookFormat>,
+    isbn: Option<String>,
+    page_count: Option<i32>,
+    duration_minutes: Option<i32>,
+    language: Option<String>,
+    publisher_id: Option<i32>,
+    imprint_id: Option<i32>,
+    series_id: Option<i32>,
+    series_position: Option<Decimal>,
     title: String,
-    year: String,
+    published: Option<PublicationDate>,
     cover: String,
+    #[serde(default)]
+    genre_ids: Vec<i32>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Strips separators from an ISBN-10 or ISBN-13, verifies its check digit
+/// and returns it as ISBN-13.
+fn normalize_isbn(value: &str) -> Option<String> {
+    let isbn = value
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .map(|c| c.to_ascii_uppercase())
+        .collect::<String>();
+
+    let digits = isbn
+        .chars()
+        .map(|c| match c {
+            'X' => Some(10),
+            c => c.to_digit(10),
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    match digits.len() {
+        10 => {
+            if digits[..9].contains(&10) {
+                return None;
+            }
+
+            let sum: u32 = digits
+                .iter()
+                .enumerate()
+                .map(|(i, d)| (10 - i as u32) * d)
+                .sum();
+
+            if !sum.is_multiple_of(11) {
+                return None;
+            }
+
+            let mut isbn13 = vec![9, 7, 8];
+            isbn13.extend(&digits[..9]);
+            let check = isbn13
+                .iter()
+                .enumerate()
+                .map(|(i, d)| if i % 2 == 0 { *d } else { d * 3 })
+                .sum::<u32>();
+            isbn13.push((10 - check % 10) % 10);
+
+            Some(isbn13.iter().map(|d| d.to_string()).collect())
+        }
+        13 => {
+            if digits.contains(&10) {
+                return None;
+            }
+
+            let sum: u32 = digits
+                .iter()
+                .enumerate()
+                .map(|(i, d)| if i % 2 == 0 { *d } else { d * 3 })
+                .sum();
+
+            sum.is_multiple_of(10).then_some(isbn)
+        }
+        _ => None,
+    }
+}
+
+impl ReqBook {
+    async fn check_work(&self, db: &DatabaseConnection) -> Result<(), ErrorResponse> {
+        if let Some(work_id) = self.work_id {
+            if Work::find_by_id(work_id).one(db).await?.is_none() {
+                return Err(ErrorResponse((
+                    Status::UnprocessableEntity,
+                    Json(GenericResponse {
+                        message: "Cannot find work with specified ID".to_string(),
+                    }),
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates the edition details, returning the normalized ISBN.
+    async fn check_edition(
+        &self,
+        db: &DatabaseConnection,
+        book_id: Option<i32>,
+    ) -> Result<Option<String>, ErrorResponse> {
+        let unprocessable = |message: &str| {
+            ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: message.to_string(),
+                }),
+            ))
+        };
+
+        if self.page_count.is_some_and(|p| p <= 0) {
+            return Err(unprocessable("page_count must be positive"));
+        }
+
+        if self.duration_minutes.is_some_and(|d| d <= 0) {
+            return Err(unprocessable("duration_minutes must be positive"));
+        }
+
+        if self.duration_minutes.is_some() && self.format != Some(BookFormat::Audiobook) {
+            return Err(unprocessable("Only audiobooks have a duration"));
+        }
+
+        let isbn = match &self.isbn {
+            Some(i) => match normalize_isbn(i) {
+                Some(i) => i,
+                None => return Err(unprocessable("Invalid ISBN")),
+            },
+            None => return Ok(None),
+        };
+
+        let mut taken = Book::find().filter(book::Column::Isbn.eq(&isbn));
+
+        if let Some(id) = book_id {
+            taken = taken.filter(book::Column::Id.ne(id));
+        }
+
+        if taken.one(db).await?.is_some() {
+            return Err(unprocessable("A book exists with that ISBN"));
+        }
+
+        Ok(Some(isbn))
+    }
+
+    /// Validates the series placement. `book_id` is the book being updated,
+    /// which may keep its own position.
+    async fn check_series(
+        &self,
+        db: &DatabaseConnection,
+        book_id: Option<i32>,
+    ) -> Result<(), ErrorResponse> {
+        let unprocessable = |message: &str| {
+            ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: message.to_string(),
+                }),
+            ))
+        };
+
+        let (series_id, position) = match (self.series_id, self.series_position) {
+            (None, None) => return Ok(()),
+            (Some(s), Some(p)) => (s, p),
+            _ => {
+                return Err(unprocessable(
+                    "series_id and series_position must be given together",
+                ))
+            }
+        };
+
+        if position.is_sign_negative() {
+            return Err(unprocessable("series_position cannot be negative"));
+        }
+
+        if Series::find_by_id(series_id).one(db).await?.is_none() {
+            return Err(unprocessable("Cannot find series with specified ID"));
+        }
+
+        let mut taken = Book::find()
+            .filter(book::Column::SeriesId.eq(series_id))
+            .filter(book::Column::SeriesPosition.eq(position));
+
+        if let Some(id) = book_id {
+            taken = taken.filter(book::Column::Id.ne(id));
+        }
+
+        if taken.one(db).await?.is_some() {
+            return Err(unprocessable(
+                "Another book already has that position in the series",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validates the publisher and imprint, returning the publisher the book
+    /// belongs to. An imprint implies its publisher.
+    async fn check_publisher(&self, db: &DatabaseConnection) -> Result<Option<i32>, ErrorResponse> {
+        let unprocessable = |message: &str| {
+            ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: message.to_string(),
+                }),
+            ))
+        };
+
+        if let Some(imprint_id) = self.imprint_id {
+            let imprint = match Imprint::find_by_id(imprint_id).one(db).await? {
+                Some(i) => i,
+                None => return Err(unprocessable("Cannot find imprint with specified ID")),
+            };
+
+            if self.publisher_id.is_some_and(|p| p != imprint.publisher_id) {
+                return Err(unprocessable("Imprint does not belong to the publisher"));
+            }
+
+            return Ok(Some(imprint.publisher_id));
+        }
+
+        if let Some(publisher_id) = self.publisher_id {
+            if Publisher::find_by_id(publisher_id).one(db).await?.is_none() {
+                return Err(unprocessable("Cannot find publisher with specified ID"));
+            }
+        }
+
+        Ok(self.publisher_id)
+    }
+
+    async fn check_genres(&self, db: &DatabaseConnection) -> Result<(), ErrorResponse> {
+        let found = Genre::find()
+            .filter(genre::Column::Id.is_in(self.genre_ids.clone()))
+            .count(db)
+            .await?;
+
+        let mut ids = self.genre_ids.clone();
+        ids.sort_unstable();
+        ids.dedup();
+
+        if found as usize != ids.len() {
+            return Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: "Cannot find genre with specified ID".to_string(),
+                }),
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the genres and tags of the book with the requested ones.
+    async fn save_taxonomy<C: ConnectionTrait>(&self, db: &C, book_id: i32) -> Result<(), DbErr> {
+        BookGenre::delete_many()
+            .filter(book_genre::Column::BookId.eq(book_id))
+            .exec(db)
+            .await?;
+        BookTag::delete_many()
+            .filter(book_tag::Column::BookId.eq(book_id))
+            .exec(db)
+            .await?;
+
+        let mut genre_ids = self.genre_ids.clone();
+        genre_ids.sort_unstable();
+        genre_ids.dedup();
+
+        for genre_id in genre_ids {
+            book_genre::ActiveModel {
+                book_id: Set(book_id),
+                genre_id: Set(genre_id),
+            }
+            .insert(db)
+            .await?;
+        }
+
+        let mut tag_ids = vec![];
+        for name in self.tags.iter().filter(|t| !t.trim().is_empty()) {
+            tag_ids.push(find_or_create(db, name).await?.id);
+        }
+        tag_ids.sort_unstable();
+        tag_ids.dedup();
+
+        for tag_id in tag_ids {
+            book_tag::ActiveModel {
+                book_id: Set(book_id),
+                tag_id: Set(tag_id),
+            }
+            .insert(db)
+            .await?;
+        }
+
+        Ok(())
+    }
 }
 
-#[get("/")]
+#[derive(FromFormField, Default)]
+pub enum BookSort {
+    #[default]
+    Updated,
+    Published,
+    Title,
+    Price,
+    #[field(value = "price_desc")]
+    PriceDesc,
+}
+
+#[derive(FromForm)]
+pub struct BookFilter {
+    published_from: Option<PublicationDate>,
+    published_to: Option<PublicationDate>,
+    work: Option<i32>,
+    format: Option<BookFormat>,
+    language: Option<String>,
+    isbn: Option<String>,
+    publisher: Option<i32>,
+    imprint: Option<i32>,
+    series: Option<i32>,
+    genre: Option<i32>,
+    tag: Option<String>,
+    currency: Option<String>,
+    min_price: Option<Amount>,
+    max_price: Option<Amount>,
+    sort: Option<BookSort>,
+}
+
+#[get("/?<filter..>")]
 pub async fn index(
     db: &State<DatabaseConnection>,
     _user: AuthenticatedUser,
+    filter: BookFilter,
 ) -> Response<Json<ResBookList>> {
     let db = db as &DatabaseConnection;
 
-    let books = Book::find()
-        .order_by_desc(book::Column::UpdatedAt)
-        .all(db)
-        .await?
-        .iter()
-        .map(ResBook::from)
-        .collect::<Vec<_>>();
+    let mut query = Book::find();
+
+    if let Some(from) = filter.published_from {
+        query = query.filter(book::Column::PublishedOn.gte(from.first_day()));
+    }
+
+    if let Some(to) = filter.published_to {
+        query = query.filter(book::Column::PublishedOn.lte(to.last_day()));
+    }
+
+    if let Some(work_id) = filter.work {
+        query = query.filter(book::Column::WorkId.eq(work_id));
+    }
+
+    if let Some(format) = filter.format {
+        query = query.filter(book::Column::Format.eq(format));
+    }
+
+    if let Some(language) = &filter.language {
+        query = query.filter(book::Column::Language.eq(language));
+    }
+
+    if let Some(isbn) = &filter.isbn {
+        query = query.filter(book::Column::Isbn.eq(normalize_isbn(isbn)));
+    }
+
+    if let Some(publisher_id) = filter.publisher {
+        query = query.filter(book::Column::PublisherId.eq(publisher_id));
+    }
+
+    if let Some(imprint_id) = filter.imprint {
+        query = query.filter(book::Column::ImprintId.eq(imprint_id));
+    }
+
+    if let Some(series_id) = filter.series {
+        query = query.filter(book::Column::SeriesId.eq(series_id));
+    }
+
+    if let Some(genre_id) = filter.genre {
+        query = query.filter(
+            book::Column::Id.in_subquery(
+                Query::select()
+                    .column(book_genre::Column::BookId)
+                    .from(BookGenre)
+                    .and_where(book_genre::Column::GenreId.is_in(subtree_ids(db, genre_id).await?))
+                    .to_owned(),
+            ),
+        );
+    }
+
+    if let Some(name) = &filter.tag {
+        query = query.filter(
+            book::Column::Id.in_subquery(
+                Query::select()
+                    .column(book_tag::Column::BookId)
+                    .from(BookTag)
+                    .inner_join(
+                        Tag,
+                        Expr::col((Tag, tag::Column::Id))
+                            .equals((BookTag, book_tag::Column::TagId)),
+                    )
+                    .and_where(tag::Column::Name.eq(super::tag::normalize(name)))
+                    .to_owned(),
+            ),
+        );
+    }
+
+    let sort = filter.sort.unwrap_or_default();
+    let by_price = matches!(sort, BookSort::Price | BookSort::PriceDesc)
+        || filter.min_price.is_some()
+        || filter.max_price.is_some();
+
+    let current_price = current_price_expr(DateTimeUtc::from(SystemTime::now()));
+
+    match &filter.currency {
+        Some(currency) => {
+            query = query
+                .join(JoinType::InnerJoin, book::Relation::Price.def())
+                .filter(price::Column::Currency.eq(normalize_currency(currency)));
+
+            if let Some(min) = &filter.min_price {
+                query = query.filter(Expr::expr(current_price.clone()).gte(min.0));
+            }
+
+            if let Some(max) = &filter.max_price {
+                query = query.filter(Expr::expr(current_price.clone()).lte(max.0));
+            }
+        }
+        None if by_price => {
+            return Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: "Filtering or sorting by price requires a currency".to_string(),
+                }),
+            )));
+        }
+        None => {}
+    }
+
+    query = match sort {
+        BookSort::Price => query.order_by(current_price, Order::Asc),
+        BookSort::PriceDesc => query.order_by(current_price, Order::Desc),
+        BookSort::Updated => query.order_by_desc(book::Column::UpdatedAt),
+        BookSort::Published => query.order_by_desc(book::Column::PublishedOn),
+        BookSort::Title => query.order_by_asc(book::Column::Title),
+    };
+
+    let books = ResBook::load(db, query.all(db).await?).await?;
 
     Ok(SuccessResponse((
         Status::Ok,
@@ -82,25 +642,63 @@ pub async fn index(
 #[post("/", data = "<req_book>")]
 pub async fn create(
     db: &State<DatabaseConnection>,
-    user: AuthenticatedUser,
+    user: CatalogueEditor,
     req_book: Json<ReqBook>,
 ) -> Response<Json<ResBook>> {
     let db = db as &DatabaseConnection;
 
+    req_book.check_work(db).await?;
+    let isbn = req_book.check_edition(db, None).await?;
+    let publisher_id = req_book.check_publisher(db).await?;
+    req_book.check_series(db, None).await?;
+    req_book.check_genres(db).await?;
+
+    let txn = db.begin().await?;
+
+    // A book created without a work starts a new work of its own.
+    let work_id = match req_book.work_id {
+        Some(w) => w,
+        None => {
+            work::ActiveModel {
+                user_id: Set(user.id as i32),
+                author_id: Set(req_book.author_id),
+                title: Set(req_book.title.to_owned()),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?
+            .id
+        }
+    };
+
     let book = book::ActiveModel {
         user_id: Set(user.id as i32),
         author_id: Set(req_book.author_id),
+        work_id: Set(work_id),
+        format: Set(req_book.format),
+        isbn: Set(isbn),
+        page_count: Set(req_book.page_count),
+        duration_minutes: Set(req_book.duration_minutes),
+        language: Set(req_book.language.to_owned()),
+        publisher_id: Set(publisher_id),
+        imprint_id: Set(req_book.imprint_id),
+        series_id: Set(req_book.series_id),
+        series_position: Set(req_book.series_position),
         title: Set(req_book.title.to_owned()),
-        year: Set(req_book.year.to_owned()),
+        published_on: Set(req_book.published.map(|p| p.first_day())),
+        published_precision: Set(req_book.published.map(|p| p.precision())),
         cover: Set(req_book.cover.to_owned()),
         ..Default::default()
     };
 
-    let book = book.insert(db).await?;
+    let book = book.insert(&txn).await?;
+    req_book.save_taxonomy(&txn, book.id).await?;
+
+    txn.commit().await?;
 
     Ok(SuccessResponse((
         Status::Created,
-        Json(ResBook::from(&book)),
+        Json(ResBook::load_one(db, book).await?),
     )))
 }
 
@@ -126,13 +724,16 @@ pub async fn show(
         }
     };
 
-    Ok(SuccessResponse((Status::Ok, Json(ResBook::from(&book)))))
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResBook::load_one(db, book).await?),
+    )))
 }
 
 #[put("/<id>", data = "<req_book>")]
 pub async fn update(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    _editor: CatalogueEditor,
     id: i32,
     req_book: Json<ReqBook>,
 ) -> Response<Json<ResBook>> {
@@ -152,22 +753,51 @@ pub async fn update(
         }
     };
 
-    book.author_id = Set(req_book.author_id.to_owned());
+    req_book.check_work(db).await?;
+    let isbn = req_book.check_edition(db, Some(id)).await?;
+    let publisher_id = req_book.check_publisher(db).await?;
+    req_book.check_series(db, Some(id)).await?;
+
+    book.author_id = Set(req_book.author_id);
+    if let Some(work_id) = req_book.work_id {
+        book.work_id = Set(work_id);
+    }
+    book.format = Set(req_book.format);
+    book.isbn = Set(isbn);
+    book.page_count = Set(req_book.page_count);
+    book.duration_minutes = Set(req_book.duration_minutes);
+    book.language = Set(req_book.language.to_owned());
+    book.publisher_id = Set(publisher_id);
+    book.imprint_id = Set(req_book.imprint_id);
+    book.series_id = Set(req_book.series_id);
+    book.series_position = Set(req_book.series_position);
     book.title = Set(req_book.title.to_owned());
-    book.year = Set(req_book.year.to_owned());
+    book.published_on = Set(req_book.published.map(|p| p.first_day()));
+    book.published_precision = Set(req_book.published.map(|p| p.precision()));
+    book.legacy_year = Set(None);
     book.cover = Set(req_book.cover.to_owned());
 
     book.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
 
-    let book = book.update(db).await?;
+    req_book.check_genres(db).await?;
+
+    let txn = db.begin().await?;
 
-    Ok(SuccessResponse((Status::Ok, Json(ResBook::from(&book)))))
+    let book = book.update(&txn).await?;
+    req_book.save_taxonomy(&txn, book.id).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResBook::load_one(db, book).await?),
+    )))
 }
 
 #[delete("/<id>")]
 pub async fn delete(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    _editor: CatalogueEditor,
     id: i32,
 ) -> Response<Json<GenericResponse>> {
     let db = db as &DatabaseConnection;
@@ -186,6 +816,21 @@ pub async fn delete(
         }
     };
 
+    // The stock ledger is kept for good, so stocked books stay.
+    if StockMovement::find()
+        .filter(stock_movement::Column::BookId.eq(book.id))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        return Err(ErrorResponse((
+            Status::Conflict,
+            Json(GenericResponse {
+                message: "Book has stock movements and cannot be deleted".to_string(),
+            }),
+        )));
+    }
+
     book.delete(db).await?;
 
     Ok(SuccessResponse((
@@ -195,3 +840,53 @@ pub async fn delete(
         }),
     )))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isbn13_is_kept() {
+        assert_eq!(
+            normalize_isbn("978-3-16-148410-0"),
+            Some("9783161484100".to_string())
+        );
+        assert_eq!(
+            normalize_isbn("978 0 306 40615 7"),
+            Some("9780306406157".to_string())
+        );
+    }
+
+    #[test]
+    fn isbn10_is_converted_to_isbn13() {
+        assert_eq!(
+            normalize_isbn("0-306-40615-2"),
+            Some("9780306406157".to_string())
+        );
+        assert_eq!(
+            normalize_isbn("0-8044-2957-x"),
+            Some("9780804429573".to_string())
+        );
+    }
+
+    #[test]
+    fn wrong_check_digits_are_rejected() {
+        assert_eq!(normalize_isbn("978-3-16-148410-1"), None);
+        assert_eq!(normalize_isbn("0-306-40615-3"), None);
+    }
+
+    #[test]
+    fn malformed_isbns_are_rejected() {
+        for value in [
+            "",
+            "030640615",
+            "03064061522",
+            "X306406152",
+            "978316148410X",
+            "978-3-16-14841O-0",
+            "0.306.40615.2",
+        ] {
+            assert_eq!(normalize_isbn(value), None, "{:?}", value);
+        }
+    }
+}
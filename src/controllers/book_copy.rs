@@ -0,0 +1,150 @@
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+
+use crate::{
+    auth::{AdminUser, AuthenticatedUser},
+    entities::{book_copy, prelude::*, sea_orm_active_enums::CopyStatus},
+};
+
+use super::{hold::shelve, ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResBookCopy {
+    id: i32,
+    book_id: i32,
+    location_id: Option<i32>,
+    barcode: String,
+    status: CopyStatus,
+}
+
+impl From<&book_copy::Model> for ResBookCopy {
+    fn from(c: &book_copy::Model) -> Self {
+        Self {
+            id: c.id,
+            book_id: c.book_id,
+            location_id: c.location_id,
+            barcode: c.barcode.to_owned(),
+            status: c.status,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResBookCopyList {
+    total: usize,
+    copies: Vec<ResBookCopy>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqBookCopy {
+    barcode: String,
+    location_id: Option<i32>,
+}
+
+#[get("/<id>/copies")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResBookCopyList>> {
+    let db = db as &DatabaseConnection;
+
+    let copies = BookCopy::find()
+        .filter(book_copy::Column::BookId.eq(id))
+        .order_by_asc(book_copy::Column::Barcode)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResBookCopy::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResBookCopyList {
+            total: copies.len(),
+            copies,
+        }),
+    )))
+}
+
+/// Adds a physical copy to the lending library. It goes straight to the
+/// first waiting hold, if any.
+#[post("/<id>/copies", data = "<req_copy>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+    req_copy: Json<ReqBookCopy>,
+) -> Response<Json<ResBookCopy>> {
+    let db = db as &DatabaseConnection;
+
+    let unprocessable = |message: &str| {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: message.to_string(),
+            }),
+        ))
+    };
+
+    if Book::find_by_id(id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find a book with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    let barcode = req_copy.barcode.trim();
+
+    if barcode.is_empty() || barcode.len() > 32 {
+        return Err(unprocessable("barcode must be between 1 and 32 characters"));
+    }
+
+    if BookCopy::find()
+        .filter(book_copy::Column::Barcode.eq(barcode))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        return Err(unprocessable("A copy with that barcode already exists"));
+    }
+
+    if let Some(location_id) = req_copy.location_id {
+        if Location::find_by_id(location_id).one(db).await?.is_none() {
+            return Err(unprocessable("Cannot find location with specified ID"));
+        }
+    }
+
+    let txn = db.begin().await?;
+
+    let copy = book_copy::ActiveModel {
+        book_id: Set(id),
+        location_id: Set(req_copy.location_id),
+        barcode: Set(barcode.to_string()),
+        status: Set(CopyStatus::Available),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    let copy = shelve(&txn, copy).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResBookCopy::from(&copy)),
+    )))
+}
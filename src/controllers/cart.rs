@@ -0,0 +1,711 @@
+use std::time::SystemTime;
+
+use rand::{distributions::Alphanumeric, Rng};
+use rocket::{
+    http::Status,
+    request::{self, FromRequest, Outcome, Request},
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Decimal},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    ModelTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+
+use crate::{
+    auth::{AuthenticatedUser, MaybeUser},
+    entities::{
+        book, cart, cart_item, order, prelude::*, price, promotion,
+        sea_orm_active_enums::OrderStatus,
+    },
+    promotions,
+};
+
+use super::{
+    price::{current_price, normalize_currency},
+    promotion::normalize_code,
+    stock, ErrorResponse, GenericResponse, Response, SuccessResponse,
+};
+
+/// The `cart-token` header a guest uses to identify their cart.
+pub struct CartToken(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CartToken {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(CartToken(
+            req.headers().get_one("cart-token").map(str::to_string),
+        ))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResCartItem {
+    book_id: i32,
+    title: String,
+    quantity: i32,
+    unit_price: Decimal,
+    line_total: Decimal,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResCart {
+    id: i32,
+    currency: String,
+    guest_token: Option<String>,
+    coupon_code: Option<String>,
+    item_count: i32,
+    subtotal: Decimal,
+    discount: Decimal,
+    total: Decimal,
+    items: Vec<ResCartItem>,
+    promotions: Vec<promotions::Applied>,
+    notes: Vec<String>,
+}
+
+impl ResCart {
+    async fn load(db: &DatabaseConnection, cart: cart::Model) -> Result<Self, DbErr> {
+        let items = cart
+            .find_related(CartItem)
+            .find_also_related(Book)
+            .order_by_asc(cart_item::Column::Id)
+            .all(db)
+            .await?;
+
+        let lines = promotions::lines(db, &items).await?;
+        let evaluation = promotions::evaluate(
+            db,
+            &lines,
+            &cart.currency,
+            cart.user_id,
+            cart.coupon_code.as_deref(),
+            false,
+        )
+        .await?;
+
+        let items = items
+            .into_iter()
+            .map(|(i, b)| ResCartItem {
+                book_id: i.book_id,
+                title: b.map(|b| b.title).unwrap_or_default(),
+                quantity: i.quantity,
+                unit_price: i.unit_price,
+                line_total: i.unit_price * Decimal::from(i.quantity),
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            id: cart.id,
+            currency: cart.currency,
+            guest_token: cart.guest_token,
+            coupon_code: cart.coupon_code,
+            item_count: items
+                .iter()
+                .fold(0i32, |count, i| count.saturating_add(i.quantity)),
+            subtotal: evaluation.subtotal,
+            discount: evaluation.discount,
+            total: evaluation.total,
+            items,
+            promotions: evaluation.applied,
+            notes: evaluation.notes,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqCartItem {
+    book_id: i32,
+    quantity: i32,
+    /// Required when the item starts a new cart.
+    currency: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqCartQuantity {
+    quantity: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqCoupon {
+    code: String,
+}
+
+fn unprocessable(message: &str) -> ErrorResponse {
+    ErrorResponse((
+        Status::UnprocessableEntity,
+        Json(GenericResponse {
+            message: message.to_string(),
+        }),
+    ))
+}
+
+/// The most copies of one book a cart may hold.
+const MAX_QUANTITY: i32 = 99;
+
+fn check_quantity(quantity: i32) -> Result<(), ErrorResponse> {
+    if !(1..=MAX_QUANTITY).contains(&quantity) {
+        return Err(unprocessable(&format!(
+            "quantity must be between 1 and {}",
+            MAX_QUANTITY
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks that enough copies are in stock. Digital editions are never out
+/// of stock, so on purpose only [`MAX_QUANTITY`] limits them.
+async fn check_stock<C: ConnectionTrait>(
+    db: &C,
+    book: &book::Model,
+    quantity: i32,
+) -> Result<(), ErrorResponse> {
+    if !stock::is_stocked(book) {
+        return Ok(());
+    }
+
+    let available = stock::available(db, book.id).await?;
+
+    if quantity > available {
+        return Err(unprocessable(&format!(
+            "Only {} copies of \"{}\" are available",
+            available, book.title
+        )));
+    }
+
+    Ok(())
+}
+
+/// The price of a book in `currency` right now.
+pub async fn snapshot_price<C: ConnectionTrait>(
+    db: &C,
+    book_id: i32,
+    currency: &str,
+) -> Result<Option<Decimal>, DbErr> {
+    let price = Price::find()
+        .filter(price::Column::BookId.eq(book_id))
+        .filter(price::Column::Currency.eq(currency))
+        .one(db)
+        .await?;
+
+    Ok(price.map(|p| current_price(&p, DateTimeUtc::from(SystemTime::now()))))
+}
+
+/// The cart of the signed-in user, or the guest cart named by `token`.
+pub async fn find_cart<C: ConnectionTrait>(
+    db: &C,
+    user: &Option<AuthenticatedUser>,
+    token: &CartToken,
+) -> Result<Option<cart::Model>, DbErr> {
+    match (user, &token.0) {
+        (Some(u), _) => {
+            Cart::find()
+                .filter(cart::Column::UserId.eq(u.id as i32))
+                .one(db)
+                .await
+        }
+        (None, Some(t)) => {
+            Cart::find()
+                .filter(cart::Column::UserId.is_null())
+                .filter(cart::Column::GuestToken.eq(t))
+                .one(db)
+                .await
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+async fn find_existing_cart<C: ConnectionTrait>(
+    db: &C,
+    user: &Option<AuthenticatedUser>,
+    token: &CartToken,
+) -> Result<cart::Model, ErrorResponse> {
+    match find_cart(db, user, token).await? {
+        Some(c) => Ok(c),
+        None => Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find a cart".to_string(),
+            }),
+        ))),
+    }
+}
+
+async fn find_item<C: ConnectionTrait>(
+    db: &C,
+    cart_id: i32,
+    book_id: i32,
+) -> Result<cart_item::Model, ErrorResponse> {
+    let item = CartItem::find()
+        .filter(cart_item::Column::CartId.eq(cart_id))
+        .filter(cart_item::Column::BookId.eq(book_id))
+        .one(db)
+        .await?;
+
+    match item {
+        Some(i) => Ok(i),
+        None => Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "That book is not in the cart".to_string(),
+            }),
+        ))),
+    }
+}
+
+/// Whether `user_id` has an order waiting for its payment. The cart is only
+/// emptied once the payment goes through, so until then it must not change.
+async fn checkout_pending<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<bool, DbErr> {
+    Ok(Order::find()
+        .filter(order::Column::UserId.eq(user_id))
+        .filter(order::Column::Status.eq(OrderStatus::Pending))
+        .one(db)
+        .await?
+        .is_some())
+}
+
+/// Locks `cart` for a change until the transaction ends, so that changes
+/// and checkout of the same cart take turns.
+async fn lock_for_change<C: ConnectionTrait>(
+    db: &C,
+    cart: &cart::Model,
+) -> Result<(), ErrorResponse> {
+    Cart::find_by_id(cart.id).lock_exclusive().one(db).await?;
+
+    if let Some(user_id) = cart.user_id {
+        if checkout_pending(db, user_id).await? {
+            return Err(ErrorResponse((
+                Status::Conflict,
+                Json(GenericResponse {
+                    message: "The cart is being checked out; cancel its pending order to change it"
+                        .to_string(),
+                }),
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+async fn touch<C: ConnectionTrait>(db: &C, cart: cart::Model) -> Result<(), DbErr> {
+    let mut cart: cart::ActiveModel = cart.into();
+    cart.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    cart.update(db).await?;
+
+    Ok(())
+}
+
+/// Moves the items of the guest cart named by `token` into the user's cart.
+///
+/// Quantities of books already in the user's cart are added together and keep
+/// the user's price. Books without a price in the user's currency are dropped.
+pub async fn merge_guest_cart(
+    db: &DatabaseConnection,
+    user_id: i32,
+    token: &CartToken,
+) -> Result<(), DbErr> {
+    let guest = match &token.0 {
+        Some(t) => {
+            Cart::find()
+                .filter(cart::Column::UserId.is_null())
+                .filter(cart::Column::GuestToken.eq(t))
+                .one(db)
+                .await?
+        }
+        None => None,
+    };
+
+    let guest = match guest {
+        Some(g) => g,
+        None => return Ok(()),
+    };
+
+    let txn = db.begin().await?;
+
+    let existing = Cart::find()
+        .filter(cart::Column::UserId.eq(user_id))
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+
+    // The guest cart is left alone while the user's own cart is being paid
+    // for, and can be merged at the next sign-in.
+    if existing.is_some() && checkout_pending(&txn, user_id).await? {
+        return txn.commit().await;
+    }
+
+    let cart = match existing {
+        Some(c) => c,
+        None => {
+            // Nothing to merge with, the guest cart simply changes hands.
+            let mut guest: cart::ActiveModel = guest.into();
+            guest.user_id = Set(Some(user_id));
+            guest.guest_token = Set(None);
+            guest.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+            guest.update(&txn).await?;
+
+            return txn.commit().await;
+        }
+    };
+
+    let items = cart.find_related(CartItem).all(&txn).await?;
+
+    for guest_item in guest.find_related(CartItem).all(&txn).await? {
+        match items.iter().find(|i| i.book_id == guest_item.book_id) {
+            Some(item) => {
+                let mut item: cart_item::ActiveModel = item.to_owned().into();
+                // Signing in should not fail over the cart, so merged
+                // quantities are capped rather than refused.
+                item.quantity = Set(item
+                    .quantity
+                    .unwrap()
+                    .saturating_add(guest_item.quantity)
+                    .min(MAX_QUANTITY));
+                item.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+                item.update(&txn).await?;
+            }
+            None => {
+                let unit_price = if guest.currency == cart.currency {
+                    Some(guest_item.unit_price)
+                } else {
+                    snapshot_price(&txn, guest_item.book_id, &cart.currency).await?
+                };
+
+                if let Some(unit_price) = unit_price {
+                    cart_item::ActiveModel {
+                        cart_id: Set(cart.id),
+                        book_id: Set(guest_item.book_id),
+                        quantity: Set(guest_item.quantity),
+                        unit_price: Set(unit_price),
+                        ..Default::default()
+                    }
+                    .insert(&txn)
+                    .await?;
+                }
+            }
+        }
+    }
+
+    let coupon_code = cart.coupon_code.to_owned().or(guest.coupon_code.to_owned());
+    guest.delete(&txn).await?;
+
+    let mut cart: cart::ActiveModel = cart.into();
+    cart.coupon_code = Set(coupon_code);
+    cart.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    cart.update(&txn).await?;
+
+    txn.commit().await
+}
+
+#[get("/")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    user: MaybeUser,
+    token: CartToken,
+) -> Response<Json<ResCart>> {
+    let db = db as &DatabaseConnection;
+
+    let cart = find_existing_cart(db, &user.0, &token).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResCart::load(db, cart).await?),
+    )))
+}
+
+#[post("/items", data = "<req_item>")]
+pub async fn add(
+    db: &State<DatabaseConnection>,
+    user: MaybeUser,
+    token: CartToken,
+    req_item: Json<ReqCartItem>,
+) -> Response<Json<ResCart>> {
+    let db = db as &DatabaseConnection;
+
+    check_quantity(req_item.quantity)?;
+
+    let currency = match &req_item.currency {
+        Some(c) => Some(
+            normalize_currency(c)
+                .ok_or_else(|| unprocessable("Currency must be a 3-letter ISO 4217 code"))?,
+        ),
+        None => None,
+    };
+
+    let book = match Book::find_by_id(req_item.book_id).one(db).await? {
+        Some(b) => b,
+        None => return Err(unprocessable("Cannot find book with specified ID")),
+    };
+
+    let user = user.0;
+
+    let txn = db.begin().await?;
+
+    let cart = match find_cart(&txn, &user, &token).await? {
+        Some(c) => {
+            lock_for_change(&txn, &c).await?;
+
+            if currency.as_ref().is_some_and(|c2| *c2 != c.currency) {
+                return Err(unprocessable(&format!(
+                    "The cart is priced in {}",
+                    c.currency
+                )));
+            }
+
+            c
+        }
+        None => {
+            let currency = match currency {
+                Some(c) => c,
+                None => return Err(unprocessable("A currency is required to start a cart")),
+            };
+
+            let guest_token = match user {
+                Some(_) => None,
+                None => Some(
+                    rand::thread_rng()
+                        .sample_iter(&Alphanumeric)
+                        .take(32)
+                        .map(char::from)
+                        .collect::<String>(),
+                ),
+            };
+
+            cart::ActiveModel {
+                user_id: Set(user.as_ref().map(|u| u.id as i32)),
+                guest_token: Set(guest_token),
+                currency: Set(currency),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?
+        }
+    };
+
+    let existing = CartItem::find()
+        .filter(cart_item::Column::CartId.eq(cart.id))
+        .filter(cart_item::Column::BookId.eq(book.id))
+        .one(&txn)
+        .await?;
+
+    match existing {
+        // Adding more of a book keeps the price it was first added at.
+        Some(item) => {
+            let quantity = item
+                .quantity
+                .checked_add(req_item.quantity)
+                .filter(|q| *q <= MAX_QUANTITY)
+                .ok_or_else(|| {
+                    unprocessable(&format!(
+                        "A cart may hold at most {} copies of a book",
+                        MAX_QUANTITY
+                    ))
+                })?;
+            check_stock(&txn, &book, quantity).await?;
+
+            let mut item: cart_item::ActiveModel = item.into();
+            item.quantity = Set(quantity);
+            item.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+            item.update(&txn).await?;
+        }
+        None => {
+            check_stock(&txn, &book, req_item.quantity).await?;
+
+            let unit_price = match snapshot_price(&txn, book.id, &cart.currency).await? {
+                Some(p) => p,
+                None => {
+                    return Err(unprocessable(&format!(
+                        "\"{}\" is not sold in {}",
+                        book.title, cart.currency
+                    )))
+                }
+            };
+
+            cart_item::ActiveModel {
+                cart_id: Set(cart.id),
+                book_id: Set(book.id),
+                quantity: Set(req_item.quantity),
+                unit_price: Set(unit_price),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+        }
+    }
+
+    touch(&txn, cart.to_owned()).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResCart::load(db, cart).await?),
+    )))
+}
+
+#[put("/items/<book_id>", data = "<req_quantity>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    user: MaybeUser,
+    token: CartToken,
+    book_id: i32,
+    req_quantity: Json<ReqCartQuantity>,
+) -> Response<Json<ResCart>> {
+    let db = db as &DatabaseConnection;
+
+    check_quantity(req_quantity.quantity)?;
+
+    let txn = db.begin().await?;
+
+    let cart = find_existing_cart(&txn, &user.0, &token).await?;
+    lock_for_change(&txn, &cart).await?;
+    let item = find_item(&txn, cart.id, book_id).await?;
+
+    if let Some(book) = Book::find_by_id(book_id).one(&txn).await? {
+        check_stock(&txn, &book, req_quantity.quantity).await?;
+    }
+
+    let mut item: cart_item::ActiveModel = item.into();
+    item.quantity = Set(req_quantity.quantity);
+    item.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    item.update(&txn).await?;
+
+    touch(&txn, cart.to_owned()).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResCart::load(db, cart).await?),
+    )))
+}
+
+#[delete("/items/<book_id>")]
+pub async fn remove(
+    db: &State<DatabaseConnection>,
+    user: MaybeUser,
+    token: CartToken,
+    book_id: i32,
+) -> Response<Json<ResCart>> {
+    let db = db as &DatabaseConnection;
+
+    let txn = db.begin().await?;
+
+    let cart = find_existing_cart(&txn, &user.0, &token).await?;
+    lock_for_change(&txn, &cart).await?;
+    let item = find_item(&txn, cart.id, book_id).await?;
+
+    item.delete(&txn).await?;
+    touch(&txn, cart.to_owned()).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResCart::load(db, cart).await?),
+    )))
+}
+
+#[put("/coupon", data = "<req_coupon>")]
+pub async fn apply_coupon(
+    db: &State<DatabaseConnection>,
+    user: MaybeUser,
+    token: CartToken,
+    req_coupon: Json<ReqCoupon>,
+) -> Response<Json<ResCart>> {
+    let db = db as &DatabaseConnection;
+
+    let txn = db.begin().await?;
+
+    let cart = find_existing_cart(&txn, &user.0, &token).await?;
+    lock_for_change(&txn, &cart).await?;
+
+    let promotion = match normalize_code(&req_coupon.code) {
+        Some(code) => {
+            Promotion::find()
+                .filter(promotion::Column::Code.eq(code))
+                .filter(promotion::Column::Active.eq(true))
+                .one(&txn)
+                .await?
+        }
+        None => None,
+    };
+
+    let promotion = match promotion {
+        Some(p) => p,
+        None => return Err(unprocessable("Unknown coupon code")),
+    };
+
+    // Whether the coupon actually gives a discount is explained in the
+    // cart's notes, since that changes as the cart does.
+    let mut cart: cart::ActiveModel = cart.into();
+    cart.coupon_code = Set(promotion.code);
+    cart.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    let cart = cart.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResCart::load(db, cart).await?),
+    )))
+}
+
+#[delete("/coupon")]
+pub async fn remove_coupon(
+    db: &State<DatabaseConnection>,
+    user: MaybeUser,
+    token: CartToken,
+) -> Response<Json<ResCart>> {
+    let db = db as &DatabaseConnection;
+
+    let txn = db.begin().await?;
+
+    let cart = find_existing_cart(&txn, &user.0, &token).await?;
+    lock_for_change(&txn, &cart).await?;
+
+    let mut cart: cart::ActiveModel = cart.into();
+    cart.coupon_code = Set(None);
+    cart.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    let cart = cart.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResCart::load(db, cart).await?),
+    )))
+}
+
+#[delete("/")]
+pub async fn clear(
+    db: &State<DatabaseConnection>,
+    user: MaybeUser,
+    token: CartToken,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let txn = db.begin().await?;
+
+    let cart = find_existing_cart(&txn, &user.0, &token).await?;
+    lock_for_change(&txn, &cart).await?;
+
+    cart.delete(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Cart emptied".to_string(),
+        }),
+    )))
+}
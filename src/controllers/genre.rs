@@ -0,0 +1,300 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::{
+    auth::{AuthenticatedUser, CatalogueEditor},
+    entities::{genre, prelude::*, promotion},
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResGenre {
+    id: i32,
+    parent_id: Option<i32>,
+    name: String,
+}
+
+impl From<&genre::Model> for ResGenre {
+    fn from(g: &genre::Model) -> Self {
+        Self {
+            id: g.id,
+            parent_id: g.parent_id,
+            name: g.name.to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResGenreList {
+    total: usize,
+    genres: Vec<ResGenre>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqGenre {
+    parent_id: Option<i32>,
+    name: String,
+}
+
+/// Returns the ids of `root` and every genre below it.
+pub async fn subtree_ids<C: ConnectionTrait>(
+    db: &C,
+    root: i32,
+) -> Result<Vec<i32>, sea_orm::DbErr> {
+    let genres = Genre::find().all(db).await?;
+
+    let mut ids = vec![root];
+    let mut i = 0;
+
+    while i < ids.len() {
+        let parent = ids[i];
+        ids.extend(
+            genres
+                .iter()
+                .filter(|g| g.parent_id == Some(parent))
+                .map(|g| g.id),
+        );
+        i += 1;
+    }
+
+    Ok(ids)
+}
+
+async fn check_parent(
+    db: &DatabaseConnection,
+    id: Option<i32>,
+    parent_id: Option<i32>,
+) -> Result<(), ErrorResponse> {
+    let parent_id = match parent_id {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+
+    if Genre::find_by_id(parent_id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Cannot find parent genre with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    if let Some(id) = id {
+        if subtree_ids(db, id).await?.contains(&parent_id) {
+            return Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: "A genre cannot be moved below itself".to_string(),
+                }),
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+) -> Response<Json<ResGenreList>> {
+    let db = db as &DatabaseConnection;
+
+    let genres = Genre::find()
+        .order_by_asc(genre::Column::Name)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResGenre::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResGenreList {
+            total: genres.len(),
+            genres,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_genre>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    req_genre: Json<ReqGenre>,
+) -> Response<Json<ResGenre>> {
+    let db = db as &DatabaseConnection;
+
+    check_parent(db, None, req_genre.parent_id).await?;
+
+    let genre = genre::ActiveModel {
+        parent_id: Set(req_genre.parent_id),
+        name: Set(req_genre.name.to_owned()),
+        ..Default::default()
+    };
+
+    let genre = genre.insert(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResGenre::from(&genre)),
+    )))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResGenre>> {
+    let db = db as &DatabaseConnection;
+
+    let genre = Genre::find_by_id(id).one(db).await?;
+
+    let genre = match genre {
+        Some(g) => g,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find genre with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((Status::Ok, Json(ResGenre::from(&genre)))))
+}
+
+#[get("/<id>/children")]
+pub async fn children(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResGenreList>> {
+    let db = db as &DatabaseConnection;
+
+    let genres = Genre::find()
+        .filter(genre::Column::ParentId.eq(id))
+        .order_by_asc(genre::Column::Name)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResGenre::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResGenreList {
+            total: genres.len(),
+            genres,
+        }),
+    )))
+}
+
+#[put("/<id>", data = "<req_genre>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+    req_genre: Json<ReqGenre>,
+) -> Response<Json<ResGenre>> {
+    let db = db as &DatabaseConnection;
+
+    let genre = Genre::find_by_id(id).one(db).await?;
+
+    let mut genre: genre::ActiveModel = match genre {
+        Some(g) => g.into(),
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find genre with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    check_parent(db, Some(id), req_genre.parent_id).await?;
+
+    genre.parent_id = Set(req_genre.parent_id);
+    genre.name = Set(req_genre.name.to_owned());
+    genre.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let genre = genre.update(db).await?;
+
+    Ok(SuccessResponse((Status::Ok, Json(ResGenre::from(&genre)))))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let genre = Genre::find_by_id(id).one(db).await?;
+
+    let genre = match genre {
+        Some(g) => g,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find genre with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    if Genre::find()
+        .filter(genre::Column::ParentId.eq(id))
+        .count(db)
+        .await?
+        > 0
+    {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Genre still has sub-genres".to_string(),
+            }),
+        )));
+    }
+
+    if Promotion::find()
+        .filter(promotion::Column::GenreId.eq(id))
+        .count(db)
+        .await?
+        > 0
+    {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Genre is used by a promotion".to_string(),
+            }),
+        )));
+    }
+
+    genre.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Genre deleted".to_string(),
+        }),
+    )))
+}
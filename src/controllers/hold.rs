@@ -0,0 +1,340 @@
+use std::time::SystemTime;
+
+use chrono::Duration;
+use rocket::{
+    http::Status,
+    serde::{json::Json, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    DbErr, EntityTrait, JoinType, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait, Set, TransactionTrait,
+};
+
+use crate::{
+    auth::AuthenticatedUser,
+    entities::{
+        book_copy, hold, loan,
+        prelude::*,
+        sea_orm_active_enums::{CopyStatus, HoldStatus},
+    },
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+/// How long a ready copy waits for its reader before it moves on.
+const PICKUP_DAYS: i64 = 7;
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResHold {
+    id: i32,
+    book_id: i32,
+    status: HoldStatus,
+    /// 1 for the next hold to be served; only set while waiting.
+    queue_position: Option<u64>,
+    book_copy_id: Option<i32>,
+    ready_at: Option<DateTimeUtc>,
+    /// When a ready hold expires if the copy has not been collected.
+    pickup_by: Option<DateTimeUtc>,
+    created_at: Option<DateTimeUtc>,
+}
+
+impl ResHold {
+    async fn load(db: &DatabaseConnection, h: hold::Model) -> Result<Self, DbErr> {
+        let queue_position = match h.status {
+            HoldStatus::Waiting => Some(
+                Hold::find()
+                    .filter(hold::Column::BookId.eq(h.book_id))
+                    .filter(hold::Column::Status.eq(HoldStatus::Waiting))
+                    .filter(hold::Column::Id.lte(h.id))
+                    .count(db)
+                    .await?,
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            id: h.id,
+            book_id: h.book_id,
+            status: h.status,
+            queue_position,
+            book_copy_id: h.book_copy_id,
+            ready_at: h.ready_at,
+            pickup_by: h
+                .ready_at
+                .filter(|_| h.status == HoldStatus::Ready)
+                .map(|at| at + Duration::days(PICKUP_DAYS)),
+            created_at: h.created_at,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResHoldList {
+    total: usize,
+    holds: Vec<ResHold>,
+}
+
+/// Hands a copy that came back to the library to the oldest waiting hold on
+/// its book, or puts it back on the shelf when nobody is waiting.
+pub async fn shelve<C: ConnectionTrait>(
+    db: &C,
+    copy: book_copy::Model,
+) -> Result<book_copy::Model, DbErr> {
+    let now = DateTimeUtc::from(SystemTime::now());
+
+    let next = Hold::find()
+        .filter(hold::Column::BookId.eq(copy.book_id))
+        .filter(hold::Column::Status.eq(HoldStatus::Waiting))
+        .order_by_asc(hold::Column::Id)
+        .lock_exclusive()
+        .one(db)
+        .await?;
+
+    let status = match next {
+        Some(next) => {
+            let mut next: hold::ActiveModel = next.into();
+            next.status = Set(HoldStatus::Ready);
+            next.book_copy_id = Set(Some(copy.id));
+            next.ready_at = Set(Some(now));
+            next.updated_at = Set(Some(now));
+            next.update(db).await?;
+
+            CopyStatus::Reserved
+        }
+        None => CopyStatus::Available,
+    };
+
+    let mut copy: book_copy::ActiveModel = copy.into();
+    copy.status = Set(status);
+    copy.updated_at = Set(Some(now));
+
+    copy.update(db).await
+}
+
+/// Expires the ready holds on `book_id`, or on every book, whose copy was not
+/// collected in time, handing the copies on to the next in line.
+pub async fn expire_uncollected<C: ConnectionTrait>(
+    db: &C,
+    book_id: Option<i32>,
+) -> Result<(), DbErr> {
+    let now = DateTimeUtc::from(SystemTime::now());
+
+    let mut query = Hold::find()
+        .filter(hold::Column::Status.eq(HoldStatus::Ready))
+        .filter(hold::Column::ReadyAt.lt(now - Duration::days(PICKUP_DAYS)));
+
+    if let Some(book_id) = book_id {
+        query = query.filter(hold::Column::BookId.eq(book_id));
+    }
+
+    for hold in query.lock_exclusive().all(db).await? {
+        let copy_id = hold.book_copy_id;
+
+        let mut hold: hold::ActiveModel = hold.into();
+        hold.status = Set(HoldStatus::Expired);
+        hold.book_copy_id = Set(None);
+        hold.updated_at = Set(Some(now));
+        hold.update(db).await?;
+
+        if let Some(copy_id) = copy_id {
+            if let Some(copy) = BookCopy::find_by_id(copy_id)
+                .lock_exclusive()
+                .one(db)
+                .await?
+            {
+                shelve(db, copy).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The waiting or ready hold of `user_id` on `book_id`.
+pub async fn find_active_hold<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    book_id: i32,
+) -> Result<Option<hold::Model>, DbErr> {
+    Hold::find()
+        .filter(hold::Column::UserId.eq(user_id))
+        .filter(hold::Column::BookId.eq(book_id))
+        .filter(hold::Column::Status.is_in([HoldStatus::Waiting, HoldStatus::Ready]))
+        .lock_exclusive()
+        .one(db)
+        .await
+}
+
+/// Holds can only be placed while every copy of the book is out.
+#[post("/<id>/holds")]
+pub async fn place(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResHold>> {
+    let db = db as &DatabaseConnection;
+
+    let unprocessable = |message: &str| {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: message.to_string(),
+            }),
+        ))
+    };
+
+    if Book::find_by_id(id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find a book with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    let txn = db.begin().await?;
+
+    expire_uncollected(&txn, Some(id)).await?;
+
+    let copies = BookCopy::find()
+        .filter(book_copy::Column::BookId.eq(id))
+        .lock_exclusive()
+        .all(&txn)
+        .await?;
+
+    if copies.is_empty() {
+        return Err(unprocessable("This book has no copies to lend"));
+    }
+
+    if copies.iter().any(|c| c.status == CopyStatus::Available) {
+        return Err(unprocessable(
+            "A copy of this book is available, borrow it instead",
+        ));
+    }
+
+    if find_active_hold(&txn, user.id as i32, id).await?.is_some() {
+        return Err(unprocessable("You already have a hold on this book"));
+    }
+
+    if Loan::find()
+        .join(JoinType::InnerJoin, loan::Relation::BookCopy.def())
+        .filter(loan::Column::UserId.eq(user.id as i32))
+        .filter(loan::Column::ReturnedAt.is_null())
+        .filter(book_copy::Column::BookId.eq(id))
+        .one(&txn)
+        .await?
+        .is_some()
+    {
+        return Err(unprocessable("You already have this book on loan"));
+    }
+
+    let hold = hold::ActiveModel {
+        book_id: Set(id),
+        user_id: Set(user.id as i32),
+        status: Set(HoldStatus::Waiting),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResHold::load(db, hold).await?),
+    )))
+}
+
+/// Waiting and ready holds of the current user.
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<ResHoldList>> {
+    let db = db as &DatabaseConnection;
+
+    let txn = db.begin().await?;
+    expire_uncollected(&txn, None).await?;
+    txn.commit().await?;
+
+    let holds = Hold::find()
+        .filter(hold::Column::UserId.eq(user.id as i32))
+        .filter(hold::Column::Status.is_in([HoldStatus::Waiting, HoldStatus::Ready]))
+        .order_by_asc(hold::Column::Id)
+        .all(db)
+        .await?;
+
+    let mut res = vec![];
+
+    for hold in holds {
+        res.push(ResHold::load(db, hold).await?);
+    }
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResHoldList {
+            total: res.len(),
+            holds: res,
+        }),
+    )))
+}
+
+/// Cancelling a ready hold passes its copy on to the next in line.
+#[delete("/<id>")]
+pub async fn cancel(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResHold>> {
+    let db = db as &DatabaseConnection;
+
+    let txn = db.begin().await?;
+
+    let hold = Hold::find_by_id(id)
+        .filter(hold::Column::UserId.eq(user.id as i32))
+        .filter(hold::Column::Status.is_in([HoldStatus::Waiting, HoldStatus::Ready]))
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+
+    let hold = match hold {
+        Some(h) => h,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find an active hold with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    let copy_id = hold.book_copy_id;
+
+    let mut hold: hold::ActiveModel = hold.into();
+    hold.status = Set(HoldStatus::Cancelled);
+    hold.book_copy_id = Set(None);
+    hold.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    let hold = hold.update(&txn).await?;
+
+    if let Some(copy_id) = copy_id {
+        if let Some(copy) = BookCopy::find_by_id(copy_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+        {
+            shelve(&txn, copy).await?;
+        }
+    }
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResHold::load(db, hold).await?),
+    )))
+}
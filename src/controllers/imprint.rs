@@ -0,0 +1,217 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Expr},
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter,
+    QueryOrder, Set,
+};
+
+use crate::{
+    auth::{AuthenticatedUser, CatalogueEditor},
+    entities::{book, imprint, prelude::*},
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResImprint {
+    id: i32,
+    publisher_id: i32,
+    name: String,
+}
+
+impl From<&imprint::Model> for ResImprint {
+    fn from(i: &imprint::Model) -> Self {
+        Self {
+            id: i.id,
+            publisher_id: i.publisher_id,
+            name: i.name.to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResImprintList {
+    pub total: usize,
+    pub imprints: Vec<ResImprint>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqImprint {
+    publisher_id: i32,
+    name: String,
+}
+
+async fn check_publisher(db: &DatabaseConnection, publisher_id: i32) -> Result<(), ErrorResponse> {
+    if Publisher::find_by_id(publisher_id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Cannot find publisher with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    Ok(())
+}
+
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+) -> Response<Json<ResImprintList>> {
+    let db = db as &DatabaseConnection;
+
+    let imprints = Imprint::find()
+        .order_by_asc(imprint::Column::Name)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResImprint::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResImprintList {
+            total: imprints.len(),
+            imprints,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_imprint>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    user: CatalogueEditor,
+    req_imprint: Json<ReqImprint>,
+) -> Response<Json<ResImprint>> {
+    let db = db as &DatabaseConnection;
+
+    check_publisher(db, req_imprint.publisher_id).await?;
+
+    let imprint = imprint::ActiveModel {
+        user_id: Set(user.id as i32),
+        publisher_id: Set(req_imprint.publisher_id),
+        name: Set(req_imprint.name.to_owned()),
+        ..Default::default()
+    };
+
+    let imprint = imprint.insert(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResImprint::from(&imprint)),
+    )))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResImprint>> {
+    let db = db as &DatabaseConnection;
+
+    let imprint = Imprint::find_by_id(id).one(db).await?;
+
+    let imprint = match imprint {
+        Some(i) => i,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find imprint with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResImprint::from(&imprint)),
+    )))
+}
+
+#[put("/<id>", data = "<req_imprint>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+    req_imprint: Json<ReqImprint>,
+) -> Response<Json<ResImprint>> {
+    let db = db as &DatabaseConnection;
+
+    let imprint = Imprint::find_by_id(id).one(db).await?;
+
+    let mut imprint: imprint::ActiveModel = match imprint {
+        Some(i) => i.into(),
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find imprint with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    check_publisher(db, req_imprint.publisher_id).await?;
+
+    imprint.publisher_id = Set(req_imprint.publisher_id);
+    imprint.name = Set(req_imprint.name.to_owned());
+    imprint.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let imprint = imprint.update(db).await?;
+
+    // Books follow their imprint when it moves to another publisher.
+    Book::update_many()
+        .col_expr(book::Column::PublisherId, Expr::value(imprint.publisher_id))
+        .filter(book::Column::ImprintId.eq(imprint.id))
+        .exec(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResImprint::from(&imprint)),
+    )))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let imprint = Imprint::find_by_id(id).one(db).await?;
+
+    let imprint = match imprint {
+        Some(i) => i,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find imprint with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    imprint.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Imprint deleted".to_string(),
+        }),
+    )))
+}
@@ -0,0 +1,173 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::{ContentType, Status},
+    serde::json::Json,
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+
+use crate::{
+    auth::AuthenticatedUser,
+    entities::{
+        invoice, invoice_sequence, order, order_line, prelude::*,
+        sea_orm_active_enums::OrderStatus, user,
+    },
+    invoices,
+    storage::BlobStorage,
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+/// The row of `invoice_sequence` holding the last number issued.
+const SEQUENCE_ID: i32 = 1;
+
+/// Issues the invoice for a paid order, or returns the one already issued.
+/// Cancelled and refunded orders keep an invoice issued earlier but never get
+/// a new one.
+///
+/// Numbers come from a counter row locked by the transaction that records the
+/// invoice, so they stay sequential without gaps even when rendering or
+/// storage fails, and concurrent issues wait for each other.
+pub async fn issue(
+    db: &DatabaseConnection,
+    storage: &dyn BlobStorage,
+    order: &order::Model,
+) -> Result<invoice::Model, ErrorResponse> {
+    if let Some(invoice) = order.find_related(Invoice).one(db).await? {
+        return Ok(invoice);
+    }
+
+    if !matches!(
+        order.status,
+        OrderStatus::Paid | OrderStatus::Shipped | OrderStatus::Delivered
+    ) {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Invoices are only issued for paid orders".to_string(),
+            }),
+        )));
+    }
+
+    let lines = order
+        .find_related(OrderLine)
+        .order_by_asc(order_line::Column::Id)
+        .all(db)
+        .await?;
+    let customer = User::find_by_id(order.user_id).one(db).await?.unwrap();
+
+    let txn = db.begin().await?;
+
+    let sequence = InvoiceSequence::find_by_id(SEQUENCE_ID)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .unwrap();
+    let number = sequence.last_number + 1;
+
+    invoice_sequence::ActiveModel {
+        id: Set(SEQUENCE_ID),
+        last_number: Set(number),
+    }
+    .update(&txn)
+    .await?;
+    let storage_key = format!("invoices/{}.pdf", invoices::format_number(number));
+    let issued_at = DateTimeUtc::from(SystemTime::now());
+
+    let invoice = invoice::ActiveModel {
+        order_id: Set(order.id),
+        number: Set(number),
+        storage_key: Set(storage_key.to_owned()),
+        created_at: Set(Some(issued_at)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    let pdf = invoices::render(number, issued_at, order, &lines, &customer);
+    storage.put(&storage_key, &pdf).await?;
+
+    txn.commit().await?;
+
+    Ok(invoice)
+}
+
+/// Renders the customer's stored invoices again from `customer`, so that a
+/// deleted account no longer shows its name and email address on them.
+/// Numbers, dates and amounts are unchanged.
+pub async fn redact<C: ConnectionTrait>(
+    db: &C,
+    storage: &dyn BlobStorage,
+    customer: &user::Model,
+) -> Result<(), ErrorResponse> {
+    let invoices = Invoice::find()
+        .find_also_related(Order)
+        .filter(order::Column::UserId.eq(customer.id))
+        .all(db)
+        .await?;
+
+    for (invoice, order) in invoices {
+        let order = order.unwrap();
+        let lines = order
+            .find_related(OrderLine)
+            .order_by_asc(order_line::Column::Id)
+            .all(db)
+            .await?;
+        let issued_at = invoice
+            .created_at
+            .unwrap_or_else(|| DateTimeUtc::from(SystemTime::now()));
+
+        let pdf = invoices::render(invoice.number, issued_at, &order, &lines, customer);
+        storage.put(&invoice.storage_key, &pdf).await?;
+    }
+
+    Ok(())
+}
+
+#[get("/<id>/invoice")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    storage: &State<Box<dyn BlobStorage>>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<(ContentType, Vec<u8>)> {
+    let db = db as &DatabaseConnection;
+    let storage = storage.inner().as_ref();
+
+    let order = Order::find_by_id(id)
+        .filter(order::Column::UserId.eq(user.id as i32))
+        .one(db)
+        .await?;
+
+    let order = match order {
+        Some(o) => o,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find order with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    let invoice = issue(db, storage, &order).await?;
+
+    let pdf = match storage.get(&invoice.storage_key).await? {
+        Some(pdf) => pdf,
+        None => {
+            return Err(ErrorResponse((
+                Status::InternalServerError,
+                Json(GenericResponse {
+                    message: "The invoice document is missing from storage".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((Status::Ok, (ContentType::PDF, pdf))))
+}
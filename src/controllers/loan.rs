@@ -0,0 +1,394 @@
+use std::time::SystemTime;
+
+use chrono::Duration;
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    DbErr, EntityTrait, JoinType, LoaderTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait,
+    Set, TransactionTrait,
+};
+
+use crate::{
+    auth::{AdminUser, AuthenticatedUser},
+    entities::{
+        book_copy, hold, loan,
+        prelude::*,
+        sea_orm_active_enums::{CopyStatus, HoldStatus, UserRole},
+    },
+};
+
+use super::{
+    hold::{expire_uncollected, find_active_hold, shelve},
+    ErrorResponse, GenericResponse, Response, SuccessResponse,
+};
+
+const LOAN_DAYS: i64 = 21;
+const MAX_RENEWALS: i32 = 2;
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResLoan {
+    id: i32,
+    user_id: i32,
+    book_id: i32,
+    title: String,
+    book_copy_id: i32,
+    barcode: String,
+    checked_out_at: Option<DateTimeUtc>,
+    due_at: DateTimeUtc,
+    returned_at: Option<DateTimeUtc>,
+    renewals: i32,
+    overdue: bool,
+}
+
+impl ResLoan {
+    async fn load(db: &DatabaseConnection, loans: Vec<loan::Model>) -> Result<Vec<Self>, DbErr> {
+        let now = DateTimeUtc::from(SystemTime::now());
+
+        // Both foreign keys are required, so every loan has a copy and every
+        // copy a book.
+        let copies = loans
+            .load_one(BookCopy, db)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        let books = copies.load_one(Book, db).await?;
+
+        Ok(loans
+            .into_iter()
+            .zip(copies)
+            .zip(books)
+            .map(|((l, c), b)| Self {
+                id: l.id,
+                user_id: l.user_id,
+                book_id: c.book_id,
+                title: b.map(|b| b.title).unwrap_or_default(),
+                book_copy_id: c.id,
+                barcode: c.barcode,
+                checked_out_at: l.created_at,
+                due_at: l.due_at,
+                returned_at: l.returned_at,
+                renewals: l.renewals,
+                overdue: l.returned_at.is_none() && l.due_at < now,
+            })
+            .collect())
+    }
+
+    async fn load_one(db: &DatabaseConnection, loan: loan::Model) -> Result<Self, DbErr> {
+        Ok(Self::load(db, vec![loan]).await?.remove(0))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResLoanList {
+    total: usize,
+    loans: Vec<ResLoan>,
+}
+
+impl ResLoanList {
+    async fn load(db: &DatabaseConnection, loans: Vec<loan::Model>) -> Result<Self, DbErr> {
+        let loans = ResLoan::load(db, loans).await?;
+
+        Ok(Self {
+            total: loans.len(),
+            loans,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqLoan {
+    book_id: i32,
+}
+
+#[derive(FromFormField)]
+pub enum LoanFilter {
+    Active,
+    Overdue,
+    Returned,
+}
+
+/// The active loan `id`, limited to those of `user_id` when given.
+async fn find_active_loan<C: ConnectionTrait>(
+    db: &C,
+    user_id: Option<i32>,
+    id: i32,
+) -> Result<loan::Model, ErrorResponse> {
+    let mut query = Loan::find_by_id(id).filter(loan::Column::ReturnedAt.is_null());
+
+    if let Some(user_id) = user_id {
+        query = query.filter(loan::Column::UserId.eq(user_id));
+    }
+
+    match query.lock_exclusive().one(db).await? {
+        Some(l) => Ok(l),
+        None => Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find an active loan with specified ID".to_string(),
+            }),
+        ))),
+    }
+}
+
+/// Loan history of the current user, newest first.
+#[get("/?<status>")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    status: Option<LoanFilter>,
+) -> Response<Json<ResLoanList>> {
+    let db = db as &DatabaseConnection;
+
+    let mut query = Loan::find().filter(loan::Column::UserId.eq(user.id as i32));
+
+    query = match status {
+        Some(LoanFilter::Active) => query.filter(loan::Column::ReturnedAt.is_null()),
+        Some(LoanFilter::Overdue) => query
+            .filter(loan::Column::ReturnedAt.is_null())
+            .filter(loan::Column::DueAt.lt(DateTimeUtc::from(SystemTime::now()))),
+        Some(LoanFilter::Returned) => query.filter(loan::Column::ReturnedAt.is_not_null()),
+        None => query,
+    };
+
+    let loans = query
+        .order_by_desc(loan::Column::CreatedAt)
+        .order_by_desc(loan::Column::Id)
+        .all(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResLoanList::load(db, loans).await?),
+    )))
+}
+
+/// Every overdue loan in the library, most overdue first.
+#[get("/overdue")]
+pub async fn overdue(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+) -> Response<Json<ResLoanList>> {
+    let db = db as &DatabaseConnection;
+
+    let loans = Loan::find()
+        .filter(loan::Column::ReturnedAt.is_null())
+        .filter(loan::Column::DueAt.lt(DateTimeUtc::from(SystemTime::now())))
+        .order_by_asc(loan::Column::DueAt)
+        .all(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResLoanList::load(db, loans).await?),
+    )))
+}
+
+/// Checks out the copy held for the user, or else any copy on the shelf.
+#[post("/", data = "<req_loan>")]
+pub async fn checkout(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    req_loan: Json<ReqLoan>,
+) -> Response<Json<ResLoan>> {
+    let db = db as &DatabaseConnection;
+    let user_id = user.id as i32;
+
+    let unprocessable = |message: &str| {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: message.to_string(),
+            }),
+        ))
+    };
+
+    let txn = db.begin().await?;
+
+    if Loan::find()
+        .join(JoinType::InnerJoin, loan::Relation::BookCopy.def())
+        .filter(loan::Column::UserId.eq(user_id))
+        .filter(loan::Column::ReturnedAt.is_null())
+        .filter(book_copy::Column::BookId.eq(req_loan.book_id))
+        .one(&txn)
+        .await?
+        .is_some()
+    {
+        return Err(unprocessable("You already have this book on loan"));
+    }
+
+    let now = DateTimeUtc::from(SystemTime::now());
+
+    expire_uncollected(&txn, Some(req_loan.book_id)).await?;
+
+    let hold = find_active_hold(&txn, user_id, req_loan.book_id).await?;
+
+    let held_copy = match hold.as_ref().filter(|h| h.status == HoldStatus::Ready) {
+        Some(hold) => match hold.book_copy_id {
+            Some(copy_id) => {
+                BookCopy::find_by_id(copy_id)
+                    .lock_exclusive()
+                    .one(&txn)
+                    .await?
+            }
+            None => None,
+        },
+        None => None,
+    };
+
+    let copy = match held_copy {
+        Some(c) => c,
+        None => {
+            let copy = BookCopy::find()
+                .filter(book_copy::Column::BookId.eq(req_loan.book_id))
+                .filter(book_copy::Column::Status.eq(CopyStatus::Available))
+                .order_by_asc(book_copy::Column::Id)
+                .lock_exclusive()
+                .one(&txn)
+                .await?;
+
+            match copy {
+                Some(c) => c,
+                None => {
+                    return Err(unprocessable(
+                        "No copy of this book is available, place a hold instead",
+                    ))
+                }
+            }
+        }
+    };
+
+    if let Some(hold) = hold {
+        let mut hold: hold::ActiveModel = hold.into();
+        hold.status = Set(HoldStatus::Fulfilled);
+        hold.updated_at = Set(Some(now));
+        hold.update(&txn).await?;
+    }
+
+    let loan = loan::ActiveModel {
+        book_copy_id: Set(copy.id),
+        user_id: Set(user_id),
+        due_at: Set(now + Duration::days(LOAN_DAYS)),
+        renewals: Set(0),
+        created_at: Set(Some(now)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    let mut copy: book_copy::ActiveModel = copy.into();
+    copy.status = Set(CopyStatus::OnLoan);
+    copy.updated_at = Set(Some(now));
+    copy.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResLoan::load_one(db, loan).await?),
+    )))
+}
+
+/// Extends the due date by another loan period, unless someone is waiting
+/// for the book.
+#[post("/<id>/renew")]
+pub async fn renew(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResLoan>> {
+    let db = db as &DatabaseConnection;
+
+    let unprocessable = |message: String| {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse { message }),
+        ))
+    };
+
+    let txn = db.begin().await?;
+
+    let owner = (user.role != UserRole::Admin).then_some(user.id as i32);
+    let loan = find_active_loan(&txn, owner, id).await?;
+
+    if loan.renewals >= MAX_RENEWALS {
+        return Err(unprocessable(format!(
+            "A loan can be renewed at most {} times",
+            MAX_RENEWALS
+        )));
+    }
+
+    let copy = BookCopy::find_by_id(loan.book_copy_id)
+        .one(&txn)
+        .await?
+        .unwrap();
+
+    if Hold::find()
+        .filter(hold::Column::BookId.eq(copy.book_id))
+        .filter(hold::Column::Status.eq(HoldStatus::Waiting))
+        .one(&txn)
+        .await?
+        .is_some()
+    {
+        return Err(unprocessable(
+            "Other readers are waiting for this book".to_string(),
+        ));
+    }
+
+    let now = DateTimeUtc::from(SystemTime::now());
+    let renewals = loan.renewals + 1;
+    let due_at = loan.due_at.max(now) + Duration::days(LOAN_DAYS);
+
+    let mut loan: loan::ActiveModel = loan.into();
+    loan.renewals = Set(renewals);
+    loan.due_at = Set(due_at);
+    let loan = loan.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResLoan::load_one(db, loan).await?),
+    )))
+}
+
+/// Checks a copy back in at the desk. Only staff do this, since the copy is
+/// handed on to the next hold straight away.
+#[post("/<id>/return")]
+pub async fn give_back(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+) -> Response<Json<ResLoan>> {
+    let db = db as &DatabaseConnection;
+
+    let txn = db.begin().await?;
+
+    let loan = find_active_loan(&txn, None, id).await?;
+
+    let copy = BookCopy::find_by_id(loan.book_copy_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .unwrap();
+
+    let mut loan: loan::ActiveModel = loan.into();
+    loan.returned_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    let loan = loan.update(&txn).await?;
+
+    shelve(&txn, copy).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResLoan::load_one(db, loan).await?),
+    )))
+}
@@ -0,0 +1,247 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection,
+    EntityTrait, ModelTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::{
+    auth::{AdminUser, AuthenticatedUser},
+    entities::{location, prelude::*, stock_level, stock_movement},
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResLocation {
+    id: i32,
+    code: String,
+    name: String,
+    address: Option<String>,
+}
+
+impl From<&location::Model> for ResLocation {
+    fn from(l: &location::Model) -> Self {
+        Self {
+            id: l.id,
+            code: l.code.to_owned(),
+            name: l.name.to_owned(),
+            address: l.address.to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResLocationList {
+    total: usize,
+    locations: Vec<ResLocation>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqLocation {
+    code: String,
+    name: String,
+    address: Option<String>,
+}
+
+async fn check_code(
+    db: &DatabaseConnection,
+    id: Option<i32>,
+    code: &str,
+) -> Result<(), ErrorResponse> {
+    let mut taken = Location::find().filter(location::Column::Code.eq(code));
+
+    if let Some(id) = id {
+        taken = taken.filter(location::Column::Id.ne(id));
+    }
+
+    if taken.one(db).await?.is_some() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "A location exists with that code".to_string(),
+            }),
+        )));
+    }
+
+    Ok(())
+}
+
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+) -> Response<Json<ResLocationList>> {
+    let db = db as &DatabaseConnection;
+
+    let locations = Location::find()
+        .order_by_asc(location::Column::Code)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResLocation::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResLocationList {
+            total: locations.len(),
+            locations,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_location>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    req_location: Json<ReqLocation>,
+) -> Response<Json<ResLocation>> {
+    let db = db as &DatabaseConnection;
+
+    check_code(db, None, &req_location.code).await?;
+
+    let location = location::ActiveModel {
+        code: Set(req_location.code.to_owned()),
+        name: Set(req_location.name.to_owned()),
+        address: Set(req_location.address.to_owned()),
+        ..Default::default()
+    };
+
+    let location = location.insert(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResLocation::from(&location)),
+    )))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResLocation>> {
+    let db = db as &DatabaseConnection;
+
+    let location = Location::find_by_id(id).one(db).await?;
+
+    let location = match location {
+        Some(l) => l,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find location with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResLocation::from(&location)),
+    )))
+}
+
+#[put("/<id>", data = "<req_location>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+    req_location: Json<ReqLocation>,
+) -> Response<Json<ResLocation>> {
+    let db = db as &DatabaseConnection;
+
+    let location = Location::find_by_id(id).one(db).await?;
+
+    let mut location: location::ActiveModel = match location {
+        Some(l) => l.into(),
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find location with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    check_code(db, Some(id), &req_location.code).await?;
+
+    location.code = Set(req_location.code.to_owned());
+    location.name = Set(req_location.name.to_owned());
+    location.address = Set(req_location.address.to_owned());
+    location.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let location = location.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResLocation::from(&location)),
+    )))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let location = Location::find_by_id(id).one(db).await?;
+
+    let location = match location {
+        Some(l) => l,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find location with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    // The stock ledger is append-only, so locations it mentions must stay.
+    if StockMovement::find()
+        .filter(
+            Condition::any()
+                .add(stock_movement::Column::LocationId.eq(id))
+                .add(stock_movement::Column::CounterpartLocationId.eq(id)),
+        )
+        .one(db)
+        .await?
+        .is_some()
+    {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Location has stock history".to_string(),
+            }),
+        )));
+    }
+
+    StockLevel::delete_many()
+        .filter(stock_level::Column::LocationId.eq(id))
+        .exec(db)
+        .await?;
+
+    location.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Location deleted".to_string(),
+        }),
+    )))
+}
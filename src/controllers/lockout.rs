@@ -0,0 +1,305 @@
+use std::{net::IpAddr, time::SystemTime};
+
+use chrono::Duration;
+use rocket::{
+    http::Status,
+    serde::{json::Json, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, sea_query::OnConflict, ActiveModelTrait, ColumnTrait, DatabaseConnection,
+    DbErr, EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+
+use crate::{
+    auth::AdminUser,
+    entities::{prelude::*, sea_orm_active_enums::ThrottleScope, sign_in_throttle, user},
+    mail::{Email, Mailer},
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+/// Failures allowed before each further attempt has to wait.
+const FREE_ATTEMPTS: i32 = 3;
+/// The wait doubles with every failure past the free ones, up to this.
+const MAX_DELAY_SECONDS: i64 = 60;
+/// Failures are forgotten after this long without another one.
+const FAILURE_WINDOW_MINUTES: i64 = 60;
+const LOCKOUT_MINUTES: i64 = 15;
+const ACCOUNT_LOCKOUT_AFTER: i32 = 10;
+/// Much higher than for accounts, since many users may share an address.
+const IP_LOCKOUT_AFTER: i32 = 50;
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResLockout {
+    id: i32,
+    scope: ThrottleScope,
+    subject: String,
+    failures: i32,
+    last_failure_at: Option<DateTimeUtc>,
+    locked_until: Option<DateTimeUtc>,
+}
+
+impl From<sign_in_throttle::Model> for ResLockout {
+    fn from(t: sign_in_throttle::Model) -> Self {
+        Self {
+            id: t.id,
+            scope: t.scope,
+            subject: t.subject,
+            failures: t.failures,
+            last_failure_at: t.last_failure_at,
+            locked_until: t.locked_until,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResLockoutList {
+    total: usize,
+    lockouts: Vec<ResLockout>,
+}
+
+/// Who is signing in, for throttling: the account by its email address,
+/// whether or not it exists, and the client's IP address when known.
+pub struct SignInSubjects {
+    account: String,
+    ip: Option<String>,
+}
+
+impl SignInSubjects {
+    pub fn new(email: &str, ip: Option<IpAddr>) -> Self {
+        Self {
+            account: email.trim().to_lowercase(),
+            ip: ip.map(|ip| ip.to_string()),
+        }
+    }
+
+    fn each(&self) -> impl Iterator<Item = (ThrottleScope, &str)> {
+        std::iter::once((ThrottleScope::Account, self.account.as_str()))
+            .chain(self.ip.as_deref().map(|ip| (ThrottleScope::Ip, ip)))
+    }
+}
+
+fn lockout_after(scope: ThrottleScope) -> i32 {
+    match scope {
+        ThrottleScope::Account => ACCOUNT_LOCKOUT_AFTER,
+        ThrottleScope::Ip => IP_LOCKOUT_AFTER,
+    }
+}
+
+fn delay(failures: i32) -> Duration {
+    match failures - FREE_ATTEMPTS {
+        n if n < 0 => Duration::zero(),
+        n => Duration::seconds(2i64.saturating_pow(n as u32).min(MAX_DELAY_SECONDS)),
+    }
+}
+
+fn is_stale(throttle: &sign_in_throttle::Model, now: DateTimeUtc) -> bool {
+    throttle.locked_until.is_some_and(|until| until <= now)
+        || throttle
+            .last_failure_at
+            .is_none_or(|at| now - at > Duration::minutes(FAILURE_WINDOW_MINUTES))
+}
+
+fn too_many(message: String) -> ErrorResponse {
+    ErrorResponse((Status::TooManyRequests, Json(GenericResponse { message })))
+}
+
+/// Refuses the attempt while the account or address is locked out, or until
+/// the delay since its last failure has passed.
+pub async fn check(
+    db: &DatabaseConnection,
+    subjects: &SignInSubjects,
+) -> Result<(), ErrorResponse> {
+    let now = DateTimeUtc::from(SystemTime::now());
+
+    for (scope, subject) in subjects.each() {
+        let throttle = SignInThrottle::find()
+            .filter(sign_in_throttle::Column::Scope.eq(scope))
+            .filter(sign_in_throttle::Column::Subject.eq(subject))
+            .one(db)
+            .await?;
+
+        let throttle = match throttle {
+            Some(t) if !is_stale(&t, now) => t,
+            _ => continue,
+        };
+
+        if let Some(until) = throttle.locked_until {
+            return Err(too_many(format!(
+                "Too many failed sign-in attempts, try again in {} minutes",
+                (until - now).num_minutes() + 1
+            )));
+        }
+
+        if let Some(at) = throttle.last_failure_at {
+            let wait = at + delay(throttle.failures) - now;
+
+            if wait > Duration::zero() {
+                return Err(too_many(format!(
+                    "Too many failed sign-in attempts, try again in {} seconds",
+                    wait.num_seconds() + 1
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Counts a failed attempt against every subject, locking out those that
+/// reached their limit. The owner of `user`, if the account exists, is told
+/// when it gets locked.
+pub async fn record_failure(
+    db: &DatabaseConnection,
+    mailer: &dyn Mailer,
+    subjects: &SignInSubjects,
+    user: Option<&user::Model>,
+) -> Result<(), DbErr> {
+    let now = DateTimeUtc::from(SystemTime::now());
+    let mut account_locked = false;
+
+    let txn = db.begin().await?;
+
+    for (scope, subject) in subjects.each() {
+        // The row is created first if needed, so concurrent first failures
+        // for a subject all end up counting on the same row.
+        SignInThrottle::insert(sign_in_throttle::ActiveModel {
+            scope: Set(scope),
+            subject: Set(subject.to_string()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([
+                sign_in_throttle::Column::Scope,
+                sign_in_throttle::Column::Subject,
+            ])
+            .update_column(sign_in_throttle::Column::Subject)
+            .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+
+        let throttle = SignInThrottle::find()
+            .filter(sign_in_throttle::Column::Scope.eq(scope))
+            .filter(sign_in_throttle::Column::Subject.eq(subject))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .unwrap();
+
+        let failures = if is_stale(&throttle, now) {
+            1
+        } else {
+            throttle.failures + 1
+        };
+
+        let locked_until =
+            (failures >= lockout_after(scope)).then(|| now + Duration::minutes(LOCKOUT_MINUTES));
+
+        account_locked |= scope == ThrottleScope::Account && locked_until.is_some();
+
+        let mut throttle: sign_in_throttle::ActiveModel = throttle.into();
+        throttle.failures = Set(failures);
+        throttle.last_failure_at = Set(Some(now));
+        throttle.locked_until = Set(locked_until);
+        throttle.update(&txn).await?;
+    }
+
+    txn.commit().await?;
+
+    if let Some(user) = user.filter(|_| account_locked) {
+        notify(mailer, user).await;
+    }
+
+    Ok(())
+}
+
+/// A successful sign-in forgets the account's failures. Those of the address
+/// are kept, so one valid account cannot be used to reset them.
+pub async fn clear(db: &DatabaseConnection, subjects: &SignInSubjects) -> Result<(), DbErr> {
+    SignInThrottle::delete_many()
+        .filter(sign_in_throttle::Column::Scope.eq(ThrottleScope::Account))
+        .filter(sign_in_throttle::Column::Subject.eq(&subjects.account))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+async fn notify(mailer: &dyn Mailer, user: &user::Model) {
+    let email = Email {
+        to: user.email.to_owned(),
+        subject: "Your account has been locked".to_string(),
+        body: format!(
+            "There were too many failed attempts to sign in to your bookstore account, \
+             so it has been locked for {} minutes.\n\n\
+             If this wasn't you, someone may be guessing your password. \
+             Consider choosing a stronger one once the lock expires.\n",
+            LOCKOUT_MINUTES
+        ),
+    };
+
+    if let Err(e) = mailer.send(email).await {
+        eprintln!("Could not send lockout notification: {}", e.0);
+    }
+}
+
+/// Accounts and addresses currently locked out, longest lock first.
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+) -> Response<Json<ResLockoutList>> {
+    let db = db as &DatabaseConnection;
+
+    let lockouts = SignInThrottle::find()
+        .filter(sign_in_throttle::Column::LockedUntil.gt(DateTimeUtc::from(SystemTime::now())))
+        .order_by_desc(sign_in_throttle::Column::LockedUntil)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(ResLockout::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResLockoutList {
+            total: lockouts.len(),
+            lockouts,
+        }),
+    )))
+}
+
+/// Unlocks the account or address and forgets its failures.
+#[delete("/<id>")]
+pub async fn unlock(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let throttle = match SignInThrottle::find_by_id(id).one(db).await? {
+        Some(t) => t,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find a lockout with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    throttle.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Unlocked".to_string(),
+        }),
+    )))
+}
@@ -1,12 +1,45 @@
 use rocket::{
     http::Status,
     serde::{json::Json, Serialize},
+    Request,
 };
 use sea_orm::DbErr;
 
+use crate::{
+    auth::{auth_error, keyring::KeyringError},
+    mail::MailError,
+    storage::StorageError,
+};
+
+pub mod account;
+pub mod api_key;
 pub mod auth;
 pub mod author;
 pub mod book;
+pub mod book_copy;
+pub mod cart;
+pub mod genre;
+pub mod hold;
+pub mod imprint;
+pub mod invoice;
+pub mod loan;
+pub mod location;
+pub mod lockout;
+pub mod oauth;
+pub mod order;
+pub mod price;
+pub mod promotion;
+pub mod publisher;
+pub mod reading_list;
+pub mod review;
+pub mod series;
+pub mod session;
+pub mod stock;
+pub mod tag;
+pub mod tax_rate;
+pub mod two_factor;
+pub mod well_known;
+pub mod work;
 
 #[derive(Serialize)]
 #[serde(crate = "rocket::serde")]
@@ -32,3 +65,50 @@ impl From<DbErr> for ErrorResponse {
         ))
     }
 }
+
+impl From<StorageError> for ErrorResponse {
+    fn from(err: StorageError) -> Self {
+        ErrorResponse((
+            Status::InternalServerError,
+            Json(GenericResponse { message: err.0 }),
+        ))
+    }
+}
+
+impl From<MailError> for ErrorResponse {
+    fn from(err: MailError) -> Self {
+        ErrorResponse((
+            Status::InternalServerError,
+            Json(GenericResponse { message: err.0 }),
+        ))
+    }
+}
+
+impl From<KeyringError> for ErrorResponse {
+    fn from(err: KeyringError) -> Self {
+        ErrorResponse((
+            Status::InternalServerError,
+            Json(GenericResponse { message: err.0 }),
+        ))
+    }
+}
+
+fn auth_failure(req: &Request, fallback: &str) -> Json<GenericResponse> {
+    Json(GenericResponse {
+        message: match auth_error(req) {
+            Some(e) => e.to_string(),
+            None => fallback.to_string(),
+        },
+    })
+}
+
+/// Reports why the authentication guards refused the request.
+#[catch(401)]
+pub fn unauthorized(req: &Request) -> Json<GenericResponse> {
+    auth_failure(req, "Unauthorized")
+}
+
+#[catch(403)]
+pub fn forbidden(req: &Request) -> Json<GenericResponse> {
+    auth_failure(req, "Forbidden")
+}
@@ -0,0 +1,920 @@
+use std::time::SystemTime;
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use chrono::Duration;
+use jsonwebtoken::{decode, decode_header, Validation};
+use rocket::{
+    form::Form,
+    http::{uri::Absolute, RawStr, Status},
+    request::{self, FromRequest, Outcome, Request},
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    ModelTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth::{
+        keyring::{Keyring, KeyringError},
+        keys, AdminUser, AuthenticatedUser,
+    },
+    entities::{oauth_authorization_code, oauth_client, oauth_consent, prelude::*, user},
+    AppConfig,
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+pub const SUPPORTED_SCOPES: [&str; 3] = ["openid", "profile", "email"];
+const CODE_TTL_MINUTES: i64 = 10;
+const ACCESS_TOKEN_TTL: u64 = 60 * 60;
+
+fn epoch_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn unprocessable(message: &str) -> ErrorResponse {
+    ErrorResponse((
+        Status::UnprocessableEntity,
+        Json(GenericResponse {
+            message: message.to_string(),
+        }),
+    ))
+}
+
+/// Errors of the token endpoint, in the shape RFC 6749 requires.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResOAuthError {
+    error: String,
+    error_description: String,
+}
+
+#[derive(Responder)]
+pub struct OAuthError((Status, Json<ResOAuthError>));
+
+impl OAuthError {
+    fn new(status: Status, error: &str, description: &str) -> Self {
+        OAuthError((
+            status,
+            Json(ResOAuthError {
+                error: error.to_string(),
+                error_description: description.to_string(),
+            }),
+        ))
+    }
+
+    fn invalid_client() -> Self {
+        Self::new(
+            Status::Unauthorized,
+            "invalid_client",
+            "Client authentication failed",
+        )
+    }
+
+    fn invalid_grant(description: &str) -> Self {
+        Self::new(Status::BadRequest, "invalid_grant", description)
+    }
+}
+
+impl From<DbErr> for OAuthError {
+    fn from(err: DbErr) -> Self {
+        Self::new(
+            Status::InternalServerError,
+            "server_error",
+            &err.to_string(),
+        )
+    }
+}
+
+impl From<KeyringError> for OAuthError {
+    fn from(err: KeyringError) -> Self {
+        Self::new(Status::InternalServerError, "server_error", &err.0)
+    }
+}
+
+/// Claims of the access tokens handed to OAuth clients. Their audience is
+/// the client, so they are not accepted by the rest of the API.
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+struct AccessClaims {
+    iss: String,
+    /// The user's ID, or the client ID for client credentials.
+    sub: String,
+    aud: String,
+    client_id: String,
+    scope: String,
+    token_use: String,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct IdClaims {
+    iss: String,
+    sub: String,
+    aud: String,
+    iat: u64,
+    exp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+    #[serde(flatten)]
+    user: ResUserInfo,
+}
+
+/// Standard OIDC claims about the user, as far as the scopes allow.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResUserInfo {
+    sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email_verified: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    given_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    family_name: Option<String>,
+}
+
+impl ResUserInfo {
+    fn new(user: &user::Model, scopes: &[&str]) -> Self {
+        let email = scopes.contains(&"email");
+        let profile = scopes.contains(&"profile");
+
+        let name = [user.firstname.as_deref(), user.lastname.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Self {
+            sub: user.id.to_string(),
+            email: email.then(|| user.email.to_owned()),
+            email_verified: email.then_some(user.email_verified_at.is_some()),
+            name: (profile && !name.is_empty()).then_some(name),
+            given_name: user.firstname.to_owned().filter(|_| profile),
+            family_name: user.lastname.to_owned().filter(|_| profile),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResOAuthClient {
+    id: i32,
+    client_id: String,
+    name: String,
+    confidential: bool,
+    redirect_uris: Vec<String>,
+    created_at: Option<DateTimeUtc>,
+}
+
+impl From<&oauth_client::Model> for ResOAuthClient {
+    fn from(c: &oauth_client::Model) -> Self {
+        Self {
+            id: c.id,
+            client_id: c.client_id.to_owned(),
+            name: c.name.to_owned(),
+            confidential: c.secret_hash.is_some(),
+            redirect_uris: redirect_uris(c).map(str::to_string).collect(),
+            created_at: c.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResOAuthClientList {
+    total: usize,
+    clients: Vec<ResOAuthClient>,
+}
+
+/// The only response that includes the client secret.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResNewOAuthClient {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_secret: Option<String>,
+    #[serde(flatten)]
+    client: ResOAuthClient,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqOAuthClient {
+    name: String,
+    redirect_uris: Vec<String>,
+    /// Confidential clients get a secret. Public ones, such as single page
+    /// and mobile apps, rely on PKCE alone.
+    confidential: bool,
+}
+
+/// An authorization request, as the client sends it in the query string.
+#[derive(FromForm, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqAuthorize {
+    response_type: String,
+    client_id: String,
+    redirect_uri: String,
+    scope: Option<String>,
+    state: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
+    nonce: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqConsent {
+    #[serde(flatten)]
+    request: ReqAuthorize,
+    approve: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResAuthorize {
+    client_name: String,
+    scopes: Vec<String>,
+    consent_required: bool,
+    /// Where to send the browser next, once no consent is required.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect_to: Option<String>,
+}
+
+#[derive(FromForm)]
+pub struct ReqToken {
+    grant_type: String,
+    code: Option<String>,
+    redirect_uri: Option<String>,
+    code_verifier: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    scope: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResToken {
+    access_token: String,
+    token_type: String,
+    expires_in: u64,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_token: Option<String>,
+}
+
+/// Client credentials sent with HTTP Basic authentication.
+pub struct ClientBasicAuth(Option<(String, String)>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientBasicAuth {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let credentials = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|h| h.strip_prefix("Basic "))
+            .and_then(|b| STANDARD.decode(b.trim()).ok())
+            .and_then(|b| String::from_utf8(b).ok())
+            .and_then(|s| {
+                let (id, secret) = s.split_once(':')?;
+                Some((
+                    RawStr::new(id).url_decode().ok()?.into_owned(),
+                    RawStr::new(secret).url_decode().ok()?.into_owned(),
+                ))
+            });
+
+        Outcome::Success(ClientBasicAuth(credentials))
+    }
+}
+
+/// A user presenting an access token with the `openid` scope.
+pub struct OAuthUser {
+    id: i32,
+    scope: String,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for OAuthUser {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let config = req.rocket().state::<AppConfig>().unwrap();
+        let keyring = req.rocket().state::<Keyring>().unwrap();
+
+        let token = match req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+        {
+            Some(t) => t.trim(),
+            None => return Outcome::Error((Status::Unauthorized, ())),
+        };
+
+        let verifier = decode_header(token)
+            .ok()
+            .and_then(|h| h.kid)
+            .and_then(|kid| keyring.verifier(&kid));
+
+        let (algorithm, key) = match verifier {
+            Some(v) => v,
+            None => return Outcome::Error((Status::Unauthorized, ())),
+        };
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&config.jwt_issuer]);
+        validation.validate_aud = false;
+
+        let claims = match decode::<AccessClaims>(token, &key, &validation) {
+            Ok(data) if data.claims.token_use == "access" => data.claims,
+            _ => return Outcome::Error((Status::Unauthorized, ())),
+        };
+
+        if !claims.scope.split(' ').any(|s| s == "openid") {
+            return Outcome::Error((Status::Forbidden, ()));
+        }
+
+        match claims.sub.parse() {
+            Ok(id) => Outcome::Success(OAuthUser {
+                id,
+                scope: claims.scope,
+            }),
+            Err(_) => Outcome::Error((Status::Forbidden, ())),
+        }
+    }
+}
+
+fn redirect_uris(client: &oauth_client::Model) -> impl Iterator<Item = &str> {
+    client.redirect_uris.lines().filter(|l| !l.is_empty())
+}
+
+fn parse_scope(scope: Option<&str>) -> Option<Vec<&str>> {
+    let mut scopes: Vec<&str> = vec![];
+
+    for s in scope.unwrap_or_default().split_whitespace() {
+        if !SUPPORTED_SCOPES.contains(&s) {
+            return None;
+        }
+
+        if !scopes.contains(&s) {
+            scopes.push(s);
+        }
+    }
+
+    Some(scopes)
+}
+
+fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// PKCE values are 43 to 128 characters of the unpadded base64url alphabet
+/// (RFC 7636, section 4.2).
+fn is_pkce_value(value: &str) -> bool {
+    (43..=128).contains(&value.len())
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn with_query(uri: &str, params: &[(&str, Option<&str>)]) -> String {
+    let mut uri = uri.to_string();
+
+    for (name, value) in params {
+        if let Some(value) = value {
+            uri.push(if uri.contains('?') { '&' } else { '?' });
+            uri.push_str(name);
+            uri.push('=');
+            uri.push_str(RawStr::new(value).percent_encode().as_str());
+        }
+    }
+
+    uri
+}
+
+/// Checks an authorization request. Errors are never redirected, since the
+/// redirect URI cannot be trusted until it has been checked.
+async fn validate(
+    db: &DatabaseConnection,
+    req: &ReqAuthorize,
+) -> Result<(oauth_client::Model, Vec<String>), ErrorResponse> {
+    let client = OauthClient::find()
+        .filter(oauth_client::Column::ClientId.eq(&req.client_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| unprocessable("Unknown client_id"))?;
+
+    if !redirect_uris(&client).any(|u| u == req.redirect_uri) {
+        return Err(unprocessable(
+            "redirect_uri is not registered for this client",
+        ));
+    }
+
+    if req.response_type != "code" {
+        return Err(unprocessable("Only the code response_type is supported"));
+    }
+
+    if req.code_challenge.is_none() || req.code_challenge_method.as_deref() != Some("S256") {
+        return Err(unprocessable("PKCE with the S256 method is required"));
+    }
+
+    if !req.code_challenge.as_deref().is_some_and(is_pkce_value) {
+        return Err(unprocessable(
+            "code_challenge must be 43 to 128 base64url characters",
+        ));
+    }
+
+    let scopes = parse_scope(req.scope.as_deref())
+        .ok_or_else(|| unprocessable("scope contains an unsupported value"))?;
+
+    Ok((client, scopes.into_iter().map(str::to_string).collect()))
+}
+
+/// Issues an authorization code and returns the URI to send the browser to.
+async fn grant(
+    db: &DatabaseConnection,
+    client: &oauth_client::Model,
+    user_id: i32,
+    req: &ReqAuthorize,
+    scopes: &[String],
+) -> Result<String, DbErr> {
+    let code = keys::random_string(40);
+
+    oauth_authorization_code::ActiveModel {
+        code_hash: Set(keys::hash(&code)),
+        oauth_client_id: Set(client.id),
+        user_id: Set(user_id),
+        redirect_uri: Set(req.redirect_uri.to_owned()),
+        scope: Set(scopes.join(" ")),
+        nonce: Set(req.nonce.to_owned()),
+        code_challenge: Set(req.code_challenge.to_owned().unwrap_or_default()),
+        expires_at: Set(DateTimeUtc::from(SystemTime::now()) + Duration::minutes(CODE_TTL_MINUTES)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(with_query(
+        &req.redirect_uri,
+        &[("code", Some(&code)), ("state", req.state.as_deref())],
+    ))
+}
+
+/// First step of the authorization code flow, called by the consent page
+/// with the client's query string. When the user already consented to these
+/// scopes a code is issued right away.
+#[get("/authorize?<req_authorize..>")]
+pub async fn authorize(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    req_authorize: ReqAuthorize,
+) -> Response<Json<ResAuthorize>> {
+    let db = db as &DatabaseConnection;
+
+    let (client, scopes) = validate(db, &req_authorize).await?;
+
+    let consent = OauthConsent::find()
+        .filter(oauth_consent::Column::UserId.eq(user.id as i32))
+        .filter(oauth_consent::Column::OauthClientId.eq(client.id))
+        .one(db)
+        .await?;
+
+    let consented = consent.is_some_and(|c| {
+        let granted = c.scope.split(' ').collect::<Vec<_>>();
+        scopes.iter().all(|s| granted.contains(&s.as_str()))
+    });
+
+    let redirect_to = match consented {
+        true => Some(grant(db, &client, user.id as i32, &req_authorize, &scopes).await?),
+        false => None,
+    };
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResAuthorize {
+            client_name: client.name,
+            scopes,
+            consent_required: !consented,
+            redirect_to,
+        }),
+    )))
+}
+
+/// Records the user's decision. Approving remembers the consent and issues a
+/// code, declining sends the client an `access_denied` error.
+#[post("/authorize", data = "<req_consent>")]
+pub async fn consent(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    req_consent: Json<ReqConsent>,
+) -> Response<Json<ResAuthorize>> {
+    let db = db as &DatabaseConnection;
+    let req = &req_consent.request;
+
+    let (client, scopes) = validate(db, req).await?;
+
+    let redirect_to = if req_consent.approve {
+        let txn = db.begin().await?;
+
+        let consent = OauthConsent::find()
+            .filter(oauth_consent::Column::UserId.eq(user.id as i32))
+            .filter(oauth_consent::Column::OauthClientId.eq(client.id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?;
+
+        let mut granted = consent
+            .as_ref()
+            .map(|c| c.scope.split(' ').map(str::to_string).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        for s in &scopes {
+            if !granted.contains(s) {
+                granted.push(s.to_owned());
+            }
+        }
+
+        let mut consent = match consent {
+            Some(c) => c.into(),
+            None => oauth_consent::ActiveModel {
+                user_id: Set(user.id as i32),
+                oauth_client_id: Set(client.id),
+                ..Default::default()
+            },
+        };
+        consent.scope = Set(granted.join(" "));
+        consent.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+        consent.save(&txn).await?;
+
+        txn.commit().await?;
+
+        grant(db, &client, user.id as i32, req, &scopes).await?
+    } else {
+        with_query(
+            &req.redirect_uri,
+            &[
+                ("error", Some("access_denied")),
+                ("state", req.state.as_deref()),
+            ],
+        )
+    };
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResAuthorize {
+            client_name: client.name,
+            scopes,
+            consent_required: false,
+            redirect_to: Some(redirect_to),
+        }),
+    )))
+}
+
+/// Identifies the client from HTTP Basic credentials or the form body, and
+/// checks its secret if it has one.
+async fn authenticate_client(
+    db: &DatabaseConnection,
+    basic: ClientBasicAuth,
+    req: &ReqToken,
+) -> Result<oauth_client::Model, OAuthError> {
+    let (client_id, secret) = match basic.0 {
+        Some((id, secret)) => (id, Some(secret)),
+        None => match &req.client_id {
+            Some(id) => (id.to_owned(), req.client_secret.to_owned()),
+            None => return Err(OAuthError::invalid_client()),
+        },
+    };
+
+    let client = OauthClient::find()
+        .filter(oauth_client::Column::ClientId.eq(client_id))
+        .one(db)
+        .await?
+        .ok_or_else(OAuthError::invalid_client)?;
+
+    if let Some(secret_hash) = &client.secret_hash {
+        if secret.map(|s| keys::hash(&s)).as_ref() != Some(secret_hash) {
+            return Err(OAuthError::invalid_client());
+        }
+    }
+
+    Ok(client)
+}
+
+fn access_token(
+    config: &AppConfig,
+    keyring: &Keyring,
+    client: &oauth_client::Model,
+    sub: String,
+    scope: &str,
+) -> Result<String, KeyringError> {
+    let now = epoch_seconds();
+
+    keyring.sign(&AccessClaims {
+        iss: config.jwt_issuer.to_owned(),
+        sub,
+        aud: client.client_id.to_owned(),
+        client_id: client.client_id.to_owned(),
+        scope: scope.to_string(),
+        token_use: "access".to_string(),
+        iat: now,
+        exp: now + ACCESS_TOKEN_TTL,
+    })
+}
+
+async fn exchange_code(
+    db: &DatabaseConnection,
+    config: &AppConfig,
+    keyring: &Keyring,
+    client: oauth_client::Model,
+    req: &ReqToken,
+) -> Result<ResToken, OAuthError> {
+    let (code, redirect_uri, verifier) = match (&req.code, &req.redirect_uri, &req.code_verifier) {
+        (Some(c), Some(r), Some(v)) => (c, r, v),
+        _ => {
+            return Err(OAuthError::new(
+                Status::BadRequest,
+                "invalid_request",
+                "code, redirect_uri and code_verifier are required",
+            ))
+        }
+    };
+
+    let now = DateTimeUtc::from(SystemTime::now());
+
+    let txn = db.begin().await?;
+
+    let grant = OauthAuthorizationCode::find()
+        .filter(oauth_authorization_code::Column::CodeHash.eq(keys::hash(code)))
+        .filter(oauth_authorization_code::Column::OauthClientId.eq(client.id))
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .filter(|g| g.used_at.is_none() && g.expires_at > now)
+        .ok_or_else(|| OAuthError::invalid_grant("The code is invalid, used or expired"))?;
+
+    let mismatch = if &grant.redirect_uri != redirect_uri {
+        Some("redirect_uri does not match")
+    } else if pkce_challenge(verifier) != grant.code_challenge {
+        Some("code_verifier does not match")
+    } else {
+        None
+    };
+
+    // Any attempt spends the code, so a stolen one cannot be retried with
+    // guessed verifiers.
+    let mut grant: oauth_authorization_code::ActiveModel = grant.into();
+    grant.used_at = Set(Some(now));
+    let grant = grant.update(&txn).await?;
+
+    if let Some(description) = mismatch {
+        txn.commit().await?;
+
+        return Err(OAuthError::invalid_grant(description));
+    }
+
+    let user = grant.find_related(User).one(&txn).await?;
+
+    txn.commit().await?;
+
+    let user = user.ok_or_else(|| OAuthError::invalid_grant("The user no longer exists"))?;
+
+    let scope = grant.scope.to_owned();
+    let nonce = grant.nonce.to_owned();
+
+    let scopes = scope.split(' ').collect::<Vec<_>>();
+
+    let id_token = match scopes.contains(&"openid") {
+        true => {
+            let iat = epoch_seconds();
+
+            Some(keyring.sign(&IdClaims {
+                iss: config.jwt_issuer.to_owned(),
+                sub: user.id.to_string(),
+                aud: client.client_id.to_owned(),
+                iat,
+                exp: iat + ACCESS_TOKEN_TTL,
+                nonce,
+                user: ResUserInfo::new(&user, &scopes),
+            })?)
+        }
+        false => None,
+    };
+
+    Ok(ResToken {
+        access_token: access_token(config, keyring, &client, user.id.to_string(), &scope)?,
+        token_type: "Bearer".to_string(),
+        expires_in: ACCESS_TOKEN_TTL,
+        scope,
+        id_token,
+    })
+}
+
+/// The token endpoint, for the `authorization_code` and
+/// `client_credentials` grants. Takes form-encoded requests as RFC 6749
+/// requires.
+#[post("/token", data = "<req_token>")]
+pub async fn token(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    keyring: &State<Keyring>,
+    basic: ClientBasicAuth,
+    req_token: Form<ReqToken>,
+) -> Result<Json<ResToken>, OAuthError> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let client = authenticate_client(db, basic, &req_token).await?;
+
+    match req_token.grant_type.as_str() {
+        "authorization_code" => Ok(Json(
+            exchange_code(db, config, keyring, client, &req_token).await?,
+        )),
+        "client_credentials" => {
+            if client.secret_hash.is_none() {
+                return Err(OAuthError::new(
+                    Status::BadRequest,
+                    "unauthorized_client",
+                    "Public clients cannot use client credentials",
+                ));
+            }
+
+            // The user scopes mean nothing without a user.
+            if req_token
+                .scope
+                .as_deref()
+                .is_some_and(|s| !s.trim().is_empty())
+            {
+                return Err(OAuthError::new(
+                    Status::BadRequest,
+                    "invalid_scope",
+                    "No scopes are available to client credentials",
+                ));
+            }
+
+            Ok(Json(ResToken {
+                access_token: access_token(
+                    config,
+                    keyring,
+                    &client,
+                    client.client_id.to_owned(),
+                    "",
+                )?,
+                token_type: "Bearer".to_string(),
+                expires_in: ACCESS_TOKEN_TTL,
+                scope: String::new(),
+                id_token: None,
+            }))
+        }
+        _ => Err(OAuthError::new(
+            Status::BadRequest,
+            "unsupported_grant_type",
+            "Only authorization_code and client_credentials are supported",
+        )),
+    }
+}
+
+#[get("/userinfo")]
+pub async fn userinfo(
+    db: &State<DatabaseConnection>,
+    oauth_user: OAuthUser,
+) -> Response<Json<ResUserInfo>> {
+    let db = db as &DatabaseConnection;
+
+    let user = match User::find_by_id(oauth_user.id).one(db).await? {
+        Some(u) => u,
+        None => {
+            return Err(ErrorResponse((
+                Status::Unauthorized,
+                Json(GenericResponse {
+                    message: "The user no longer exists".to_string(),
+                }),
+            )))
+        }
+    };
+
+    let scopes = oauth_user.scope.split(' ').collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResUserInfo::new(&user, &scopes)),
+    )))
+}
+
+#[get("/")]
+pub async fn clients(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+) -> Response<Json<ResOAuthClientList>> {
+    let db = db as &DatabaseConnection;
+
+    let clients = OauthClient::find()
+        .order_by_asc(oauth_client::Column::Name)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResOAuthClient::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResOAuthClientList {
+            total: clients.len(),
+            clients,
+        }),
+    )))
+}
+
+/// Registers an app that can sign users in with their bookstore account.
+#[post("/", data = "<req_client>")]
+pub async fn register_client(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    req_client: Json<ReqOAuthClient>,
+) -> Response<Json<ResNewOAuthClient>> {
+    let db = db as &DatabaseConnection;
+
+    let name = req_client.name.trim();
+
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(unprocessable("name must be between 1 and 100 characters"));
+    }
+
+    if req_client.redirect_uris.is_empty()
+        || req_client
+            .redirect_uris
+            .iter()
+            .any(|u| Absolute::parse(u).is_err() || u.contains('#'))
+    {
+        return Err(unprocessable(
+            "redirect_uris must be absolute URIs without a fragment",
+        ));
+    }
+
+    let client_secret = req_client.confidential.then(|| keys::random_string(48));
+
+    let client = oauth_client::ActiveModel {
+        client_id: Set(keys::random_string(24)),
+        name: Set(name.to_string()),
+        secret_hash: Set(client_secret.as_deref().map(keys::hash)),
+        redirect_uris: Set(req_client.redirect_uris.join("\n")),
+        created_at: Set(Some(DateTimeUtc::from(SystemTime::now()))),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResNewOAuthClient {
+            client_secret,
+            client: ResOAuthClient::from(&client),
+        }),
+    )))
+}
+
+/// Removing a client also removes the consents given to it. Tokens it holds
+/// stay valid until they expire.
+#[delete("/<id>")]
+pub async fn remove_client(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let client = match OauthClient::find_by_id(id).one(db).await? {
+        Some(c) => c,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find a client with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    client.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Client removed".to_string(),
+        }),
+    )))
+}
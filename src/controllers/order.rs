@@ -0,0 +1,615 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Decimal},
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, LoaderTrait, ModelTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+
+use crate::{
+    auth::{AdminUser, AuthenticatedUser},
+    entities::{
+        cart, cart_item, order, order_line,
+        prelude::*,
+        promotion_redemption,
+        sea_orm_active_enums::{OrderStatus, TaxCategory},
+    },
+    payments::{Charge, PaymentProvider},
+    promotions,
+    storage::BlobStorage,
+};
+
+use super::{
+    invoice, stock,
+    tax_rate::{self, normalize_region},
+    ErrorResponse, GenericResponse, Response, SuccessResponse,
+};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResOrderLine {
+    book_id: Option<i32>,
+    title: String,
+    quantity: i32,
+    unit_price: Decimal,
+    line_total: Decimal,
+    discount: Decimal,
+    tax_category: Option<TaxCategory>,
+    tax_percent: Option<Decimal>,
+    tax_amount: Decimal,
+}
+
+impl From<&order_line::Model> for ResOrderLine {
+    fn from(l: &order_line::Model) -> Self {
+        Self {
+            book_id: l.book_id,
+            title: l.title.to_owned(),
+            quantity: l.quantity,
+            unit_price: l.unit_price,
+            line_total: l.line_total,
+            discount: l.discount,
+            tax_category: l.tax_category,
+            tax_percent: l.tax_percent,
+            tax_amount: l.tax_amount,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResOrder {
+    id: i32,
+    status: OrderStatus,
+    currency: String,
+    subtotal: Decimal,
+    discount_total: Decimal,
+    tax_total: Decimal,
+    total: Decimal,
+    region: Option<String>,
+    payment_reference: Option<String>,
+    created_at: Option<DateTimeUtc>,
+    updated_at: Option<DateTimeUtc>,
+    lines: Vec<ResOrderLine>,
+}
+
+impl ResOrder {
+    async fn load(db: &DatabaseConnection, orders: Vec<order::Model>) -> Result<Vec<Self>, DbErr> {
+        let lines = orders.load_many(OrderLine, db).await?;
+
+        Ok(orders
+            .into_iter()
+            .zip(lines)
+            .map(|(o, lines)| Self {
+                id: o.id,
+                status: o.status,
+                currency: o.currency,
+                subtotal: o.subtotal,
+                discount_total: o.discount_total,
+                tax_total: o.tax_total,
+                total: o.total,
+                region: o.region,
+                payment_reference: o.payment_reference,
+                created_at: o.created_at,
+                updated_at: o.updated_at,
+                lines: lines.iter().map(ResOrderLine::from).collect(),
+            })
+            .collect())
+    }
+
+    async fn load_one(db: &DatabaseConnection, order: order::Model) -> Result<Self, DbErr> {
+        Ok(Self::load(db, vec![order]).await?.remove(0))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResOrderList {
+    total: usize,
+    orders: Vec<ResOrder>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqCheckout {
+    payment_token: String,
+    /// Where the order is delivered, which decides the taxes on it.
+    region: String,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqOrderStatus {
+    status: OrderStatus,
+}
+
+/// The statuses an order may move to from `from`.
+fn next_statuses(from: OrderStatus) -> &'static [OrderStatus] {
+    use OrderStatus::*;
+
+    match from {
+        Pending => &[Paid, Cancelled],
+        Paid => &[Shipped, Cancelled, Refunded],
+        Shipped => &[Delivered, Refunded],
+        Delivered => &[Refunded],
+        Cancelled | Refunded => &[],
+    }
+}
+
+fn unprocessable(message: &str) -> ErrorResponse {
+    ErrorResponse((
+        Status::UnprocessableEntity,
+        Json(GenericResponse {
+            message: message.to_string(),
+        }),
+    ))
+}
+
+async fn find_order(
+    db: &DatabaseConnection,
+    id: i32,
+    user_id: Option<i32>,
+) -> Result<order::Model, ErrorResponse> {
+    let mut query = Order::find_by_id(id);
+
+    if let Some(user_id) = user_id {
+        query = query.filter(order::Column::UserId.eq(user_id));
+    }
+
+    match query.one(db).await? {
+        Some(o) => Ok(o),
+        None => Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find order with specified ID".to_string(),
+            }),
+        ))),
+    }
+}
+
+/// Moves an order to `to`, returning stock that never left the warehouse
+/// and refunding the payment where that applies. The refund only happens
+/// once the new status is stored, so a failed write never refunds an order
+/// that still looks paid.
+async fn transition(
+    db: &DatabaseConnection,
+    payments: &dyn PaymentProvider,
+    order: order::Model,
+    to: OrderStatus,
+    user_id: i32,
+) -> Result<order::Model, ErrorResponse> {
+    let txn = db.begin().await?;
+
+    // Re-read under lock, so two requests cannot both move the order on.
+    let order = match Order::find_by_id(order.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+    {
+        Some(o) => o,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find order with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+    let from = order.status;
+
+    if !next_statuses(from).contains(&to) {
+        return Err(unprocessable(&format!(
+            "Cannot move a {} order to {}",
+            format!("{:?}", from).to_lowercase(),
+            format!("{:?}", to).to_lowercase()
+        )));
+    }
+
+    if to == OrderStatus::Paid {
+        return Err(unprocessable(
+            "Orders are marked paid by the payment provider",
+        ));
+    }
+
+    if matches!(to, OrderStatus::Cancelled | OrderStatus::Refunded)
+        && matches!(from, OrderStatus::Pending | OrderStatus::Paid)
+    {
+        stock::release(&txn, order.id, user_id).await?;
+    }
+
+    // A cancelled order does not use up its promotions.
+    if to == OrderStatus::Cancelled {
+        PromotionRedemption::delete_many()
+            .filter(promotion_redemption::Column::OrderId.eq(order.id))
+            .exec(&txn)
+            .await?;
+    }
+
+    let mut order: order::ActiveModel = order.into();
+    order.status = Set(to);
+    order.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    let order = order.update(&txn).await?;
+
+    txn.commit().await?;
+
+    if matches!(to, OrderStatus::Cancelled | OrderStatus::Refunded) {
+        if let Some(reference) = &order.payment_reference {
+            if let Err(e) = payments.refund(reference, order.total).await {
+                return Err(ErrorResponse((
+                    Status::BadGateway,
+                    Json(GenericResponse {
+                        message: format!(
+                            "Order #{} is now {:?} but the refund failed and must be \
+                             retried with the payment provider: {}",
+                            order.id,
+                            format!("{:?}", to).to_lowercase(),
+                            e.0
+                        ),
+                    }),
+                )));
+            }
+        }
+    }
+
+    Ok(order)
+}
+
+#[post("/checkout", data = "<req_checkout>")]
+pub async fn checkout(
+    db: &State<DatabaseConnection>,
+    payments: &State<Box<dyn PaymentProvider>>,
+    storage: &State<Box<dyn BlobStorage>>,
+    user: AuthenticatedUser,
+    req_checkout: Json<ReqCheckout>,
+) -> Response<Json<ResOrder>> {
+    let db = db as &DatabaseConnection;
+    let user_id = user.id as i32;
+
+    let region = match normalize_region(&req_checkout.region) {
+        Some(r) => r,
+        None => {
+            return Err(unprocessable(
+                "region must be an ISO 3166 country or subdivision code",
+            ))
+        }
+    };
+
+    // Stock is taken as part of placing the order, so two customers cannot
+    // both buy the last copy. The cart and promotions are locked for the
+    // same reason.
+    let txn = db.begin().await?;
+
+    let cart = Cart::find()
+        .filter(cart::Column::UserId.eq(user_id))
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+
+    let cart = match cart {
+        Some(c) => c,
+        None => return Err(unprocessable("The cart is empty")),
+    };
+
+    // The cart is only emptied once the payment goes through, so a pending
+    // order means another checkout of this cart is under way.
+    if Order::find()
+        .filter(order::Column::UserId.eq(user_id))
+        .filter(order::Column::Status.eq(OrderStatus::Pending))
+        .one(&txn)
+        .await?
+        .is_some()
+    {
+        return Err(ErrorResponse((
+            Status::Conflict,
+            Json(GenericResponse {
+                message: "A checkout is already in progress; cancel its pending order to \
+                          start again"
+                    .to_string(),
+            }),
+        )));
+    }
+
+    let items = cart
+        .find_related(CartItem)
+        .find_also_related(Book)
+        .order_by_asc(cart_item::Column::Id)
+        .all(&txn)
+        .await?;
+
+    if items.is_empty() {
+        return Err(unprocessable("The cart is empty"));
+    }
+
+    let lines = promotions::lines(&txn, &items).await?;
+    let evaluation = promotions::evaluate(
+        &txn,
+        &lines,
+        &cart.currency,
+        Some(user_id),
+        cart.coupon_code.as_deref(),
+        true,
+    )
+    .await?;
+
+    let items = items
+        .iter()
+        .filter_map(|(i, b)| b.as_ref().map(|b| (i, b)))
+        .collect::<Vec<_>>();
+
+    // Discounts are spread over the lines by value, so each line is taxed on
+    // what the customer actually pays for it.
+    let mut discounts = vec![];
+    let mut allocated = Decimal::ZERO;
+
+    for (n, (item, _)) in items.iter().enumerate() {
+        let discount = if n + 1 == items.len() {
+            evaluation.discount - allocated
+        } else if evaluation.subtotal.is_zero() {
+            Decimal::ZERO
+        } else {
+            (evaluation.discount * item.unit_price * Decimal::from(item.quantity)
+                / evaluation.subtotal)
+                .round_dp(2)
+        };
+
+        allocated += discount;
+        discounts.push(discount);
+    }
+
+    let mut taxes = vec![];
+
+    for ((item, book), discount) in items.iter().zip(&discounts) {
+        let category = tax_rate::category(book);
+        let percent = tax_rate::find_rate(&txn, &region, category)
+            .await?
+            .map(|r| r.percent);
+        let amount = item.unit_price * Decimal::from(item.quantity) - discount;
+
+        taxes.push((
+            category,
+            percent,
+            percent.map_or(Decimal::ZERO, |p| tax_rate::tax(amount, p)),
+        ));
+    }
+
+    let tax_total = taxes.iter().map(|(_, _, t)| *t).sum::<Decimal>();
+
+    let order = order::ActiveModel {
+        user_id: Set(user_id),
+        status: Set(OrderStatus::Pending),
+        currency: Set(cart.currency.to_owned()),
+        subtotal: Set(evaluation.subtotal),
+        discount_total: Set(evaluation.discount),
+        region: Set(Some(region)),
+        tax_total: Set(tax_total),
+        total: Set(evaluation.total + tax_total),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    for applied in &evaluation.applied {
+        promotion_redemption::ActiveModel {
+            promotion_id: Set(applied.promotion_id),
+            user_id: Set(user_id),
+            order_id: Set(order.id),
+            discount: Set(applied.discount),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+    }
+
+    for (((item, book), discount), (category, percent, tax)) in
+        items.iter().zip(discounts).zip(taxes)
+    {
+        stock::reserve(&txn, book, item.quantity, order.id, user_id).await?;
+
+        order_line::ActiveModel {
+            order_id: Set(order.id),
+            book_id: Set(Some(book.id)),
+            title: Set(book.title.to_owned()),
+            quantity: Set(item.quantity),
+            unit_price: Set(item.unit_price),
+            line_total: Set(item.unit_price * Decimal::from(item.quantity)),
+            discount: Set(discount),
+            tax_category: Set(Some(category)),
+            tax_percent: Set(percent),
+            tax_amount: Set(tax),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+    }
+
+    txn.commit().await?;
+
+    let (order_id, total) = (order.id, order.total);
+
+    let charge = payments
+        .charge(Charge {
+            order_id: order.id,
+            amount: order.total,
+            currency: &order.currency,
+            payment_token: &req_checkout.payment_token,
+        })
+        .await;
+
+    let reference = match charge {
+        Ok(r) => r,
+        Err(e) => {
+            // The cart is kept so the customer can try again.
+            transition(
+                db,
+                payments.inner().as_ref(),
+                order,
+                OrderStatus::Cancelled,
+                user_id,
+            )
+            .await?;
+
+            return Err(ErrorResponse((
+                Status::PaymentRequired,
+                Json(GenericResponse { message: e.0 }),
+            )));
+        }
+    };
+
+    let txn = db.begin().await?;
+
+    // The customer may have cancelled the order while it was being charged,
+    // which already gave its stock and promotions back. The charge is then
+    // returned rather than reviving the order.
+    let order = Order::find_by_id(order.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .filter(|o| o.status == OrderStatus::Pending);
+
+    let order = match order {
+        Some(o) => o,
+        None => {
+            txn.rollback().await?;
+
+            let (status, message) = match payments.refund(&reference, total).await {
+                Ok(()) => (
+                    Status::Conflict,
+                    format!(
+                        "Order #{} was cancelled during payment, the charge has been refunded",
+                        order_id
+                    ),
+                ),
+                Err(e) => (
+                    Status::BadGateway,
+                    format!(
+                        "Order #{} was cancelled during payment but the refund failed and \
+                         must be retried with the payment provider: {}",
+                        order_id, e.0
+                    ),
+                ),
+            };
+
+            return Err(ErrorResponse((status, Json(GenericResponse { message }))));
+        }
+    };
+
+    let mut order: order::ActiveModel = order.into();
+    order.status = Set(OrderStatus::Paid);
+    order.payment_reference = Set(Some(reference));
+    order.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    let order = order.update(&txn).await?;
+
+    cart.delete(&txn).await?;
+
+    txn.commit().await?;
+
+    // The order is paid either way; a failed invoice is issued again when
+    // it is first downloaded.
+    if let Err(ErrorResponse((_, e))) = invoice::issue(db, storage.inner().as_ref(), &order).await {
+        eprintln!(
+            "Could not issue invoice for order #{}: {}",
+            order.id, e.message
+        );
+    }
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResOrder::load_one(db, order).await?),
+    )))
+}
+
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<ResOrderList>> {
+    let db = db as &DatabaseConnection;
+
+    let orders = Order::find()
+        .filter(order::Column::UserId.eq(user.id as i32))
+        .order_by_desc(order::Column::Id)
+        .all(db)
+        .await?;
+    let orders = ResOrder::load(db, orders).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResOrderList {
+            total: orders.len(),
+            orders,
+        }),
+    )))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResOrder>> {
+    let db = db as &DatabaseConnection;
+
+    let order = find_order(db, id, Some(user.id as i32)).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResOrder::load_one(db, order).await?),
+    )))
+}
+
+#[post("/<id>/cancel")]
+pub async fn cancel(
+    db: &State<DatabaseConnection>,
+    payments: &State<Box<dyn PaymentProvider>>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResOrder>> {
+    let db = db as &DatabaseConnection;
+
+    let order = find_order(db, id, Some(user.id as i32)).await?;
+    let order = transition(
+        db,
+        payments.inner().as_ref(),
+        order,
+        OrderStatus::Cancelled,
+        user.id as i32,
+    )
+    .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResOrder::load_one(db, order).await?),
+    )))
+}
+
+/// Fulfilment: shipping, delivery and refunds of any customer's order.
+#[put("/<id>/status", data = "<req_status>")]
+pub async fn update_status(
+    db: &State<DatabaseConnection>,
+    payments: &State<Box<dyn PaymentProvider>>,
+    admin: AdminUser,
+    id: i32,
+    req_status: Json<ReqOrderStatus>,
+) -> Response<Json<ResOrder>> {
+    let db = db as &DatabaseConnection;
+
+    let order = find_order(db, id, None).await?;
+    let order = transition(
+        db,
+        payments.inner().as_ref(),
+        order,
+        req_status.status,
+        admin.id as i32,
+    )
+    .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResOrder::load_one(db, order).await?),
+    )))
+}
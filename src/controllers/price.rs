@@ -0,0 +1,303 @@
+use std::{str::FromStr, time::SystemTime};
+
+use rocket::{
+    form::{self, FromFormField, ValueField},
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Decimal},
+    sea_query::{Expr, SimpleExpr},
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, ModelTrait,
+    QueryFilter, QueryOrder, Set, TryIntoModel,
+};
+
+use crate::{
+    auth::{AdminUser, AuthenticatedUser},
+    entities::{prelude::*, price},
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResPrice {
+    currency: String,
+    list_price: Decimal,
+    sale_price: Option<Decimal>,
+    sale_starts_at: Option<DateTimeUtc>,
+    sale_ends_at: Option<DateTimeUtc>,
+    price: Decimal,
+    on_sale: bool,
+}
+
+impl From<&price::Model> for ResPrice {
+    fn from(p: &price::Model) -> Self {
+        let now = DateTimeUtc::from(SystemTime::now());
+
+        Self {
+            currency: p.currency.to_owned(),
+            list_price: p.list_price,
+            sale_price: p.sale_price,
+            sale_starts_at: p.sale_starts_at,
+            sale_ends_at: p.sale_ends_at,
+            price: current_price(p, now),
+            on_sale: on_sale(p, now),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResPriceList {
+    total: usize,
+    prices: Vec<ResPrice>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqPrice {
+    list_price: Decimal,
+    sale_price: Option<Decimal>,
+    sale_starts_at: Option<DateTimeUtc>,
+    sale_ends_at: Option<DateTimeUtc>,
+}
+
+/// A decimal amount given in a query string.
+pub struct Amount(pub Decimal);
+
+#[rocket::async_trait]
+impl<'v> FromFormField<'v> for Amount {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        Decimal::from_str(field.value)
+            .map(Amount)
+            .map_err(|_| form::Error::validation("expected a decimal amount").into())
+    }
+}
+
+/// Whether the sale price applies at `now`.
+pub fn on_sale(p: &price::Model, now: DateTimeUtc) -> bool {
+    p.sale_price.is_some()
+        && p.sale_starts_at.is_none_or(|s| s <= now)
+        && p.sale_ends_at.is_none_or(|e| e > now)
+}
+
+/// The price a customer pays at `now`.
+pub fn current_price(p: &price::Model, now: DateTimeUtc) -> Decimal {
+    match p.sale_price {
+        Some(sale) if on_sale(p, now) => sale,
+        _ => p.list_price,
+    }
+}
+
+/// SQL counterpart of [`current_price`], for filtering and sorting.
+pub fn current_price_expr(now: DateTimeUtc) -> SimpleExpr {
+    Expr::case(
+        Condition::all()
+            .add(price::Column::SalePrice.is_not_null())
+            .add(
+                Condition::any()
+                    .add(price::Column::SaleStartsAt.is_null())
+                    .add(price::Column::SaleStartsAt.lte(now)),
+            )
+            .add(
+                Condition::any()
+                    .add(price::Column::SaleEndsAt.is_null())
+                    .add(price::Column::SaleEndsAt.gt(now)),
+            ),
+        Expr::col((Price, price::Column::SalePrice)),
+    )
+    .finally(Expr::col((Price, price::Column::ListPrice)))
+    .into()
+}
+
+/// Currency codes are ISO 4217, e.g. `USD`.
+pub fn normalize_currency(currency: &str) -> Option<String> {
+    let currency = currency.trim().to_uppercase();
+
+    if currency.len() == 3 && currency.chars().all(|c| c.is_ascii_uppercase()) {
+        Some(currency)
+    } else {
+        None
+    }
+}
+
+impl ReqPrice {
+    fn check(&self) -> Result<(), ErrorResponse> {
+        let unprocessable = |message: &str| {
+            ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: message.to_string(),
+                }),
+            ))
+        };
+
+        let amounts = std::iter::once(self.list_price).chain(self.sale_price);
+
+        for amount in amounts {
+            if amount.is_sign_negative() {
+                return Err(unprocessable("Prices cannot be negative"));
+            }
+
+            if amount.normalize().scale() > 2 {
+                return Err(unprocessable(
+                    "Prices cannot have more than 2 decimal places",
+                ));
+            }
+        }
+
+        if self.sale_price.is_some_and(|s| s > self.list_price) {
+            return Err(unprocessable("sale_price cannot exceed list_price"));
+        }
+
+        if self.sale_price.is_none()
+            && (self.sale_starts_at.is_some() || self.sale_ends_at.is_some())
+        {
+            return Err(unprocessable("A sale window requires a sale_price"));
+        }
+
+        if let (Some(start), Some(end)) = (self.sale_starts_at, self.sale_ends_at) {
+            if start >= end {
+                return Err(unprocessable("sale_ends_at must be after sale_starts_at"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn find_book(db: &DatabaseConnection, id: i32) -> Result<(), ErrorResponse> {
+    if Book::find_by_id(id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find book with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_currency(currency: &str) -> Result<String, ErrorResponse> {
+    normalize_currency(currency).ok_or_else(|| {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Currency must be a 3-letter ISO 4217 code".to_string(),
+            }),
+        ))
+    })
+}
+
+#[get("/<id>/prices")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResPriceList>> {
+    let db = db as &DatabaseConnection;
+
+    find_book(db, id).await?;
+
+    let prices = Price::find()
+        .filter(price::Column::BookId.eq(id))
+        .order_by_asc(price::Column::Currency)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResPrice::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResPriceList {
+            total: prices.len(),
+            prices,
+        }),
+    )))
+}
+
+#[put("/<id>/prices/<currency>", data = "<req_price>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+    currency: &str,
+    req_price: Json<ReqPrice>,
+) -> Response<Json<ResPrice>> {
+    let db = db as &DatabaseConnection;
+
+    find_book(db, id).await?;
+    let currency = check_currency(currency)?;
+    req_price.check()?;
+
+    let existing = Price::find()
+        .filter(price::Column::BookId.eq(id))
+        .filter(price::Column::Currency.eq(&currency))
+        .one(db)
+        .await?;
+
+    let (mut price, status): (price::ActiveModel, Status) = match existing {
+        Some(p) => (p.into(), Status::Ok),
+        None => (
+            price::ActiveModel {
+                book_id: Set(id),
+                currency: Set(currency),
+                ..Default::default()
+            },
+            Status::Created,
+        ),
+    };
+
+    price.list_price = Set(req_price.list_price);
+    price.sale_price = Set(req_price.sale_price);
+    price.sale_starts_at = Set(req_price.sale_starts_at);
+    price.sale_ends_at = Set(req_price.sale_ends_at);
+    price.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let price = price.save(db).await?.try_into_model()?;
+
+    Ok(SuccessResponse((status, Json(ResPrice::from(&price)))))
+}
+
+#[delete("/<id>/prices/<currency>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+    currency: &str,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let currency = check_currency(currency)?;
+
+    let price = Price::find()
+        .filter(price::Column::BookId.eq(id))
+        .filter(price::Column::Currency.eq(&currency))
+        .one(db)
+        .await?;
+
+    let price = match price {
+        Some(p) => p,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find a price in that currency".to_string(),
+                }),
+            )))
+        }
+    };
+
+    price.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Price deleted".to_string(),
+        }),
+    )))
+}
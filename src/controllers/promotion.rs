@@ -0,0 +1,383 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Decimal},
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+
+use crate::{
+    auth::{AdminUser, AuthenticatedUser},
+    entities::{prelude::*, promotion, promotion_redemption, sea_orm_active_enums::PromotionKind},
+};
+
+use super::{price::normalize_currency, ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+/// The most items a buy-X-get-Y promotion may ask for on either side.
+const MAX_GROUP_QUANTITY: i32 = 100;
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResPromotion {
+    id: i32,
+    name: String,
+    code: Option<String>,
+    kind: PromotionKind,
+    value: Option<Decimal>,
+    currency: Option<String>,
+    buy_quantity: Option<i32>,
+    get_quantity: Option<i32>,
+    author_id: Option<i32>,
+    genre_id: Option<i32>,
+    starts_at: Option<DateTimeUtc>,
+    ends_at: Option<DateTimeUtc>,
+    usage_limit: Option<i32>,
+    per_user_limit: Option<i32>,
+    active: bool,
+    redemptions: u64,
+}
+
+impl ResPromotion {
+    async fn load(db: &DatabaseConnection, p: promotion::Model) -> Result<Self, sea_orm::DbErr> {
+        let redemptions = PromotionRedemption::find()
+            .filter(promotion_redemption::Column::PromotionId.eq(p.id))
+            .count(db)
+            .await?;
+
+        Ok(Self {
+            id: p.id,
+            name: p.name,
+            code: p.code,
+            kind: p.kind,
+            value: p.value,
+            currency: p.currency,
+            buy_quantity: p.buy_quantity,
+            get_quantity: p.get_quantity,
+            author_id: p.author_id,
+            genre_id: p.genre_id,
+            starts_at: p.starts_at,
+            ends_at: p.ends_at,
+            usage_limit: p.usage_limit,
+            per_user_limit: p.per_user_limit,
+            active: p.active,
+            redemptions,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResPromotionList {
+    total: usize,
+    promotions: Vec<ResPromotion>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqPromotion {
+    name: String,
+    code: Option<String>,
+    kind: PromotionKind,
+    /// A percentage for `percent`, an amount for `fixed`.
+    value: Option<Decimal>,
+    currency: Option<String>,
+    buy_quantity: Option<i32>,
+    get_quantity: Option<i32>,
+    author_id: Option<i32>,
+    genre_id: Option<i32>,
+    starts_at: Option<DateTimeUtc>,
+    ends_at: Option<DateTimeUtc>,
+    usage_limit: Option<i32>,
+    per_user_limit: Option<i32>,
+    #[serde(default = "default_active")]
+    active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// Coupon codes are matched case-insensitively and stored upper case.
+pub fn normalize_code(code: &str) -> Option<String> {
+    let code = code.trim().to_uppercase();
+
+    if !code.is_empty()
+        && code.len() <= 32
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Some(code)
+    } else {
+        None
+    }
+}
+
+impl ReqPromotion {
+    /// Validates the request, returning the normalized code and currency.
+    async fn check(
+        &self,
+        db: &DatabaseConnection,
+        id: Option<i32>,
+    ) -> Result<(Option<String>, Option<String>), ErrorResponse> {
+        let unprocessable = |message: &str| {
+            ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: message.to_string(),
+                }),
+            ))
+        };
+
+        let code = match &self.code {
+            Some(c) => Some(normalize_code(c).ok_or_else(|| {
+                unprocessable("code may only contain letters, digits, '-' and '_'")
+            })?),
+            None => None,
+        };
+
+        if let Some(code) = &code {
+            let mut taken = Promotion::find().filter(promotion::Column::Code.eq(code));
+
+            if let Some(id) = id {
+                taken = taken.filter(promotion::Column::Id.ne(id));
+            }
+
+            if taken.one(db).await?.is_some() {
+                return Err(unprocessable("Another promotion uses that code"));
+            }
+        }
+
+        let currency = match &self.currency {
+            Some(c) => Some(
+                normalize_currency(c)
+                    .ok_or_else(|| unprocessable("Currency must be a 3-letter ISO 4217 code"))?,
+            ),
+            None => None,
+        };
+
+        match self.kind {
+            PromotionKind::Percent => {
+                if !self
+                    .value
+                    .is_some_and(|v| v > Decimal::ZERO && v <= Decimal::from(100))
+                {
+                    return Err(unprocessable(
+                        "A percent promotion needs a value between 0 and 100",
+                    ));
+                }
+            }
+            PromotionKind::Fixed => {
+                if self.value.is_none_or(|v| v <= Decimal::ZERO) || currency.is_none() {
+                    return Err(unprocessable(
+                        "A fixed promotion needs a positive value and a currency",
+                    ));
+                }
+            }
+            PromotionKind::BuyXGetY => {
+                let in_range =
+                    |q: Option<i32>| q.is_some_and(|q| (1..=MAX_GROUP_QUANTITY).contains(&q));
+
+                if !in_range(self.buy_quantity) || !in_range(self.get_quantity) {
+                    return Err(unprocessable(&format!(
+                        "A buy_x_get_y promotion needs buy_quantity and get_quantity between 1 and {}",
+                        MAX_GROUP_QUANTITY
+                    )));
+                }
+            }
+        }
+
+        if self.usage_limit.is_some_and(|l| l < 1) || self.per_user_limit.is_some_and(|l| l < 1) {
+            return Err(unprocessable("Usage limits must be at least 1"));
+        }
+
+        if let (Some(start), Some(end)) = (self.starts_at, self.ends_at) {
+            if start >= end {
+                return Err(unprocessable("ends_at must be after starts_at"));
+            }
+        }
+
+        if let Some(author_id) = self.author_id {
+            if Author::find_by_id(author_id).one(db).await?.is_none() {
+                return Err(unprocessable("Cannot find author with specified ID"));
+            }
+        }
+
+        if let Some(genre_id) = self.genre_id {
+            if Genre::find_by_id(genre_id).one(db).await?.is_none() {
+                return Err(unprocessable("Cannot find genre with specified ID"));
+            }
+        }
+
+        Ok((code, currency))
+    }
+
+    fn apply(
+        &self,
+        p: &mut promotion::ActiveModel,
+        code: Option<String>,
+        currency: Option<String>,
+    ) {
+        p.name = Set(self.name.to_owned());
+        p.code = Set(code);
+        p.kind = Set(self.kind);
+        p.value = Set(self.value);
+        p.currency = Set(currency);
+        p.buy_quantity = Set(self.buy_quantity);
+        p.get_quantity = Set(self.get_quantity);
+        p.author_id = Set(self.author_id);
+        p.genre_id = Set(self.genre_id);
+        p.starts_at = Set(self.starts_at);
+        p.ends_at = Set(self.ends_at);
+        p.usage_limit = Set(self.usage_limit);
+        p.per_user_limit = Set(self.per_user_limit);
+        p.active = Set(self.active);
+    }
+}
+
+async fn find_promotion(
+    db: &DatabaseConnection,
+    id: i32,
+) -> Result<promotion::Model, ErrorResponse> {
+    match Promotion::find_by_id(id).one(db).await? {
+        Some(p) => Ok(p),
+        None => Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find promotion with specified ID".to_string(),
+            }),
+        ))),
+    }
+}
+
+/// Promotions and their coupon codes are managed by admins; customers only
+/// see the ones that apply to their cart.
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+) -> Response<Json<ResPromotionList>> {
+    let db = db as &DatabaseConnection;
+
+    let mut promotions = vec![];
+
+    for p in Promotion::find()
+        .order_by_desc(promotion::Column::Id)
+        .all(db)
+        .await?
+    {
+        promotions.push(ResPromotion::load(db, p).await?);
+    }
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResPromotionList {
+            total: promotions.len(),
+            promotions,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_promotion>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    user: AuthenticatedUser,
+    req_promotion: Json<ReqPromotion>,
+) -> Response<Json<ResPromotion>> {
+    let db = db as &DatabaseConnection;
+
+    let (code, currency) = req_promotion.check(db, None).await?;
+
+    let mut promotion = promotion::ActiveModel {
+        user_id: Set(user.id as i32),
+        ..Default::default()
+    };
+    req_promotion.apply(&mut promotion, code, currency);
+
+    let promotion = promotion.insert(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResPromotion::load(db, promotion).await?),
+    )))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+) -> Response<Json<ResPromotion>> {
+    let db = db as &DatabaseConnection;
+
+    let promotion = find_promotion(db, id).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResPromotion::load(db, promotion).await?),
+    )))
+}
+
+#[put("/<id>", data = "<req_promotion>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+    req_promotion: Json<ReqPromotion>,
+) -> Response<Json<ResPromotion>> {
+    let db = db as &DatabaseConnection;
+
+    let mut promotion: promotion::ActiveModel = find_promotion(db, id).await?.into();
+
+    let (code, currency) = req_promotion.check(db, Some(id)).await?;
+    req_promotion.apply(&mut promotion, code, currency);
+    promotion.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let promotion = promotion.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResPromotion::load(db, promotion).await?),
+    )))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let promotion = find_promotion(db, id).await?;
+
+    // Redeemed promotions are kept so past orders still explain their totals.
+    if PromotionRedemption::find()
+        .filter(promotion_redemption::Column::PromotionId.eq(id))
+        .count(db)
+        .await?
+        > 0
+    {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Promotion has been redeemed, deactivate it instead".to_string(),
+            }),
+        )));
+    }
+
+    promotion.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Promotion deleted".to_string(),
+        }),
+    )))
+}
@@ -0,0 +1,221 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    ModelTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::{
+    auth::{AuthenticatedUser, CatalogueEditor},
+    entities::{imprint, prelude::*, publisher},
+};
+
+use super::{
+    imprint::{ResImprint, ResImprintList},
+    ErrorResponse, GenericResponse, Response, SuccessResponse,
+};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResPublisher {
+    id: i32,
+    name: String,
+    website: Option<String>,
+}
+
+impl From<&publisher::Model> for ResPublisher {
+    fn from(p: &publisher::Model) -> Self {
+        Self {
+            id: p.id,
+            name: p.name.to_owned(),
+            website: p.website.to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResPublisherList {
+    total: usize,
+    publishers: Vec<ResPublisher>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqPublisher {
+    name: String,
+    website: Option<String>,
+}
+
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+) -> Response<Json<ResPublisherList>> {
+    let db = db as &DatabaseConnection;
+
+    let publishers = Publisher::find()
+        .order_by_asc(publisher::Column::Name)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResPublisher::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResPublisherList {
+            total: publishers.len(),
+            publishers,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_publisher>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    user: CatalogueEditor,
+    req_publisher: Json<ReqPublisher>,
+) -> Response<Json<ResPublisher>> {
+    let db = db as &DatabaseConnection;
+
+    let publisher = publisher::ActiveModel {
+        user_id: Set(user.id as i32),
+        name: Set(req_publisher.name.to_owned()),
+        website: Set(req_publisher.website.to_owned()),
+        ..Default::default()
+    };
+
+    let publisher = publisher.insert(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResPublisher::from(&publisher)),
+    )))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResPublisher>> {
+    let db = db as &DatabaseConnection;
+
+    let publisher = Publisher::find_by_id(id).one(db).await?;
+
+    let publisher = match publisher {
+        Some(p) => p,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find publisher with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResPublisher::from(&publisher)),
+    )))
+}
+
+#[get("/<id>/imprints")]
+pub async fn imprints(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResImprintList>> {
+    let db = db as &DatabaseConnection;
+
+    let imprints = Imprint::find()
+        .filter(imprint::Column::PublisherId.eq(id))
+        .order_by_asc(imprint::Column::Name)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResImprint::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResImprintList {
+            total: imprints.len(),
+            imprints,
+        }),
+    )))
+}
+
+#[put("/<id>", data = "<req_publisher>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+    req_publisher: Json<ReqPublisher>,
+) -> Response<Json<ResPublisher>> {
+    let db = db as &DatabaseConnection;
+
+    let publisher = Publisher::find_by_id(id).one(db).await?;
+
+    let mut publisher: publisher::ActiveModel = match publisher {
+        Some(p) => p.into(),
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find publisher with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    publisher.name = Set(req_publisher.name.to_owned());
+    publisher.website = Set(req_publisher.website.to_owned());
+    publisher.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let publisher = publisher.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResPublisher::from(&publisher)),
+    )))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let publisher = Publisher::find_by_id(id).one(db).await?;
+
+    let publisher = match publisher {
+        Some(p) => p,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find publisher with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    publisher.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Publisher deleted".to_string(),
+        }),
+    )))
+}
@@ -0,0 +1,589 @@
+use std::time::SystemTime;
+
+use rand::{distributions::Alphanumeric, Rng};
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, sea_query::OnConflict, ActiveModelTrait, ColumnTrait, ConnectionTrait,
+    DatabaseConnection, DbErr, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
+
+use crate::{
+    auth::AuthenticatedUser,
+    entities::{
+        prelude::*,
+        reading_list, reading_list_item,
+        sea_orm_active_enums::{ReadingListKind, ReadingListPrivacy},
+    },
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+/// Lists every user has, created the first time their lists are fetched.
+const BUILT_IN: [(ReadingListKind, &str); 3] = [
+    (ReadingListKind::WantToRead, "Want to read"),
+    (ReadingListKind::CurrentlyReading, "Currently reading"),
+    (ReadingListKind::Finished, "Finished"),
+];
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResReadingList {
+    id: i32,
+    /// Only shown to the owner, so a shared link does not reveal who made it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_id: Option<i32>,
+    name: String,
+    kind: ReadingListKind,
+    privacy: ReadingListPrivacy,
+    share_token: Option<String>,
+    items: Vec<ResReadingListItem>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResReadingListItem {
+    book_id: i32,
+    title: String,
+    position: i32,
+    note: Option<String>,
+    added_at: Option<DateTimeUtc>,
+}
+
+impl ResReadingList {
+    /// The owner and share token are only shown to the owner of the list.
+    async fn load(
+        db: &DatabaseConnection,
+        list: reading_list::Model,
+        owner: bool,
+    ) -> Result<Self, DbErr> {
+        let items = list
+            .find_related(ReadingListItem)
+            .find_also_related(Book)
+            .order_by_asc(reading_list_item::Column::Position)
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|(item, book)| {
+                Some(ResReadingListItem {
+                    book_id: item.book_id,
+                    title: book?.title,
+                    position: item.position,
+                    note: item.note,
+                    added_at: item.created_at,
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            id: list.id,
+            user_id: owner.then_some(list.user_id),
+            name: list.name,
+            kind: list.kind,
+            privacy: list.privacy,
+            share_token: if owner { list.share_token } else { None },
+            items,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResReadingListList {
+    total: usize,
+    lists: Vec<ResReadingList>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqReadingList {
+    name: String,
+    privacy: Option<ReadingListPrivacy>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqReadingListItem {
+    book_id: i32,
+    note: Option<String>,
+    position: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqReadingListItemUpdate {
+    note: Option<String>,
+    position: Option<i32>,
+}
+
+impl ReqReadingList {
+    fn check(&self) -> Result<String, ErrorResponse> {
+        let name = self.name.trim();
+
+        if name.is_empty() || name.chars().count() > 255 {
+            return Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: "name must be between 1 and 255 characters".to_string(),
+                }),
+            )));
+        }
+
+        Ok(name.to_string())
+    }
+}
+
+fn normalize_note(note: &Option<String>) -> Option<String> {
+    note.as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+}
+
+/// Links are only issued while a list is shared, so making a list private
+/// revokes its link and sharing it again issues a new one.
+fn share_token(privacy: ReadingListPrivacy, current: Option<String>) -> Option<String> {
+    match privacy {
+        ReadingListPrivacy::Private => None,
+        _ => current.or_else(|| {
+            Some(
+                rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(32)
+                    .map(char::from)
+                    .collect::<String>(),
+            )
+        }),
+    }
+}
+
+async fn ensure_built_in(db: &DatabaseConnection, user_id: i32) -> Result<(), DbErr> {
+    // The unique (user_id, built_in_kind) index turns a concurrent second
+    // insert into a no-op instead of a duplicate list.
+    for (kind, name) in BUILT_IN {
+        ReadingList::insert(reading_list::ActiveModel {
+            user_id: Set(user_id),
+            name: Set(name.to_string()),
+            kind: Set(kind),
+            built_in_kind: Set(Some(kind)),
+            privacy: Set(ReadingListPrivacy::Private),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([
+                reading_list::Column::UserId,
+                reading_list::Column::BuiltInKind,
+            ])
+            .update_column(reading_list::Column::BuiltInKind)
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn find_own_list(
+    db: &DatabaseConnection,
+    user_id: i32,
+    id: i32,
+) -> Result<reading_list::Model, ErrorResponse> {
+    let list = ReadingList::find_by_id(id)
+        .filter(reading_list::Column::UserId.eq(user_id))
+        .one(db)
+        .await?;
+
+    match list {
+        Some(l) => Ok(l),
+        None => Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find list with specified ID".to_string(),
+            }),
+        ))),
+    }
+}
+
+async fn find_item(
+    db: &DatabaseConnection,
+    list_id: i32,
+    book_id: i32,
+) -> Result<reading_list_item::Model, ErrorResponse> {
+    let item = ReadingListItem::find()
+        .filter(reading_list_item::Column::ReadingListId.eq(list_id))
+        .filter(reading_list_item::Column::BookId.eq(book_id))
+        .one(db)
+        .await?;
+
+    match item {
+        Some(i) => Ok(i),
+        None => Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "The book is not on this list".to_string(),
+            }),
+        ))),
+    }
+}
+
+/// Moves `book_id` to the 1-based `position` (the end when `None`) and
+/// renumbers the list so positions stay contiguous. A book that is not on
+/// the list only closes the gaps.
+async fn arrange<C: ConnectionTrait>(
+    db: &C,
+    list_id: i32,
+    book_id: i32,
+    position: Option<i32>,
+) -> Result<(), DbErr> {
+    let mut items = ReadingListItem::find()
+        .filter(reading_list_item::Column::ReadingListId.eq(list_id))
+        .order_by_asc(reading_list_item::Column::Position)
+        .order_by_asc(reading_list_item::Column::Id)
+        .lock_exclusive()
+        .all(db)
+        .await?;
+
+    if let Some(i) = items.iter().position(|i| i.book_id == book_id) {
+        let item = items.remove(i);
+        let at = match position {
+            Some(p) => (p.max(1) as usize - 1).min(items.len()),
+            None => items.len(),
+        };
+        items.insert(at, item);
+    }
+
+    for (i, item) in items.into_iter().enumerate() {
+        let position = i as i32 + 1;
+
+        if item.position != position {
+            let mut item: reading_list_item::ActiveModel = item.into();
+            item.position = Set(position);
+            item.update(db).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn touch(db: &DatabaseConnection, list: reading_list::Model) -> Result<(), DbErr> {
+    let mut list: reading_list::ActiveModel = list.into();
+    list.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    list.update(db).await?;
+
+    Ok(())
+}
+
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<ResReadingListList>> {
+    let db = db as &DatabaseConnection;
+
+    ensure_built_in(db, user.id as i32).await?;
+
+    let lists = ReadingList::find()
+        .filter(reading_list::Column::UserId.eq(user.id as i32))
+        .order_by_asc(reading_list::Column::Id)
+        .all(db)
+        .await?;
+
+    let mut res = vec![];
+
+    for list in lists {
+        res.push(ResReadingList::load(db, list, true).await?);
+    }
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReadingListList {
+            total: res.len(),
+            lists: res,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_list>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    req_list: Json<ReqReadingList>,
+) -> Response<Json<ResReadingList>> {
+    let db = db as &DatabaseConnection;
+
+    let name = req_list.check()?;
+    let privacy = req_list.privacy.unwrap_or(ReadingListPrivacy::Private);
+
+    let list = reading_list::ActiveModel {
+        user_id: Set(user.id as i32),
+        name: Set(name),
+        kind: Set(ReadingListKind::Custom),
+        privacy: Set(privacy),
+        share_token: Set(share_token(privacy, None)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResReadingList::load(db, list, true).await?),
+    )))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResReadingList>> {
+    let db = db as &DatabaseConnection;
+
+    let list = find_own_list(db, user.id as i32, id).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReadingList::load(db, list, true).await?),
+    )))
+}
+
+#[put("/<id>", data = "<req_list>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+    req_list: Json<ReqReadingList>,
+) -> Response<Json<ResReadingList>> {
+    let db = db as &DatabaseConnection;
+
+    let list = find_own_list(db, user.id as i32, id).await?;
+    let name = req_list.check()?;
+    let privacy = req_list.privacy.unwrap_or(list.privacy);
+    let token = share_token(privacy, list.share_token.to_owned());
+
+    let mut list: reading_list::ActiveModel = list.into();
+    list.name = Set(name);
+    list.privacy = Set(privacy);
+    list.share_token = Set(token);
+    list.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let list = list.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReadingList::load(db, list, true).await?),
+    )))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let list = find_own_list(db, user.id as i32, id).await?;
+
+    if list.kind != ReadingListKind::Custom {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Built-in lists cannot be deleted".to_string(),
+            }),
+        )));
+    }
+
+    list.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "List deleted".to_string(),
+        }),
+    )))
+}
+
+#[post("/<id>/items", data = "<req_item>")]
+pub async fn add_item(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+    req_item: Json<ReqReadingListItem>,
+) -> Response<Json<ResReadingList>> {
+    let db = db as &DatabaseConnection;
+
+    let list = find_own_list(db, user.id as i32, id).await?;
+
+    if Book::find_by_id(req_item.book_id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find a book with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    let items = list.find_related(ReadingListItem);
+
+    if items
+        .clone()
+        .filter(reading_list_item::Column::BookId.eq(req_item.book_id))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "The book is already on this list".to_string(),
+            }),
+        )));
+    }
+
+    let txn = db.begin().await?;
+
+    reading_list_item::ActiveModel {
+        reading_list_id: Set(list.id),
+        book_id: Set(req_item.book_id),
+        position: Set(items.count(&txn).await? as i32 + 1),
+        note: Set(normalize_note(&req_item.note)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    arrange(&txn, list.id, req_item.book_id, req_item.position).await?;
+
+    txn.commit().await?;
+
+    touch(db, list.to_owned()).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResReadingList::load(db, list, true).await?),
+    )))
+}
+
+/// Replaces the note and, when `position` is given, moves the book.
+#[put("/<id>/items/<book_id>", data = "<req_item>")]
+pub async fn update_item(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+    book_id: i32,
+    req_item: Json<ReqReadingListItemUpdate>,
+) -> Response<Json<ResReadingList>> {
+    let db = db as &DatabaseConnection;
+
+    let list = find_own_list(db, user.id as i32, id).await?;
+    let item = find_item(db, list.id, book_id).await?;
+
+    let txn = db.begin().await?;
+
+    let mut item: reading_list_item::ActiveModel = item.into();
+    item.note = Set(normalize_note(&req_item.note));
+    item.update(&txn).await?;
+
+    if let Some(position) = req_item.position {
+        arrange(&txn, list.id, book_id, Some(position)).await?;
+    }
+
+    txn.commit().await?;
+
+    touch(db, list.to_owned()).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReadingList::load(db, list, true).await?),
+    )))
+}
+
+#[delete("/<id>/items/<book_id>")]
+pub async fn remove_item(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+    book_id: i32,
+) -> Response<Json<ResReadingList>> {
+    let db = db as &DatabaseConnection;
+
+    let list = find_own_list(db, user.id as i32, id).await?;
+    let item = find_item(db, list.id, book_id).await?;
+
+    let txn = db.begin().await?;
+
+    item.delete(&txn).await?;
+    arrange(&txn, list.id, book_id, None).await?;
+
+    txn.commit().await?;
+
+    touch(db, list.to_owned()).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReadingList::load(db, list, true).await?),
+    )))
+}
+
+/// Public lists can be viewed by anyone, without signing in.
+#[get("/<id>")]
+pub async fn public(db: &State<DatabaseConnection>, id: i32) -> Response<Json<ResReadingList>> {
+    let db = db as &DatabaseConnection;
+
+    let list = ReadingList::find_by_id(id)
+        .filter(reading_list::Column::Privacy.eq(ReadingListPrivacy::Public))
+        .one(db)
+        .await?;
+
+    let list = match list {
+        Some(l) => l,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find list with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReadingList::load(db, list, false).await?),
+    )))
+}
+
+/// Lists shared by link or public can be viewed by anyone with the link.
+#[get("/shared/<token>")]
+pub async fn shared(db: &State<DatabaseConnection>, token: &str) -> Response<Json<ResReadingList>> {
+    let db = db as &DatabaseConnection;
+
+    let list = ReadingList::find()
+        .filter(reading_list::Column::ShareToken.eq(token))
+        .filter(reading_list::Column::Privacy.ne(ReadingListPrivacy::Private))
+        .one(db)
+        .await?;
+
+    let list = match list {
+        Some(l) => l,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find a shared list for this link".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReadingList::load(db, list, false).await?),
+    )))
+}
@@ -0,0 +1,360 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Decimal},
+    sea_query::{Expr, Func, SimpleExpr},
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, ModelTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+
+use crate::{
+    auth::{AdminUser, AuthenticatedUser},
+    entities::{
+        prelude::*,
+        review,
+        sea_orm_active_enums::{ReviewStatus, UserRole},
+    },
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResReview {
+    id: i32,
+    user_id: i32,
+    book_id: i32,
+    rating: i16,
+    body: Option<String>,
+    status: ReviewStatus,
+    moderation_note: Option<String>,
+    created_at: Option<DateTimeUtc>,
+    updated_at: Option<DateTimeUtc>,
+}
+
+impl From<&review::Model> for ResReview {
+    fn from(r: &review::Model) -> Self {
+        Self {
+            id: r.id,
+            user_id: r.user_id,
+            book_id: r.book_id,
+            rating: r.rating,
+            body: r.body.to_owned(),
+            status: r.status,
+            moderation_note: r.moderation_note.to_owned(),
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResReviewList {
+    total: usize,
+    reviews: Vec<ResReview>,
+}
+
+impl ResReviewList {
+    fn new(reviews: Vec<review::Model>) -> Self {
+        let reviews = reviews.iter().map(ResReview::from).collect::<Vec<_>>();
+
+        Self {
+            total: reviews.len(),
+            reviews,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqReview {
+    rating: i16,
+    body: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqModeration {
+    status: ReviewStatus,
+    note: Option<String>,
+}
+
+/// Average rating and number of approved reviews of a book.
+pub struct Rating {
+    pub book_id: i32,
+    pub average: Decimal,
+    pub count: i64,
+}
+
+/// Ratings of `book_ids` that have at least one approved review.
+pub async fn ratings(db: &DatabaseConnection, book_ids: Vec<i32>) -> Result<Vec<Rating>, DbErr> {
+    let rows: Vec<(i32, Decimal, i64)> = Review::find()
+        .select_only()
+        .column(review::Column::BookId)
+        .column_as(
+            SimpleExpr::from(Func::avg(Expr::col(review::Column::Rating))),
+            "average",
+        )
+        .column_as(review::Column::Id.count(), "count")
+        .filter(review::Column::BookId.is_in(book_ids))
+        .filter(review::Column::Status.eq(ReviewStatus::Approved))
+        .group_by(review::Column::BookId)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(book_id, average, count)| Rating {
+            book_id,
+            average: average.round_dp(2),
+            count,
+        })
+        .collect())
+}
+
+impl ReqReview {
+    fn check(&self) -> Result<Option<String>, ErrorResponse> {
+        if !(1..=5).contains(&self.rating) {
+            return Err(ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: "rating must be between 1 and 5".to_string(),
+                }),
+            )));
+        }
+
+        Ok(self
+            .body
+            .as_deref()
+            .map(str::trim)
+            .filter(|b| !b.is_empty())
+            .map(str::to_string))
+    }
+}
+
+async fn find_review(db: &DatabaseConnection, id: i32) -> Result<review::Model, ErrorResponse> {
+    match Review::find_by_id(id).one(db).await? {
+        Some(r) => Ok(r),
+        None => Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find review with specified ID".to_string(),
+            }),
+        ))),
+    }
+}
+
+/// Reviews of other users are hidden as if they did not exist.
+async fn find_own_review(
+    db: &DatabaseConnection,
+    user_id: i32,
+    id: i32,
+) -> Result<review::Model, ErrorResponse> {
+    let review = find_review(db, id).await?;
+
+    if review.user_id != user_id {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find review with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    Ok(review)
+}
+
+#[get("/<book_id>/reviews")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    book_id: i32,
+) -> Response<Json<ResReviewList>> {
+    let db = db as &DatabaseConnection;
+
+    let reviews = Review::find()
+        .filter(review::Column::BookId.eq(book_id))
+        .filter(review::Column::Status.eq(ReviewStatus::Approved))
+        .order_by_desc(review::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReviewList::new(reviews)),
+    )))
+}
+
+#[post("/<book_id>/reviews", data = "<req_review>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    book_id: i32,
+    req_review: Json<ReqReview>,
+) -> Response<Json<ResReview>> {
+    let db = db as &DatabaseConnection;
+
+    if Book::find_by_id(book_id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find a book with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    let body = req_review.check()?;
+
+    if Review::find()
+        .filter(review::Column::UserId.eq(user.id as i32))
+        .filter(review::Column::BookId.eq(book_id))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "You have already reviewed this book".to_string(),
+            }),
+        )));
+    }
+
+    let review = review::ActiveModel {
+        user_id: Set(user.id as i32),
+        book_id: Set(book_id),
+        rating: Set(req_review.rating),
+        body: Set(body),
+        status: Set(ReviewStatus::Pending),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResReview::from(&review)),
+    )))
+}
+
+#[get("/mine")]
+pub async fn mine(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<ResReviewList>> {
+    let db = db as &DatabaseConnection;
+
+    let reviews = Review::find()
+        .filter(review::Column::UserId.eq(user.id as i32))
+        .order_by_desc(review::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReviewList::new(reviews)),
+    )))
+}
+
+/// Edited reviews go back to moderation.
+#[put("/<id>", data = "<req_review>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+    req_review: Json<ReqReview>,
+) -> Response<Json<ResReview>> {
+    let db = db as &DatabaseConnection;
+
+    let review = find_own_review(db, user.id as i32, id).await?;
+
+    let body = req_review.check()?;
+
+    let mut review: review::ActiveModel = review.into();
+    review.rating = Set(req_review.rating);
+    review.body = Set(body);
+    review.status = Set(ReviewStatus::Pending);
+    review.moderation_note = Set(None);
+    review.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let review = review.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReview::from(&review)),
+    )))
+}
+
+/// Authors can delete their own reviews, admins any review.
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let review = match user.role {
+        UserRole::Admin => find_review(db, id).await?,
+        UserRole::User => find_own_review(db, user.id as i32, id).await?,
+    };
+
+    review.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Review deleted".to_string(),
+        }),
+    )))
+}
+
+/// The moderation queue, oldest first. Defaults to pending reviews.
+#[get("/?<status>")]
+pub async fn queue(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    status: Option<ReviewStatus>,
+) -> Response<Json<ResReviewList>> {
+    let db = db as &DatabaseConnection;
+
+    let reviews = Review::find()
+        .filter(review::Column::Status.eq(status.unwrap_or(ReviewStatus::Pending)))
+        .order_by_asc(review::Column::UpdatedAt)
+        .all(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReviewList::new(reviews)),
+    )))
+}
+
+/// Leaves `updated_at` alone, which tracks the author's last edit.
+#[put("/<id>/moderation", data = "<req_moderation>")]
+pub async fn moderate(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+    req_moderation: Json<ReqModeration>,
+) -> Response<Json<ResReview>> {
+    let db = db as &DatabaseConnection;
+
+    let mut review: review::ActiveModel = find_review(db, id).await?.into();
+    review.status = Set(req_moderation.status);
+    review.moderation_note = Set(req_moderation.note.to_owned());
+
+    let review = review.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResReview::from(&review)),
+    )))
+}
@@ -0,0 +1,228 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    ModelTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::{
+    auth::{AuthenticatedUser, CatalogueEditor},
+    entities::{book, prelude::*, series},
+};
+
+use super::{
+    book::{ResBook, ResBookList},
+    ErrorResponse, GenericResponse, Response, SuccessResponse,
+};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResSeries {
+    id: i32,
+    title: String,
+    description: Option<String>,
+}
+
+impl From<&series::Model> for ResSeries {
+    fn from(s: &series::Model) -> Self {
+        Self {
+            id: s.id,
+            title: s.title.to_owned(),
+            description: s.description.to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResSeriesList {
+    total: usize,
+    series: Vec<ResSeries>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqSeries {
+    title: String,
+    description: Option<String>,
+}
+
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+) -> Response<Json<ResSeriesList>> {
+    let db = db as &DatabaseConnection;
+
+    let series = Series::find()
+        .order_by_asc(series::Column::Title)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResSeries::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResSeriesList {
+            total: series.len(),
+            series,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_series>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    user: CatalogueEditor,
+    req_series: Json<ReqSeries>,
+) -> Response<Json<ResSeries>> {
+    let db = db as &DatabaseConnection;
+
+    let series = series::ActiveModel {
+        user_id: Set(user.id as i32),
+        title: Set(req_series.title.to_owned()),
+        description: Set(req_series.description.to_owned()),
+        ..Default::default()
+    };
+
+    let series = series.insert(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResSeries::from(&series)),
+    )))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResSeries>> {
+    let db = db as &DatabaseConnection;
+
+    let series = Series::find_by_id(id).one(db).await?;
+
+    let series = match series {
+        Some(s) => s,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find series with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResSeries::from(&series)),
+    )))
+}
+
+#[get("/<id>/books")]
+pub async fn books(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResBookList>> {
+    let db = db as &DatabaseConnection;
+
+    if Series::find_by_id(id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find series with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    let books = Book::find()
+        .filter(book::Column::SeriesId.eq(id))
+        .order_by_asc(book::Column::SeriesPosition)
+        .all(db)
+        .await?;
+    let books = ResBook::load(db, books).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResBookList {
+            total: books.len(),
+            books,
+        }),
+    )))
+}
+
+#[put("/<id>", data = "<req_series>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+    req_series: Json<ReqSeries>,
+) -> Response<Json<ResSeries>> {
+    let db = db as &DatabaseConnection;
+
+    let series = Series::find_by_id(id).one(db).await?;
+
+    let mut series: series::ActiveModel = match series {
+        Some(s) => s.into(),
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find series with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    series.title = Set(req_series.title.to_owned());
+    series.description = Set(req_series.description.to_owned());
+    series.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let series = series.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResSeries::from(&series)),
+    )))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let series = Series::find_by_id(id).one(db).await?;
+
+    let series = match series {
+        Some(s) => s,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find series with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    series.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Series deleted".to_string(),
+        }),
+    )))
+}
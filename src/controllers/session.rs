@@ -0,0 +1,161 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::{CookieJar, Status},
+    serde::{json::Json, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Expr},
+    sea_query::IntoCondition,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+
+use crate::{
+    auth::{remove_session_cookies, AuthenticatedUser},
+    entities::{prelude::*, session},
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResSession {
+    id: i32,
+    device_name: Option<String>,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    /// Whether this is the session the request was made with.
+    current: bool,
+    created_at: Option<DateTimeUtc>,
+    last_seen_at: Option<DateTimeUtc>,
+    expires_at: DateTimeUtc,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResSessionList {
+    total: usize,
+    sessions: Vec<ResSession>,
+}
+
+/// Revokes the active sessions matching `filter`.
+pub async fn revoke_sessions<C: ConnectionTrait>(
+    db: &C,
+    filter: impl IntoCondition,
+) -> Result<u64, DbErr> {
+    let result = Session::update_many()
+        .col_expr(
+            session::Column::RevokedAt,
+            Expr::value(DateTimeUtc::from(SystemTime::now())),
+        )
+        .filter(session::Column::RevokedAt.is_null())
+        .filter(filter)
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected)
+}
+
+/// The user's sessions that are neither revoked nor expired, most recently
+/// seen first.
+#[get("/sessions")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<ResSessionList>> {
+    let db = db as &DatabaseConnection;
+
+    let sessions = Session::find()
+        .filter(session::Column::UserId.eq(user.id as i32))
+        .filter(session::Column::RevokedAt.is_null())
+        .filter(session::Column::ExpiresAt.gt(DateTimeUtc::from(SystemTime::now())))
+        .order_by_desc(session::Column::LastSeenAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|s| ResSession {
+            id: s.id,
+            device_name: s.device_name,
+            ip_address: s.ip_address,
+            user_agent: s.user_agent,
+            current: user.session_id == Some(s.id),
+            created_at: s.created_at,
+            last_seen_at: s.last_seen_at,
+            expires_at: s.expires_at,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResSessionList {
+            total: sessions.len(),
+            sessions,
+        }),
+    )))
+}
+
+/// Signs out of every session but the current one. Requests made with an
+/// API key have no current session, so every session is revoked.
+#[delete("/sessions/others")]
+pub async fn revoke_others(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let mut filter = session::Column::UserId.eq(user.id as i32);
+
+    if let Some(id) = user.session_id {
+        filter = filter.and(session::Column::Id.ne(id));
+    }
+
+    let revoked = revoke_sessions(db, filter).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: format!("Revoked {} sessions", revoked),
+        }),
+    )))
+}
+
+/// Signs out of one session. Revoking the current session also clears the
+/// session cookie.
+#[delete("/sessions/<id>")]
+pub async fn revoke(
+    db: &State<DatabaseConnection>,
+    cookies: &CookieJar<'_>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let revoked = revoke_sessions(
+        db,
+        session::Column::Id
+            .eq(id)
+            .and(session::Column::UserId.eq(user.id as i32)),
+    )
+    .await?;
+
+    if revoked == 0 {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find an active session with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    if user.session_id == Some(id) {
+        remove_session_cookies(cookies);
+    }
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Session revoked".to_string(),
+        }),
+    )))
+}
@@ -0,0 +1,579 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Decimal},
+    sea_query::Expr,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait, TryIntoModel,
+};
+
+use crate::{
+    auth::{AdminUser, AuthenticatedUser},
+    entities::{
+        book,
+        prelude::*,
+        sea_orm_active_enums::{BookFormat, StockMovementKind},
+        stock_level, stock_movement,
+    },
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResStockLevel {
+    book_id: i32,
+    location_id: i32,
+    quantity: i32,
+    low_stock_threshold: i32,
+    low_stock: bool,
+}
+
+impl From<&stock_level::Model> for ResStockLevel {
+    fn from(s: &stock_level::Model) -> Self {
+        Self {
+            book_id: s.book_id,
+            location_id: s.location_id,
+            quantity: s.quantity,
+            low_stock_threshold: s.low_stock_threshold,
+            low_stock: s.low_stock_threshold > 0 && s.quantity <= s.low_stock_threshold,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResStock {
+    book_id: i32,
+    quantity: i32,
+    levels: Vec<ResStockLevel>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResStockLevelList {
+    total: usize,
+    levels: Vec<ResStockLevel>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResStockMovement {
+    id: i32,
+    book_id: i32,
+    location_id: i32,
+    counterpart_location_id: Option<i32>,
+    kind: StockMovementKind,
+    quantity: i32,
+    note: Option<String>,
+    user_id: Option<i32>,
+    order_id: Option<i32>,
+    created_at: Option<DateTimeUtc>,
+}
+
+impl From<&stock_movement::Model> for ResStockMovement {
+    fn from(m: &stock_movement::Model) -> Self {
+        Self {
+            id: m.id,
+            book_id: m.book_id,
+            location_id: m.location_id,
+            counterpart_location_id: m.counterpart_location_id,
+            kind: m.kind,
+            quantity: m.quantity,
+            note: m.note.to_owned(),
+            user_id: m.user_id,
+            order_id: m.order_id,
+            created_at: m.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResStockMovementList {
+    total: usize,
+    movements: Vec<ResStockMovement>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqStockMovement {
+    book_id: i32,
+    location_id: i32,
+    kind: StockMovementKind,
+    /// Always positive, except for adjustments which are signed.
+    quantity: i32,
+    to_location_id: Option<i32>,
+    note: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqStockThreshold {
+    low_stock_threshold: i32,
+}
+
+/// A change to the stock of one book at one location.
+pub struct Movement {
+    pub user_id: Option<i32>,
+    pub book_id: i32,
+    pub location_id: i32,
+    pub counterpart_location_id: Option<i32>,
+    pub kind: StockMovementKind,
+    pub quantity: i32,
+    pub note: Option<String>,
+    pub order_id: Option<i32>,
+}
+
+/// Records `movement` in the ledger and applies it to the stock level. The
+/// level row is locked, so callers should run this inside a transaction.
+pub async fn record<C: ConnectionTrait>(
+    db: &C,
+    movement: Movement,
+) -> Result<stock_level::Model, ErrorResponse> {
+    let level = StockLevel::find()
+        .filter(stock_level::Column::BookId.eq(movement.book_id))
+        .filter(stock_level::Column::LocationId.eq(movement.location_id))
+        .lock_exclusive()
+        .one(db)
+        .await?;
+
+    let unprocessable = |message: &str| {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: message.to_string(),
+            }),
+        ))
+    };
+
+    let quantity = level
+        .as_ref()
+        .map_or(0, |l| l.quantity)
+        .checked_add(movement.quantity)
+        .ok_or_else(|| unprocessable("Stock level at location would be too large"))?;
+
+    if quantity < 0 {
+        return Err(unprocessable("Insufficient stock at location"));
+    }
+
+    let mut level: stock_level::ActiveModel = match level {
+        Some(l) => l.into(),
+        None => stock_level::ActiveModel {
+            book_id: Set(movement.book_id),
+            location_id: Set(movement.location_id),
+            low_stock_threshold: Set(0),
+            ..Default::default()
+        },
+    };
+
+    level.quantity = Set(quantity);
+    level.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let level = level.save(db).await?.try_into_model()?;
+
+    stock_movement::ActiveModel {
+        user_id: Set(movement.user_id),
+        book_id: Set(movement.book_id),
+        location_id: Set(movement.location_id),
+        counterpart_location_id: Set(movement.counterpart_location_id),
+        kind: Set(movement.kind),
+        quantity: Set(movement.quantity),
+        note: Set(movement.note),
+        order_id: Set(movement.order_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(level)
+}
+
+/// Digital editions are not stocked and can always be sold.
+pub fn is_stocked(book: &book::Model) -> bool {
+    !matches!(book.format, Some(BookFormat::Ebook | BookFormat::Audiobook))
+}
+
+/// Takes `quantity` copies of a book out of stock for an order, drawing from
+/// the best-stocked locations first. Must run inside a transaction.
+pub async fn reserve<C: ConnectionTrait>(
+    db: &C,
+    book: &book::Model,
+    quantity: i32,
+    order_id: i32,
+    user_id: i32,
+) -> Result<(), ErrorResponse> {
+    if !is_stocked(book) {
+        return Ok(());
+    }
+
+    let levels = StockLevel::find()
+        .filter(stock_level::Column::BookId.eq(book.id))
+        .filter(stock_level::Column::Quantity.gt(0))
+        .order_by_desc(stock_level::Column::Quantity)
+        .lock_exclusive()
+        .all(db)
+        .await?;
+
+    if levels.iter().map(|l| i64::from(l.quantity)).sum::<i64>() < i64::from(quantity) {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: format!("\"{}\" is out of stock", book.title),
+            }),
+        )));
+    }
+
+    let mut remaining = quantity;
+
+    for level in levels {
+        if remaining == 0 {
+            break;
+        }
+
+        let take = remaining.min(level.quantity);
+        remaining -= take;
+
+        record(
+            db,
+            Movement {
+                user_id: Some(user_id),
+                book_id: book.id,
+                location_id: level.location_id,
+                counterpart_location_id: None,
+                kind: StockMovementKind::Sale,
+                quantity: -take,
+                note: None,
+                order_id: Some(order_id),
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Puts the stock reserved for an order back where it was taken from.
+pub async fn release<C: ConnectionTrait>(
+    db: &C,
+    order_id: i32,
+    user_id: i32,
+) -> Result<(), ErrorResponse> {
+    let sales = StockMovement::find()
+        .filter(stock_movement::Column::OrderId.eq(order_id))
+        .filter(stock_movement::Column::Kind.eq(StockMovementKind::Sale))
+        .all(db)
+        .await?;
+
+    for sale in sales {
+        record(
+            db,
+            Movement {
+                user_id: Some(user_id),
+                book_id: sale.book_id,
+                location_id: sale.location_id,
+                counterpart_location_id: None,
+                kind: StockMovementKind::Adjustment,
+                quantity: -sale.quantity,
+                note: Some(format!("Order #{} returned to stock", order_id)),
+                order_id: Some(order_id),
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Copies of a book on hand across all locations.
+pub async fn available<C: ConnectionTrait>(db: &C, book_id: i32) -> Result<i32, DbErr> {
+    let quantity: Option<Option<Decimal>> = StockLevel::find()
+        .select_only()
+        .column_as(Expr::col(stock_level::Column::Quantity).sum(), "quantity")
+        .filter(stock_level::Column::BookId.eq(book_id))
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    // MySQL sums integers as DECIMAL.
+    Ok(quantity
+        .flatten()
+        .and_then(|q| i32::try_from(q).ok())
+        .unwrap_or(0))
+}
+
+async fn check_book(db: &DatabaseConnection, book_id: i32) -> Result<(), ErrorResponse> {
+    if Book::find_by_id(book_id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find book with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    Ok(())
+}
+
+async fn check_location(db: &DatabaseConnection, location_id: i32) -> Result<(), ErrorResponse> {
+    if Location::find_by_id(location_id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Cannot find location with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    Ok(())
+}
+
+#[get("/<id>/stock")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResStock>> {
+    let db = db as &DatabaseConnection;
+
+    check_book(db, id).await?;
+
+    let levels = StockLevel::find()
+        .filter(stock_level::Column::BookId.eq(id))
+        .order_by_asc(stock_level::Column::LocationId)
+        .all(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResStock {
+            book_id: id,
+            quantity: levels.iter().map(|l| l.quantity).sum(),
+            levels: levels.iter().map(ResStockLevel::from).collect(),
+        }),
+    )))
+}
+
+#[put("/<id>/stock/<location_id>", data = "<req_threshold>")]
+pub async fn threshold(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+    location_id: i32,
+    req_threshold: Json<ReqStockThreshold>,
+) -> Response<Json<ResStockLevel>> {
+    let db = db as &DatabaseConnection;
+
+    check_book(db, id).await?;
+    check_location(db, location_id).await?;
+
+    if req_threshold.low_stock_threshold < 0 {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "low_stock_threshold cannot be negative".to_string(),
+            }),
+        )));
+    }
+
+    let level = StockLevel::find()
+        .filter(stock_level::Column::BookId.eq(id))
+        .filter(stock_level::Column::LocationId.eq(location_id))
+        .one(db)
+        .await?;
+
+    let mut level: stock_level::ActiveModel = match level {
+        Some(l) => l.into(),
+        None => stock_level::ActiveModel {
+            book_id: Set(id),
+            location_id: Set(location_id),
+            quantity: Set(0),
+            ..Default::default()
+        },
+    };
+
+    level.low_stock_threshold = Set(req_threshold.low_stock_threshold);
+    level.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let level = level.save(db).await?.try_into_model()?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResStockLevel::from(&level)),
+    )))
+}
+
+#[get("/movements?<book>&<location>")]
+pub async fn movements(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    book: Option<i32>,
+    location: Option<i32>,
+) -> Response<Json<ResStockMovementList>> {
+    let db = db as &DatabaseConnection;
+
+    let mut query = StockMovement::find();
+
+    if let Some(book_id) = book {
+        query = query.filter(stock_movement::Column::BookId.eq(book_id));
+    }
+
+    if let Some(location_id) = location {
+        query = query.filter(stock_movement::Column::LocationId.eq(location_id));
+    }
+
+    let movements = query
+        .order_by_desc(stock_movement::Column::Id)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResStockMovement::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResStockMovementList {
+            total: movements.len(),
+            movements,
+        }),
+    )))
+}
+
+#[post("/movements", data = "<req_movement>")]
+pub async fn create_movement(
+    db: &State<DatabaseConnection>,
+    admin: AdminUser,
+    req_movement: Json<ReqStockMovement>,
+) -> Response<Json<ResStockLevelList>> {
+    let db = db as &DatabaseConnection;
+
+    let unprocessable = |message: &str| {
+        ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: message.to_string(),
+            }),
+        ))
+    };
+
+    check_book(db, req_movement.book_id).await?;
+    check_location(db, req_movement.location_id).await?;
+
+    let quantity = req_movement.quantity;
+
+    let delta = match req_movement.kind {
+        StockMovementKind::Adjustment if quantity == 0 => {
+            return Err(unprocessable("An adjustment cannot be zero"))
+        }
+        StockMovementKind::Adjustment => quantity,
+        _ if quantity <= 0 => return Err(unprocessable("quantity must be positive")),
+        StockMovementKind::Receipt => quantity,
+        StockMovementKind::Sale | StockMovementKind::Transfer => -quantity,
+    };
+
+    let to_location_id = match (req_movement.kind, req_movement.to_location_id) {
+        (StockMovementKind::Transfer, Some(to)) if to == req_movement.location_id => {
+            return Err(unprocessable("Cannot transfer to the same location"))
+        }
+        (StockMovementKind::Transfer, Some(to)) => {
+            check_location(db, to).await?;
+            Some(to)
+        }
+        (StockMovementKind::Transfer, None) => {
+            return Err(unprocessable("A transfer requires to_location_id"))
+        }
+        (_, Some(_)) => return Err(unprocessable("Only transfers have a to_location_id")),
+        (_, None) => None,
+    };
+
+    let txn = db.begin().await?;
+
+    let mut levels = vec![
+        record(
+            &txn,
+            Movement {
+                user_id: Some(admin.id as i32),
+                book_id: req_movement.book_id,
+                location_id: req_movement.location_id,
+                counterpart_location_id: to_location_id,
+                kind: req_movement.kind,
+                quantity: delta,
+                note: req_movement.note.to_owned(),
+                order_id: None,
+            },
+        )
+        .await?,
+    ];
+
+    if let Some(to) = to_location_id {
+        levels.push(
+            record(
+                &txn,
+                Movement {
+                    user_id: Some(admin.id as i32),
+                    book_id: req_movement.book_id,
+                    location_id: to,
+                    counterpart_location_id: Some(req_movement.location_id),
+                    kind: StockMovementKind::Transfer,
+                    quantity,
+                    note: req_movement.note.to_owned(),
+                    order_id: None,
+                },
+            )
+            .await?,
+        );
+    }
+
+    txn.commit().await?;
+
+    let levels = levels.iter().map(ResStockLevel::from).collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResStockLevelList {
+            total: levels.len(),
+            levels,
+        }),
+    )))
+}
+
+#[get("/low?<location>")]
+pub async fn low(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    location: Option<i32>,
+) -> Response<Json<ResStockLevelList>> {
+    let db = db as &DatabaseConnection;
+
+    let mut query = StockLevel::find()
+        .filter(stock_level::Column::LowStockThreshold.gt(0))
+        .filter(
+            Expr::col(stock_level::Column::Quantity)
+                .lte(Expr::col(stock_level::Column::LowStockThreshold)),
+        );
+
+    if let Some(location_id) = location {
+        query = query.filter(stock_level::Column::LocationId.eq(location_id));
+    }
+
+    let levels = query
+        .order_by_asc(stock_level::Column::Quantity)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResStockLevel::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResStockLevelList {
+            total: levels.len(),
+            levels,
+        }),
+    )))
+}
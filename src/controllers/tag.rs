@@ -0,0 +1,233 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    DbErr, EntityTrait, ModelTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::{
+    auth::{AuthenticatedUser, CatalogueEditor},
+    entities::{prelude::*, tag},
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResTag {
+    id: i32,
+    name: String,
+}
+
+impl From<&tag::Model> for ResTag {
+    fn from(t: &tag::Model) -> Self {
+        Self {
+            id: t.id,
+            name: t.name.to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResTagList {
+    total: usize,
+    tags: Vec<ResTag>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqTag {
+    name: String,
+}
+
+/// Tags are free-form, so they are compared trimmed and lowercased.
+pub fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Looks up a tag by name, creating it when it does not exist yet.
+pub async fn find_or_create<C: ConnectionTrait>(db: &C, name: &str) -> Result<tag::Model, DbErr> {
+    let name = normalize(name);
+
+    if let Some(t) = Tag::find()
+        .filter(tag::Column::Name.eq(&name))
+        .one(db)
+        .await?
+    {
+        return Ok(t);
+    }
+
+    tag::ActiveModel {
+        name: Set(name),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+async fn check_name(db: &DatabaseConnection, name: &str) -> Result<String, ErrorResponse> {
+    let name = normalize(name);
+
+    if name.is_empty() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Tag name cannot be empty".to_string(),
+            }),
+        )));
+    }
+
+    if Tag::find()
+        .filter(tag::Column::Name.eq(&name))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "A tag exists with that name".to_string(),
+            }),
+        )));
+    }
+
+    Ok(name)
+}
+
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+) -> Response<Json<ResTagList>> {
+    let db = db as &DatabaseConnection;
+
+    let tags = Tag::find()
+        .order_by_asc(tag::Column::Name)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResTag::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResTagList {
+            total: tags.len(),
+            tags,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_tag>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    req_tag: Json<ReqTag>,
+) -> Response<Json<ResTag>> {
+    let db = db as &DatabaseConnection;
+
+    let name = check_name(db, &req_tag.name).await?;
+
+    let tag = tag::ActiveModel {
+        name: Set(name),
+        ..Default::default()
+    };
+
+    let tag = tag.insert(db).await?;
+
+    Ok(SuccessResponse((Status::Created, Json(ResTag::from(&tag)))))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResTag>> {
+    let db = db as &DatabaseConnection;
+
+    let tag = Tag::find_by_id(id).one(db).await?;
+
+    let tag = match tag {
+        Some(t) => t,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find tag with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((Status::Ok, Json(ResTag::from(&tag)))))
+}
+
+#[put("/<id>", data = "<req_tag>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+    req_tag: Json<ReqTag>,
+) -> Response<Json<ResTag>> {
+    let db = db as &DatabaseConnection;
+
+    let tag = Tag::find_by_id(id).one(db).await?;
+
+    let mut tag: tag::ActiveModel = match tag {
+        Some(t) => t.into(),
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find tag with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    tag.name = Set(check_name(db, &req_tag.name).await?);
+    tag.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let tag = tag.update(db).await?;
+
+    Ok(SuccessResponse((Status::Ok, Json(ResTag::from(&tag)))))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let tag = Tag::find_by_id(id).one(db).await?;
+
+    let tag = match tag {
+        Some(t) => t,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find tag with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    tag.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Tag deleted".to_string(),
+        }),
+    )))
+}
@@ -0,0 +1,340 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use rust_decimal::RoundingStrategy;
+use sea_orm::{
+    prelude::{DateTimeUtc, Decimal},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    ModelTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::{
+    auth::{AdminUser, AuthenticatedUser},
+    entities::{
+        book,
+        prelude::*,
+        sea_orm_active_enums::{BookFormat, TaxCategory},
+        tax_rate,
+    },
+};
+
+use super::{ErrorResponse, GenericResponse, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResTaxRate {
+    id: i32,
+    region: String,
+    category: TaxCategory,
+    name: String,
+    percent: Decimal,
+}
+
+impl From<&tax_rate::Model> for ResTaxRate {
+    fn from(t: &tax_rate::Model) -> Self {
+        Self {
+            id: t.id,
+            region: t.region.to_owned(),
+            category: t.category,
+            name: t.name.to_owned(),
+            percent: t.percent,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResTaxRateList {
+    total: usize,
+    tax_rates: Vec<ResTaxRate>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqTaxRate {
+    region: String,
+    category: TaxCategory,
+    name: String,
+    percent: Decimal,
+}
+
+/// Regions are ISO 3166-1 countries (`DE`) or ISO 3166-2 subdivisions
+/// (`US-CA`).
+pub fn normalize_region(region: &str) -> Option<String> {
+    let region = region.trim().to_uppercase();
+    let (country, subdivision) = match region.split_once('-') {
+        Some((c, s)) => (c, Some(s)),
+        None => (region.as_str(), None),
+    };
+
+    let valid = country.len() == 2
+        && country.chars().all(|c| c.is_ascii_uppercase())
+        && subdivision.is_none_or(|s| {
+            (1..=3).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+
+    if valid {
+        Some(region)
+    } else {
+        None
+    }
+}
+
+pub fn category(book: &book::Model) -> TaxCategory {
+    match book.format {
+        Some(BookFormat::Ebook | BookFormat::Audiobook) => TaxCategory::Digital,
+        _ => TaxCategory::Printed,
+    }
+}
+
+/// The rate for `region`, falling back to its country's rate when the
+/// subdivision has none of its own.
+pub async fn find_rate<C: ConnectionTrait>(
+    db: &C,
+    region: &str,
+    category: TaxCategory,
+) -> Result<Option<tax_rate::Model>, DbErr> {
+    let mut regions = vec![region];
+
+    if let Some((country, _)) = region.split_once('-') {
+        regions.push(country);
+    }
+
+    for region in regions {
+        let rate = TaxRate::find()
+            .filter(tax_rate::Column::Region.eq(region))
+            .filter(tax_rate::Column::Category.eq(category))
+            .one(db)
+            .await?;
+
+        if rate.is_some() {
+            return Ok(rate);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Tax on `amount` at `percent`, rounded half away from zero to the cent.
+/// Rounding is done per order line.
+pub fn tax(amount: Decimal, percent: Decimal) -> Decimal {
+    (amount * percent / Decimal::from(100))
+        .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+impl ReqTaxRate {
+    async fn check(
+        &self,
+        db: &DatabaseConnection,
+        id: Option<i32>,
+    ) -> Result<String, ErrorResponse> {
+        let unprocessable = |message: &str| {
+            ErrorResponse((
+                Status::UnprocessableEntity,
+                Json(GenericResponse {
+                    message: message.to_string(),
+                }),
+            ))
+        };
+
+        let region = normalize_region(&self.region).ok_or_else(|| {
+            unprocessable("region must be an ISO 3166 country or subdivision code")
+        })?;
+
+        if self.percent.is_sign_negative() || self.percent > Decimal::from(100) {
+            return Err(unprocessable("percent must be between 0 and 100"));
+        }
+
+        if self.percent.normalize().scale() > 4 {
+            return Err(unprocessable(
+                "percent cannot have more than 4 decimal places",
+            ));
+        }
+
+        let mut taken = TaxRate::find()
+            .filter(tax_rate::Column::Region.eq(&region))
+            .filter(tax_rate::Column::Category.eq(self.category));
+
+        if let Some(id) = id {
+            taken = taken.filter(tax_rate::Column::Id.ne(id));
+        }
+
+        if taken.one(db).await?.is_some() {
+            return Err(unprocessable(
+                "A rate for that region and category already exists",
+            ));
+        }
+
+        Ok(region)
+    }
+}
+
+async fn find_tax_rate(db: &DatabaseConnection, id: i32) -> Result<tax_rate::Model, ErrorResponse> {
+    match TaxRate::find_by_id(id).one(db).await? {
+        Some(t) => Ok(t),
+        None => Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find tax rate with specified ID".to_string(),
+            }),
+        ))),
+    }
+}
+
+#[get("/?<region>")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    region: Option<&str>,
+) -> Response<Json<ResTaxRateList>> {
+    let db = db as &DatabaseConnection;
+
+    let mut query = TaxRate::find();
+
+    if let Some(region) = region {
+        query = query.filter(tax_rate::Column::Region.eq(region.trim().to_uppercase()));
+    }
+
+    let tax_rates = query
+        .order_by_asc(tax_rate::Column::Region)
+        .order_by_asc(tax_rate::Column::Category)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResTaxRate::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResTaxRateList {
+            total: tax_rates.len(),
+            tax_rates,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_tax_rate>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    req_tax_rate: Json<ReqTaxRate>,
+) -> Response<Json<ResTaxRate>> {
+    let db = db as &DatabaseConnection;
+
+    let region = req_tax_rate.check(db, None).await?;
+
+    let tax_rate = tax_rate::ActiveModel {
+        region: Set(region),
+        category: Set(req_tax_rate.category),
+        name: Set(req_tax_rate.name.to_owned()),
+        percent: Set(req_tax_rate.percent),
+        ..Default::default()
+    };
+
+    let tax_rate = tax_rate.insert(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResTaxRate::from(&tax_rate)),
+    )))
+}
+
+#[put("/<id>", data = "<req_tax_rate>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+    req_tax_rate: Json<ReqTaxRate>,
+) -> Response<Json<ResTaxRate>> {
+    let db = db as &DatabaseConnection;
+
+    let mut tax_rate: tax_rate::ActiveModel = find_tax_rate(db, id).await?.into();
+
+    let region = req_tax_rate.check(db, Some(id)).await?;
+
+    tax_rate.region = Set(region);
+    tax_rate.category = Set(req_tax_rate.category);
+    tax_rate.name = Set(req_tax_rate.name.to_owned());
+    tax_rate.percent = Set(req_tax_rate.percent);
+    tax_rate.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let tax_rate = tax_rate.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResTaxRate::from(&tax_rate)),
+    )))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _admin: AdminUser,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let tax_rate = find_tax_rate(db, id).await?;
+
+    tax_rate.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Tax rate deleted".to_string(),
+        }),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tax_rounds_to_the_cent() {
+        assert_eq!(
+            tax(Decimal::new(1999, 2), Decimal::from(19)),
+            Decimal::new(380, 2)
+        );
+        assert_eq!(
+            tax(Decimal::new(1000, 2), Decimal::new(725, 2)),
+            Decimal::new(73, 2)
+        );
+    }
+
+    #[test]
+    fn tax_rounds_half_away_from_zero() {
+        // 0.125 would round down to 0.12 with banker's rounding.
+        assert_eq!(
+            tax(Decimal::new(125, 2), Decimal::from(10)),
+            Decimal::new(13, 2)
+        );
+        assert_eq!(
+            tax(Decimal::new(-125, 2), Decimal::from(10)),
+            Decimal::new(-13, 2)
+        );
+    }
+
+    #[test]
+    fn tax_at_zero_percent_is_zero() {
+        assert!(tax(Decimal::new(4250, 2), Decimal::ZERO).is_zero());
+    }
+
+    #[test]
+    fn regions_are_normalized() {
+        assert_eq!(normalize_region(" de "), Some("DE".to_string()));
+        assert_eq!(normalize_region("us-ca"), Some("US-CA".to_string()));
+        assert_eq!(normalize_region("FR-75C"), Some("FR-75C".to_string()));
+    }
+
+    #[test]
+    fn invalid_regions_are_rejected() {
+        for region in ["", "D", "DEU", "D1", "US-", "US-CALI", "US-C_A", "US-CA-1"] {
+            assert_eq!(normalize_region(region), None, "{:?}", region);
+        }
+    }
+}
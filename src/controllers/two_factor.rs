@@ -0,0 +1,414 @@
+use std::{net::IpAddr, time::SystemTime};
+
+use bcrypt::{hash, verify};
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::{DateTimeUtc, Expr},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+
+use crate::{
+    auth::{decode_action_token, two_factor, AuthenticatedUser},
+    entities::{prelude::*, recovery_code, user},
+    mail::Mailer,
+    AppConfig,
+};
+
+use super::{
+    auth::{ResSignIn, SessionIssuer, SIGN_IN_CHALLENGE},
+    cart::{merge_guest_cart, CartToken},
+    lockout::{self, SignInSubjects},
+    ErrorResponse, GenericResponse, Response, SuccessResponse,
+};
+
+/// Recovery codes are random enough that a low bcrypt cost is plenty, and
+/// checking one means trying every unused code.
+const RECOVERY_CODE_COST: u32 = 6;
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResEnrollment {
+    secret: String,
+    otpauth_uri: String,
+    qr_svg: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResRecoveryCodes {
+    recovery_codes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqDisable {
+    password: String,
+    /// Either a code from the authenticator app or an unused recovery code.
+    code: String,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqSignInTwoFactor {
+    challenge_token: String,
+    /// Either a code from the authenticator app or an unused recovery code.
+    code: String,
+    device_name: Option<String>,
+}
+
+fn unprocessable(message: &str) -> ErrorResponse {
+    ErrorResponse((
+        Status::UnprocessableEntity,
+        Json(GenericResponse {
+            message: message.to_string(),
+        }),
+    ))
+}
+
+fn invalid_code() -> ErrorResponse {
+    ErrorResponse((
+        Status::Unauthorized,
+        Json(GenericResponse {
+            message: "Invalid authentication code".to_string(),
+        }),
+    ))
+}
+
+/// Checks an authenticator code and records its time step, so each code
+/// works once. The step is claimed with a conditional update, so of two
+/// concurrent requests with the same code only one succeeds.
+async fn check_totp<C: ConnectionTrait>(
+    db: &C,
+    user: &user::Model,
+    code: &str,
+) -> Result<bool, DbErr> {
+    let step = match &user.totp_secret {
+        Some(secret) => two_factor::check(secret, &user.email, code, user.totp_last_step),
+        None => None,
+    };
+
+    let step = match step {
+        Some(step) => step,
+        None => return Ok(false),
+    };
+
+    let claimed = User::update_many()
+        .col_expr(user::Column::TotpLastStep, Expr::value(step))
+        .filter(user::Column::Id.eq(user.id))
+        .filter(
+            Condition::any()
+                .add(user::Column::TotpLastStep.is_null())
+                .add(user::Column::TotpLastStep.lt(step)),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(claimed.rows_affected == 1)
+}
+
+/// Replaces the user's recovery codes with fresh ones and returns them in
+/// plain text. Only their hashes are stored.
+async fn replace_recovery_codes<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+) -> Result<Vec<String>, DbErr> {
+    RecoveryCode::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let now = DateTimeUtc::from(SystemTime::now());
+    let codes = two_factor::recovery_codes();
+
+    RecoveryCode::insert_many(codes.iter().map(|code| {
+        recovery_code::ActiveModel {
+            user_id: Set(user_id),
+            code_hash: Set(hash(
+                two_factor::normalize_recovery_code(code),
+                RECOVERY_CODE_COST,
+            )
+            .unwrap()),
+            created_at: Set(Some(now)),
+            ..Default::default()
+        }
+    }))
+    .exec(db)
+    .await?;
+
+    Ok(codes)
+}
+
+/// Marks the matching recovery code as used, returning whether there was one.
+async fn use_recovery_code(
+    db: &DatabaseConnection,
+    user_id: i32,
+    code: &str,
+) -> Result<bool, DbErr> {
+    let code = two_factor::normalize_recovery_code(code);
+
+    let unused = RecoveryCode::find()
+        .filter(recovery_code::Column::UserId.eq(user_id))
+        .filter(recovery_code::Column::UsedAt.is_null())
+        .all(db)
+        .await?;
+
+    match unused
+        .into_iter()
+        .find(|c| verify(&code, &c.code_hash).unwrap_or(false))
+    {
+        Some(c) => {
+            let mut c: recovery_code::ActiveModel = c.into();
+            c.used_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+            c.update(db).await?;
+
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Starts enrolment with a new secret. Two-factor authentication stays off
+/// until a code from it is confirmed, so enrolling again simply starts over.
+#[post("/2fa/enroll")]
+pub async fn enroll(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<Json<ResEnrollment>> {
+    let db = db as &DatabaseConnection;
+
+    let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
+
+    if user.totp_enabled_at.is_some() {
+        return Err(unprocessable(
+            "Two-factor authentication is already enabled",
+        ));
+    }
+
+    let secret = two_factor::new_secret();
+
+    let totp = match two_factor::totp(&secret, &user.email) {
+        Some(t) => t,
+        None => {
+            return Err(unprocessable(
+                "Two-factor authentication is not available for this email address",
+            ))
+        }
+    };
+
+    let otpauth_uri = totp.get_url();
+    let qr_svg = two_factor::qr_svg(&otpauth_uri).unwrap_or_default();
+
+    let mut user: user::ActiveModel = user.into();
+    user.totp_secret = Set(Some(secret.to_owned()));
+    user.update(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResEnrollment {
+            secret,
+            otpauth_uri,
+            qr_svg,
+        }),
+    )))
+}
+
+/// Turns two-factor authentication on and returns the recovery codes. They
+/// are not shown again.
+#[post("/2fa/confirm", data = "<req_code>")]
+pub async fn confirm(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    req_code: Json<ReqCode>,
+) -> Response<Json<ResRecoveryCodes>> {
+    let db = db as &DatabaseConnection;
+
+    let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
+
+    if user.totp_enabled_at.is_some() {
+        return Err(unprocessable(
+            "Two-factor authentication is already enabled",
+        ));
+    }
+
+    if user.totp_secret.is_none() {
+        return Err(unprocessable("Start enrolment first"));
+    }
+
+    if !check_totp(db, &user, &req_code.code).await? {
+        return Err(invalid_code());
+    }
+
+    let txn = db.begin().await?;
+
+    let user_id = user.id;
+
+    let mut user: user::ActiveModel = user.into();
+    user.totp_enabled_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    user.update(&txn).await?;
+
+    let recovery_codes = replace_recovery_codes(&txn, user_id).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResRecoveryCodes { recovery_codes }),
+    )))
+}
+
+/// Requires a current authenticator code, and invalidates the old codes.
+#[post("/2fa/recovery-codes", data = "<req_code>")]
+pub async fn regenerate_recovery_codes(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    req_code: Json<ReqCode>,
+) -> Response<Json<ResRecoveryCodes>> {
+    let db = db as &DatabaseConnection;
+
+    let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
+
+    if user.totp_enabled_at.is_none() {
+        return Err(unprocessable("Two-factor authentication is not enabled"));
+    }
+
+    if !check_totp(db, &user, &req_code.code).await? {
+        return Err(invalid_code());
+    }
+
+    let txn = db.begin().await?;
+    let recovery_codes = replace_recovery_codes(&txn, user.id).await?;
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResRecoveryCodes { recovery_codes }),
+    )))
+}
+
+/// Needs the password and a second factor, so a stolen session or password
+/// alone cannot switch it off.
+#[delete("/2fa", data = "<req_disable>")]
+pub async fn disable(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    req_disable: Json<ReqDisable>,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let user = User::find_by_id(user.id as i32).one(db).await?.unwrap();
+
+    if !verify(&req_disable.password, &user.password).unwrap_or(false) {
+        return Err(ErrorResponse((
+            Status::Forbidden,
+            Json(GenericResponse {
+                message: "The password is incorrect".to_string(),
+            }),
+        )));
+    }
+
+    if user.totp_enabled_at.is_none() {
+        return Err(unprocessable("Two-factor authentication is not enabled"));
+    }
+
+    if !check_totp(db, &user, &req_disable.code).await?
+        && !use_recovery_code(db, user.id, &req_disable.code).await?
+    {
+        return Err(invalid_code());
+    }
+
+    let txn = db.begin().await?;
+
+    RecoveryCode::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+
+    let mut user: user::ActiveModel = user.into();
+    user.totp_secret = Set(None);
+    user.totp_enabled_at = Set(None);
+    user.totp_last_step = Set(None);
+    user.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Two-factor authentication disabled".to_string(),
+        }),
+    )))
+}
+
+/// Second step of signing in to an account with two-factor authentication.
+/// Wrong codes count as failed sign-in attempts.
+#[post("/sign-in/2fa", data = "<req_sign_in>")]
+pub async fn sign_in(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    sessions: SessionIssuer<'_>,
+    mailer: &State<Box<dyn Mailer>>,
+    client_ip: Option<IpAddr>,
+    cart_token: CartToken,
+    req_sign_in: Json<ReqSignInTwoFactor>,
+) -> Response<Json<ResSignIn>> {
+    let db = db as &DatabaseConnection;
+    let config = config as &AppConfig;
+
+    let expired = || {
+        ErrorResponse((
+            Status::Unauthorized,
+            Json(GenericResponse {
+                message: "The sign-in challenge is invalid or has expired".to_string(),
+            }),
+        ))
+    };
+
+    let claims = decode_action_token(config, &req_sign_in.challenge_token, SIGN_IN_CHALLENGE)
+        .ok_or_else(expired)?;
+
+    let user = User::find_by_id(claims.sub as i32)
+        .one(db)
+        .await?
+        .filter(|u| {
+            u.email == claims.email
+                && u.session_version == claims.ver
+                && u.totp_enabled_at.is_some()
+        })
+        .ok_or_else(expired)?;
+
+    let subjects = SignInSubjects::new(&user.email, client_ip);
+
+    lockout::check(db, &subjects).await?;
+
+    if !check_totp(db, &user, &req_sign_in.code).await?
+        && !use_recovery_code(db, user.id, &req_sign_in.code).await?
+    {
+        lockout::record_failure(db, mailer.inner().as_ref(), &subjects, Some(&user)).await?;
+
+        return Err(invalid_code());
+    }
+
+    lockout::clear(db, &subjects).await?;
+
+    merge_guest_cart(db, user.id, &cart_token).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(
+            sessions
+                .start(&user, req_sign_in.device_name.as_deref())
+                .await?,
+        ),
+    )))
+}
@@ -0,0 +1,72 @@
+use jsonwebtoken::jwk::JwkSet;
+use rocket::{
+    http::Status,
+    serde::{json::Json, Serialize},
+    State,
+};
+
+use crate::{auth::keyring::Keyring, entities::sea_orm_active_enums::SigningAlgorithm, AppConfig};
+
+use super::{oauth::SUPPORTED_SCOPES, Response, SuccessResponse};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResOpenIdConfiguration {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: String,
+    jwks_uri: String,
+    response_types_supported: Vec<String>,
+    grant_types_supported: Vec<String>,
+    subject_types_supported: Vec<String>,
+    id_token_signing_alg_values_supported: Vec<String>,
+    scopes_supported: Vec<String>,
+    token_endpoint_auth_methods_supported: Vec<String>,
+    code_challenge_methods_supported: Vec<String>,
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+/// Public keys to verify session tokens with, for other services.
+#[get("/jwks.json")]
+pub async fn jwks(keyring: &State<Keyring>) -> Response<Json<JwkSet>> {
+    Ok(SuccessResponse((Status::Ok, Json(keyring.jwks()))))
+}
+
+/// OpenID Connect discovery. Clients compare `issuer` with the URL they
+/// fetched this from, so BOOKSTORE_JWT_ISSUER should be set to the public URL
+/// when apps sign in with OpenID Connect.
+#[get("/openid-configuration")]
+pub async fn openid_configuration(
+    config: &State<AppConfig>,
+) -> Response<Json<ResOpenIdConfiguration>> {
+    let url = |path: &str| format!("{}{}", config.public_url, path);
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResOpenIdConfiguration {
+            issuer: config.jwt_issuer.to_owned(),
+            authorization_endpoint: url("/oauth/authorize"),
+            token_endpoint: url("/oauth/token"),
+            userinfo_endpoint: url("/oauth/userinfo"),
+            jwks_uri: url("/.well-known/jwks.json"),
+            response_types_supported: strings(&["code"]),
+            grant_types_supported: strings(&["authorization_code", "client_credentials"]),
+            subject_types_supported: strings(&["public"]),
+            id_token_signing_alg_values_supported: strings(&[match config.jwt_algorithm {
+                SigningAlgorithm::RS256 => "RS256",
+                SigningAlgorithm::EdDSA => "EdDSA",
+            }]),
+            scopes_supported: strings(&SUPPORTED_SCOPES),
+            token_endpoint_auth_methods_supported: strings(&[
+                "client_secret_basic",
+                "client_secret_post",
+                "none",
+            ]),
+            code_challenge_methods_supported: strings(&["S256"]),
+        }),
+    )))
+}
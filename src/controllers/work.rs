@@ -0,0 +1,253 @@
+use std::time::SystemTime;
+
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    State,
+};
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::{
+    auth::{AuthenticatedUser, CatalogueEditor},
+    entities::{book, prelude::*, work},
+};
+
+use super::{
+    book::{ResBook, ResBookList},
+    ErrorResponse, GenericResponse, Response, SuccessResponse,
+};
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResWork {
+    id: i32,
+    author_id: i32,
+    title: String,
+}
+
+impl From<&work::Model> for ResWork {
+    fn from(w: &work::Model) -> Self {
+        Self {
+            id: w.id,
+            author_id: w.author_id,
+            title: w.title.to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResWorkList {
+    total: usize,
+    works: Vec<ResWork>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqWork {
+    author_id: i32,
+    title: String,
+}
+
+async fn check_author(db: &DatabaseConnection, author_id: i32) -> Result<(), ErrorResponse> {
+    if Author::find_by_id(author_id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Cannot find author with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    Ok(())
+}
+
+#[get("/")]
+pub async fn index(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+) -> Response<Json<ResWorkList>> {
+    let db = db as &DatabaseConnection;
+
+    let works = Work::find()
+        .order_by_asc(work::Column::Title)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResWork::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResWorkList {
+            total: works.len(),
+            works,
+        }),
+    )))
+}
+
+#[post("/", data = "<req_work>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    user: CatalogueEditor,
+    req_work: Json<ReqWork>,
+) -> Response<Json<ResWork>> {
+    let db = db as &DatabaseConnection;
+
+    check_author(db, req_work.author_id).await?;
+
+    let work = work::ActiveModel {
+        user_id: Set(user.id as i32),
+        author_id: Set(req_work.author_id),
+        title: Set(req_work.title.to_owned()),
+        ..Default::default()
+    };
+
+    let work = work.insert(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResWork::from(&work)),
+    )))
+}
+
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResWork>> {
+    let db = db as &DatabaseConnection;
+
+    let work = Work::find_by_id(id).one(db).await?;
+
+    let work = match work {
+        Some(w) => w,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find work with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    Ok(SuccessResponse((Status::Ok, Json(ResWork::from(&work)))))
+}
+
+#[get("/<id>/editions")]
+pub async fn editions(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResBookList>> {
+    let db = db as &DatabaseConnection;
+
+    if Work::find_by_id(id).one(db).await?.is_none() {
+        return Err(ErrorResponse((
+            Status::NotFound,
+            Json(GenericResponse {
+                message: "Cannot find work with specified ID".to_string(),
+            }),
+        )));
+    }
+
+    let books = Book::find()
+        .filter(book::Column::WorkId.eq(id))
+        .order_by_asc(book::Column::PublishedOn)
+        .all(db)
+        .await?;
+    let books = ResBook::load(db, books).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResBookList {
+            total: books.len(),
+            books,
+        }),
+    )))
+}
+
+#[put("/<id>", data = "<req_work>")]
+pub async fn update(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+    req_work: Json<ReqWork>,
+) -> Response<Json<ResWork>> {
+    let db = db as &DatabaseConnection;
+
+    let work = Work::find_by_id(id).one(db).await?;
+
+    let mut work: work::ActiveModel = match work {
+        Some(w) => w.into(),
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find work with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    check_author(db, req_work.author_id).await?;
+
+    work.author_id = Set(req_work.author_id);
+    work.title = Set(req_work.title.to_owned());
+    work.updated_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+
+    let work = work.update(db).await?;
+
+    Ok(SuccessResponse((Status::Ok, Json(ResWork::from(&work)))))
+}
+
+#[delete("/<id>")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    _editor: CatalogueEditor,
+    id: i32,
+) -> Response<Json<GenericResponse>> {
+    let db = db as &DatabaseConnection;
+
+    let work = Work::find_by_id(id).one(db).await?;
+
+    let work = match work {
+        Some(w) => w,
+        None => {
+            return Err(ErrorResponse((
+                Status::NotFound,
+                Json(GenericResponse {
+                    message: "Cannot find work with specified ID".to_string(),
+                }),
+            )))
+        }
+    };
+
+    if Book::find()
+        .filter(book::Column::WorkId.eq(id))
+        .count(db)
+        .await?
+        > 0
+    {
+        return Err(ErrorResponse((
+            Status::UnprocessableEntity,
+            Json(GenericResponse {
+                message: "Work still has editions".to_string(),
+            }),
+        )));
+    }
+
+    work.delete(db).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(GenericResponse {
+            message: "Work deleted".to_string(),
+        }),
+    )))
+}
@@ -0,0 +1,212 @@
+use std::fmt;
+
+use chrono::{Datelike, NaiveDate};
+use rocket::form::{self, FromFormField, ValueField};
+use rocket::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::entities::sea_orm_active_enums::DatePrecision;
+
+/// A publication date that may only be known to the year or month.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PublicationDate {
+    Year(i32),
+    YearMonth(i32, u32),
+    Date(NaiveDate),
+}
+
+impl PublicationDate {
+    /// Parses `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let parts = value.trim().split('-').collect::<Vec<_>>();
+
+        let year = match parts.first() {
+            Some(y) if y.len() == 4 && y.chars().all(|c| c.is_ascii_digit()) => {
+                y.parse::<i32>().ok()?
+            }
+            _ => return None,
+        };
+
+        let number = |s: &str| -> Option<u32> {
+            if s.len() == 2 && s.chars().all(|c| c.is_ascii_digit()) {
+                s.parse().ok()
+            } else {
+                None
+            }
+        };
+
+        match parts.len() {
+            1 => Some(Self::Year(year)),
+            2 => {
+                let month = number(parts[1])?;
+                NaiveDate::from_ymd_opt(year, month, 1)?;
+                Some(Self::YearMonth(year, month))
+            }
+            3 => {
+                let date = NaiveDate::from_ymd_opt(year, number(parts[1])?, number(parts[2])?)?;
+                Some(Self::Date(date))
+            }
+            _ => None,
+        }
+    }
+
+    /// First day covered by this date, used for storage and lower bounds.
+    pub fn first_day(&self) -> NaiveDate {
+        match *self {
+            Self::Year(y) => NaiveDate::from_ymd_opt(y, 1, 1).unwrap(),
+            Self::YearMonth(y, m) => NaiveDate::from_ymd_opt(y, m, 1).unwrap(),
+            Self::Date(d) => d,
+        }
+    }
+
+    /// Last day covered by this date, used for upper bounds.
+    pub fn last_day(&self) -> NaiveDate {
+        match *self {
+            Self::Year(y) => NaiveDate::from_ymd_opt(y, 12, 31).unwrap(),
+            Self::YearMonth(y, m) => {
+                let (y, m) = if m == 12 { (y + 1, 1) } else { (y, m + 1) };
+                NaiveDate::from_ymd_opt(y, m, 1)
+                    .unwrap()
+                    .pred_opt()
+                    .unwrap()
+            }
+            Self::Date(d) => d,
+        }
+    }
+
+    pub fn precision(&self) -> DatePrecision {
+        match self {
+            Self::Year(_) => DatePrecision::Year,
+            Self::YearMonth(_, _) => DatePrecision::Month,
+            Self::Date(_) => DatePrecision::Day,
+        }
+    }
+
+    /// Rebuilds a publication date from the `published_on` and
+    /// `published_precision` columns of a book.
+    pub fn from_columns(date: Option<NaiveDate>, precision: Option<DatePrecision>) -> Option<Self> {
+        let date = date?;
+
+        Some(match precision? {
+            DatePrecision::Year => Self::Year(date.year()),
+            DatePrecision::Month => Self::YearMonth(date.year(), date.month()),
+            DatePrecision::Day => Self::Date(date),
+        })
+    }
+}
+
+impl fmt::Display for PublicationDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Year(y) => write!(f, "{:04}", y),
+            Self::YearMonth(y, m) => write!(f, "{:04}-{:02}", y, m),
+            Self::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+        }
+    }
+}
+
+impl Serialize for PublicationDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicationDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+
+        PublicationDate::parse(&value).ok_or_else(|| {
+            de::Error::custom(format!(
+                "invalid publication date `{}`, expected YYYY, YYYY-MM or YYYY-MM-DD",
+                value
+            ))
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl<'v> FromFormField<'v> for PublicationDate {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        PublicationDate::parse(field.value)
+            .ok_or_else(|| form::Error::validation("expected YYYY, YYYY-MM or YYYY-MM-DD").into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parses_year_only() {
+        let year = PublicationDate::parse("1984").unwrap();
+
+        assert_eq!(year, PublicationDate::Year(1984));
+        assert_eq!(year.first_day(), date(1984, 1, 1));
+        assert_eq!(year.last_day(), date(1984, 12, 31));
+        assert_eq!(year.precision(), DatePrecision::Year);
+        assert_eq!(year.to_string(), "1984");
+    }
+
+    #[test]
+    fn parses_year_and_month() {
+        let month = PublicationDate::parse(" 2024-02 ").unwrap();
+
+        assert_eq!(month, PublicationDate::YearMonth(2024, 2));
+        assert_eq!(month.first_day(), date(2024, 2, 1));
+        assert_eq!(month.last_day(), date(2024, 2, 29));
+        assert_eq!(month.precision(), DatePrecision::Month);
+        assert_eq!(month.to_string(), "2024-02");
+    }
+
+    #[test]
+    fn december_ends_on_the_last_day_of_the_year() {
+        let month = PublicationDate::parse("1999-12").unwrap();
+
+        assert_eq!(month.last_day(), date(1999, 12, 31));
+    }
+
+    #[test]
+    fn parses_full_dates() {
+        let day = PublicationDate::parse("1851-10-18").unwrap();
+
+        assert_eq!(day, PublicationDate::Date(date(1851, 10, 18)));
+        assert_eq!(day.first_day(), day.last_day());
+        assert_eq!(day.precision(), DatePrecision::Day);
+        assert_eq!(day.to_string(), "1851-10-18");
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for value in [
+            "",
+            "84",
+            "19845",
+            "abcd",
+            "1984-1",
+            "1984-13",
+            "1984-00",
+            "1984-02-30",
+            "2023-02-29",
+            "1984-01-01-01",
+            "1984/01/01",
+            "1984-01-",
+        ] {
+            assert_eq!(PublicationDate::parse(value), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_columns() {
+        for value in ["1984", "2024-02", "1851-10-18"] {
+            let parsed = PublicationDate::parse(value).unwrap();
+
+            assert_eq!(
+                PublicationDate::from_columns(Some(parsed.first_day()), Some(parsed.precision())),
+                Some(parsed)
+            );
+        }
+    }
+}
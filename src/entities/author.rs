@@ -19,6 +19,8 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::book::Entity")]
     Book,
+    #[sea_orm(has_many = "super::promotion::Entity")]
+    Promotion,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::UserId",
@@ -27,6 +29,8 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     User,
+    #[sea_orm(has_many = "super::work::Entity")]
+    Work,
 }
 
 impl Related<super::book::Entity> for Entity {
@@ -35,10 +39,22 @@ impl Related<super::book::Entity> for Entity {
     }
 }
 
+impl Related<super::promotion::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Promotion.def()
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
     }
 }
 
+impl Related<super::work::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Work.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
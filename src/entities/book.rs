@@ -1,5 +1,7 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+use super::sea_orm_active_enums::BookFormat;
+use super::sea_orm_active_enums::DatePrecision;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -9,9 +11,23 @@ pub struct Model {
     pub id: i32,
     pub user_id: i32,
     pub author_id: i32,
+    pub work_id: i32,
+    pub publisher_id: Option<i32>,
+    pub imprint_id: Option<i32>,
+    pub series_id: Option<i32>,
+    #[sea_orm(column_type = "Decimal(Some((6, 2)))", nullable)]
+    pub series_position: Option<Decimal>,
     pub title: String,
-    pub year: String,
+    pub published_on: Option<Date>,
+    pub published_precision: Option<DatePrecision>,
+    pub legacy_year: Option<String>,
     pub cover: String,
+    pub format: Option<BookFormat>,
+    #[sea_orm(unique)]
+    pub isbn: Option<String>,
+    pub page_count: Option<i32>,
+    pub duration_minutes: Option<i32>,
+    pub language: Option<String>,
     pub created_at: Option<DateTimeUtc>,
     pub updated_at: Option<DateTimeUtc>,
 }
@@ -26,6 +42,52 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Author,
+    #[sea_orm(has_many = "super::book_copy::Entity")]
+    BookCopy,
+    #[sea_orm(has_many = "super::book_genre::Entity")]
+    BookGenre,
+    #[sea_orm(has_many = "super::book_tag::Entity")]
+    BookTag,
+    #[sea_orm(has_many = "super::cart_item::Entity")]
+    CartItem,
+    #[sea_orm(has_many = "super::hold::Entity")]
+    Hold,
+    #[sea_orm(
+        belongs_to = "super::imprint::Entity",
+        from = "Column::ImprintId",
+        to = "super::imprint::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Imprint,
+    #[sea_orm(has_many = "super::order_line::Entity")]
+    OrderLine,
+    #[sea_orm(has_many = "super::price::Entity")]
+    Price,
+    #[sea_orm(
+        belongs_to = "super::publisher::Entity",
+        from = "Column::PublisherId",
+        to = "super::publisher::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Publisher,
+    #[sea_orm(has_many = "super::reading_list_item::Entity")]
+    ReadingListItem,
+    #[sea_orm(has_many = "super::review::Entity")]
+    Review,
+    #[sea_orm(
+        belongs_to = "super::series::Entity",
+        from = "Column::SeriesId",
+        to = "super::series::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Series,
+    #[sea_orm(has_many = "super::stock_level::Entity")]
+    StockLevel,
+    #[sea_orm(has_many = "super::stock_movement::Entity")]
+    StockMovement,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::UserId",
@@ -34,6 +96,14 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     User,
+    #[sea_orm(
+        belongs_to = "super::work::Entity",
+        from = "Column::WorkId",
+        to = "super::work::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Work,
 }
 
 impl Related<super::author::Entity> for Entity {
@@ -42,10 +112,118 @@ impl Related<super::author::Entity> for Entity {
     }
 }
 
+impl Related<super::book_copy::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BookCopy.def()
+    }
+}
+
+impl Related<super::book_genre::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BookGenre.def()
+    }
+}
+
+impl Related<super::book_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BookTag.def()
+    }
+}
+
+impl Related<super::cart_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CartItem.def()
+    }
+}
+
+impl Related<super::genre::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::book_genre::Relation::Genre.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::book_genre::Relation::Book.def().rev())
+    }
+}
+
+impl Related<super::hold::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Hold.def()
+    }
+}
+
+impl Related<super::imprint::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Imprint.def()
+    }
+}
+
+impl Related<super::order_line::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrderLine.def()
+    }
+}
+
+impl Related<super::price::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Price.def()
+    }
+}
+
+impl Related<super::publisher::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Publisher.def()
+    }
+}
+
+impl Related<super::reading_list_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ReadingListItem.def()
+    }
+}
+
+impl Related<super::review::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Review.def()
+    }
+}
+
+impl Related<super::series::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Series.def()
+    }
+}
+
+impl Related<super::stock_level::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StockLevel.def()
+    }
+}
+
+impl Related<super::stock_movement::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StockMovement.def()
+    }
+}
+
+impl Related<super::tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::book_tag::Relation::Tag.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::book_tag::Relation::Book.def().rev())
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
     }
 }
 
+impl Related<super::work::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Work.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
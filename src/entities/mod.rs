@@ -2,6 +2,42 @@
 
 pub mod prelude;
 
+pub mod api_key;
 pub mod author;
 pub mod book;
+pub mod book_copy;
+pub mod book_genre;
+pub mod book_tag;
+pub mod cart;
+pub mod cart_item;
+pub mod genre;
+pub mod hold;
+pub mod imprint;
+pub mod invoice;
+pub mod invoice_sequence;
+pub mod loan;
+pub mod location;
+pub mod oauth_authorization_code;
+pub mod oauth_client;
+pub mod oauth_consent;
+pub mod order;
+pub mod order_line;
+pub mod price;
+pub mod promotion;
+pub mod promotion_redemption;
+pub mod publisher;
+pub mod reading_list;
+pub mod reading_list_item;
+pub mod recovery_code;
+pub mod review;
+pub mod sea_orm_active_enums;
+pub mod series;
+pub mod session;
+pub mod sign_in_throttle;
+pub mod signing_key;
+pub mod stock_level;
+pub mod stock_movement;
+pub mod tag;
+pub mod tax_rate;
 pub mod user;
+pub mod work;
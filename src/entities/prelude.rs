@@ -1,5 +1,40 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+pub use super::api_key::Entity as ApiKey;
 pub use super::author::Entity as Author;
 pub use super::book::Entity as Book;
+pub use super::book_copy::Entity as BookCopy;
+pub use super::book_genre::Entity as BookGenre;
+pub use super::book_tag::Entity as BookTag;
+pub use super::cart::Entity as Cart;
+pub use super::cart_item::Entity as CartItem;
+pub use super::genre::Entity as Genre;
+pub use super::hold::Entity as Hold;
+pub use super::imprint::Entity as Imprint;
+pub use super::invoice::Entity as Invoice;
+pub use super::invoice_sequence::Entity as InvoiceSequence;
+pub use super::loan::Entity as Loan;
+pub use super::location::Entity as Location;
+pub use super::oauth_authorization_code::Entity as OauthAuthorizationCode;
+pub use super::oauth_client::Entity as OauthClient;
+pub use super::oauth_consent::Entity as OauthConsent;
+pub use super::order::Entity as Order;
+pub use super::order_line::Entity as OrderLine;
+pub use super::price::Entity as Price;
+pub use super::promotion::Entity as Promotion;
+pub use super::promotion_redemption::Entity as PromotionRedemption;
+pub use super::publisher::Entity as Publisher;
+pub use super::reading_list::Entity as ReadingList;
+pub use super::reading_list_item::Entity as ReadingListItem;
+pub use super::recovery_code::Entity as RecoveryCode;
+pub use super::review::Entity as Review;
+pub use super::series::Entity as Series;
+pub use super::session::Entity as Session;
+pub use super::sign_in_throttle::Entity as SignInThrottle;
+pub use super::signing_key::Entity as SigningKey;
+pub use super::stock_level::Entity as StockLevel;
+pub use super::stock_movement::Entity as StockMovement;
+pub use super::tag::Entity as Tag;
+pub use super::tax_rate::Entity as TaxRate;
 pub use super::user::Entity as User;
+pub use super::work::Entity as Work;
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+use super::sea_orm_active_enums::UserRole;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -14,14 +15,64 @@ pub struct Model {
     pub lastname: Option<String>,
     pub created_at: Option<DateTimeUtc>,
     pub updated_at: Option<DateTimeUtc>,
+    pub role: UserRole,
+    pub email_verified_at: Option<DateTimeUtc>,
+    pub verification_sent_at: Option<DateTimeUtc>,
+    pub session_version: i32,
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<DateTimeUtc>,
+    pub deleted_at: Option<DateTimeUtc>,
+    pub totp_last_step: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::api_key::Entity")]
+    ApiKey,
     #[sea_orm(has_many = "super::author::Entity")]
     Author,
     #[sea_orm(has_many = "super::book::Entity")]
     Book,
+    #[sea_orm(has_one = "super::cart::Entity")]
+    Cart,
+    #[sea_orm(has_many = "super::hold::Entity")]
+    Hold,
+    #[sea_orm(has_many = "super::imprint::Entity")]
+    Imprint,
+    #[sea_orm(has_many = "super::loan::Entity")]
+    Loan,
+    #[sea_orm(has_many = "super::oauth_authorization_code::Entity")]
+    OauthAuthorizationCode,
+    #[sea_orm(has_many = "super::oauth_consent::Entity")]
+    OauthConsent,
+    #[sea_orm(has_many = "super::order::Entity")]
+    Order,
+    #[sea_orm(has_many = "super::promotion::Entity")]
+    Promotion,
+    #[sea_orm(has_many = "super::promotion_redemption::Entity")]
+    PromotionRedemption,
+    #[sea_orm(has_many = "super::publisher::Entity")]
+    Publisher,
+    #[sea_orm(has_many = "super::reading_list::Entity")]
+    ReadingList,
+    #[sea_orm(has_many = "super::recovery_code::Entity")]
+    RecoveryCode,
+    #[sea_orm(has_many = "super::review::Entity")]
+    Review,
+    #[sea_orm(has_many = "super::series::Entity")]
+    Series,
+    #[sea_orm(has_many = "super::session::Entity")]
+    Session,
+    #[sea_orm(has_many = "super::stock_movement::Entity")]
+    StockMovement,
+    #[sea_orm(has_many = "super::work::Entity")]
+    Work,
+}
+
+impl Related<super::api_key::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ApiKey.def()
+    }
 }
 
 impl Related<super::author::Entity> for Entity {
@@ -36,4 +87,106 @@ impl Related<super::book::Entity> for Entity {
     }
 }
 
+impl Related<super::cart::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Cart.def()
+    }
+}
+
+impl Related<super::hold::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Hold.def()
+    }
+}
+
+impl Related<super::imprint::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Imprint.def()
+    }
+}
+
+impl Related<super::loan::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Loan.def()
+    }
+}
+
+impl Related<super::oauth_authorization_code::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OauthAuthorizationCode.def()
+    }
+}
+
+impl Related<super::oauth_consent::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OauthConsent.def()
+    }
+}
+
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+
+impl Related<super::promotion::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Promotion.def()
+    }
+}
+
+impl Related<super::promotion_redemption::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PromotionRedemption.def()
+    }
+}
+
+impl Related<super::publisher::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Publisher.def()
+    }
+}
+
+impl Related<super::reading_list::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ReadingList.def()
+    }
+}
+
+impl Related<super::recovery_code::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecoveryCode.def()
+    }
+}
+
+impl Related<super::review::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Review.def()
+    }
+}
+
+impl Related<super::series::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Series.def()
+    }
+}
+
+impl Related<super::session::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Session.def()
+    }
+}
+
+impl Related<super::stock_movement::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StockMovement.def()
+    }
+}
+
+impl Related<super::work::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Work.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,203 @@
+use std::time::SystemTime;
+
+use rocket::serde::{json, Serialize};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    Select,
+};
+
+use crate::entities::{
+    api_key, author, book, hold, imprint, loan, oauth_consent, order, order_line, prelude::*,
+    publisher, reading_list, reading_list_item, review, series, session, user, work,
+};
+
+use self::tar::Archive;
+
+mod tar;
+
+const README: &str = "\
+This archive holds everything the bookstore stores about your account, as \
+JSON files:\n\n\
+profile.json          your account details\n\
+authors.json, publishers.json, imprints.json, series.json, works.json,\n\
+books.json            catalogue records you created\n\
+reviews.json          your reviews\n\
+orders.json, order_lines.json\n\
+                      your orders and what they contained\n\
+reading_lists.json, reading_list_items.json\n\
+                      your reading lists\n\
+holds.json, loans.json\n\
+                      your holds and loans\n\
+api_keys.json         your API keys, without the keys themselves\n\
+sessions.json         devices you signed in on\n\
+oauth_consents.json   apps you allowed to sign you in\n";
+
+fn to_json<T: Serialize>(value: &T) -> Vec<u8> {
+    json::to_pretty_string(value).unwrap().into_bytes()
+}
+
+async fn rows<E: EntityTrait>(
+    db: &DatabaseConnection,
+    select: Select<E>,
+) -> Result<Vec<u8>, DbErr> {
+    Ok(to_json(&select.into_json().all(db).await?))
+}
+
+/// Bundles the data tied to `user` into a tar archive.
+pub async fn render(db: &DatabaseConnection, user: &user::Model) -> Result<Vec<u8>, DbErr> {
+    let id = user.id;
+    let mtime = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let profile = User::find_by_id(id)
+        .select_only()
+        .columns([
+            user::Column::Id,
+            user::Column::Email,
+            user::Column::Firstname,
+            user::Column::Lastname,
+            user::Column::Role,
+            user::Column::EmailVerifiedAt,
+            user::Column::TotpEnabledAt,
+            user::Column::CreatedAt,
+            user::Column::UpdatedAt,
+        ])
+        .into_json()
+        .one(db)
+        .await?;
+
+    let order_ids = Order::find()
+        .select_only()
+        .column(order::Column::Id)
+        .filter(order::Column::UserId.eq(id))
+        .into_tuple::<i32>()
+        .all(db)
+        .await?;
+
+    let reading_list_ids = ReadingList::find()
+        .select_only()
+        .column(reading_list::Column::Id)
+        .filter(reading_list::Column::UserId.eq(id))
+        .into_tuple::<i32>()
+        .all(db)
+        .await?;
+
+    let files = [
+        ("profile.json", to_json(&profile)),
+        (
+            "authors.json",
+            rows(db, Author::find().filter(author::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "publishers.json",
+            rows(
+                db,
+                Publisher::find().filter(publisher::Column::UserId.eq(id)),
+            )
+            .await?,
+        ),
+        (
+            "imprints.json",
+            rows(db, Imprint::find().filter(imprint::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "series.json",
+            rows(db, Series::find().filter(series::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "works.json",
+            rows(db, Work::find().filter(work::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "books.json",
+            rows(db, Book::find().filter(book::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "reviews.json",
+            rows(db, Review::find().filter(review::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "orders.json",
+            rows(db, Order::find().filter(order::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "order_lines.json",
+            rows(
+                db,
+                OrderLine::find()
+                    .filter(order_line::Column::OrderId.is_in(order_ids))
+                    .order_by_asc(order_line::Column::OrderId),
+            )
+            .await?,
+        ),
+        (
+            "reading_lists.json",
+            rows(
+                db,
+                ReadingList::find().filter(reading_list::Column::UserId.eq(id)),
+            )
+            .await?,
+        ),
+        (
+            "reading_list_items.json",
+            rows(
+                db,
+                ReadingListItem::find()
+                    .filter(reading_list_item::Column::ReadingListId.is_in(reading_list_ids))
+                    .order_by_asc(reading_list_item::Column::ReadingListId)
+                    .order_by_asc(reading_list_item::Column::Position),
+            )
+            .await?,
+        ),
+        (
+            "holds.json",
+            rows(db, Hold::find().filter(hold::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "loans.json",
+            rows(db, Loan::find().filter(loan::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "api_keys.json",
+            rows(
+                db,
+                ApiKey::find()
+                    .select_only()
+                    .columns([
+                        api_key::Column::Id,
+                        api_key::Column::Name,
+                        api_key::Column::Scope,
+                        api_key::Column::Prefix,
+                        api_key::Column::LastUsedAt,
+                        api_key::Column::RevokedAt,
+                        api_key::Column::CreatedAt,
+                    ])
+                    .filter(api_key::Column::UserId.eq(id)),
+            )
+            .await?,
+        ),
+        (
+            "sessions.json",
+            rows(db, Session::find().filter(session::Column::UserId.eq(id))).await?,
+        ),
+        (
+            "oauth_consents.json",
+            rows(
+                db,
+                OauthConsent::find().filter(oauth_consent::Column::UserId.eq(id)),
+            )
+            .await?,
+        ),
+    ];
+
+    let mut archive = Archive::new();
+    archive.add("README.txt", mtime, README.as_bytes());
+
+    for (name, contents) in files {
+        archive.add(name, mtime, &contents);
+    }
+
+    Ok(archive.finish())
+}
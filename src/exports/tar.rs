@@ -0,0 +1,117 @@
+//! Just enough of the ustar format to bundle a few regular files, which
+//! every archiver can unpack.
+
+const BLOCK: usize = 512;
+
+#[derive(Default)]
+pub struct Archive {
+    data: Vec<u8>,
+}
+
+/// Writes `value` as a NUL-terminated octal number filling `field`.
+fn octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+impl Archive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file readable by everyone. Names must be ASCII and at most 100
+    /// bytes long.
+    pub fn add(&mut self, name: &str, mtime: u64, contents: &[u8]) {
+        let mut header = [0u8; BLOCK];
+
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        octal(&mut header[100..108], 0o644);
+        octal(&mut header[108..116], 0);
+        octal(&mut header[116..124], 0);
+        octal(&mut header[124..136], contents.len() as u64);
+        octal(&mut header[136..148], mtime);
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        // The checksum is computed with its own field filled with spaces.
+        header[148..156].fill(b' ');
+        let checksum = header.iter().map(|b| *b as u64).sum::<u64>();
+        octal(&mut header[148..155], checksum);
+
+        self.data.extend_from_slice(&header);
+        self.data.extend_from_slice(contents);
+        self.data.resize(self.data.len().next_multiple_of(BLOCK), 0);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        // Two empty blocks mark the end of the archive.
+        self.data.resize(self.data.len() + 2 * BLOCK, 0);
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(header: &[u8], range: std::ops::Range<usize>) -> &str {
+        std::str::from_utf8(&header[range]).unwrap()
+    }
+
+    #[test]
+    fn octal_fills_the_field_and_ends_with_nul() {
+        let mut field = [0xffu8; 8];
+        octal(&mut field, 0o644);
+
+        assert_eq!(&field, b"0000644\0");
+    }
+
+    #[test]
+    fn header_describes_the_file() {
+        let mut archive = Archive::new();
+        archive.add("profile.json", 1_700_000_000, b"{}");
+        let data = archive.finish();
+        let header = &data[..BLOCK];
+
+        assert_eq!(&header[..13], b"profile.json\0");
+        assert_eq!(field(header, 100..108), "0000644\0");
+        assert_eq!(field(header, 124..136), "00000000002\0");
+        assert_eq!(field(header, 136..148), "14524770400\0");
+        assert_eq!(header[156], b'0');
+        assert_eq!(&header[257..265], b"ustar\x0000");
+    }
+
+    #[test]
+    fn checksum_covers_the_header_with_a_blank_checksum_field() {
+        let mut archive = Archive::new();
+        archive.add("orders.json", 0, b"[]");
+        let data = archive.finish();
+        let header = &data[..BLOCK];
+
+        let stored = u64::from_str_radix(field(header, 148..154), 8).unwrap();
+        let expected = header[..148]
+            .iter()
+            .chain(&[b' '; 8])
+            .chain(&header[156..])
+            .map(|b| *b as u64)
+            .sum::<u64>();
+
+        assert_eq!(stored, expected);
+        assert_eq!(&header[154..156], b"\0 ");
+    }
+
+    #[test]
+    fn contents_are_padded_to_whole_blocks() {
+        let mut archive = Archive::new();
+        archive.add("a.json", 0, &[b'a'; BLOCK + 1]);
+        archive.add("b.json", 0, b"");
+        let data = archive.finish();
+
+        // Header and two blocks for a, a header for b, then the end marker.
+        assert_eq!(data.len(), 6 * BLOCK);
+        assert_eq!(&data[3 * BLOCK..3 * BLOCK + 7], b"b.json\0");
+        assert!(data[4 * BLOCK..].iter().all(|b| *b == 0));
+    }
+}
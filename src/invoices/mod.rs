@@ -0,0 +1,155 @@
+use sea_orm::prelude::{DateTimeUtc, Decimal};
+
+use crate::entities::{order, order_line, user};
+
+use self::pdf::{Document, PAGE_HEIGHT, PAGE_WIDTH};
+
+mod pdf;
+
+const MARGIN: f32 = 50.0;
+const LINE_HEIGHT: f32 = 16.0;
+
+/// Invoice numbers as printed, e.g. `INV-000042`.
+pub fn format_number(number: i32) -> String {
+    format!("INV-{:06}", number)
+}
+
+/// Shortens `value` to at most `max` characters.
+fn truncate(value: &str, max: usize) -> String {
+    if value.chars().count() <= max {
+        value.to_string()
+    } else {
+        format!("{}...", value.chars().take(max - 3).collect::<String>())
+    }
+}
+
+fn money(amount: Decimal) -> String {
+    format!("{:.2}", amount)
+}
+
+/// Lays out the invoice for a paid order.
+pub fn render(
+    number: i32,
+    issued_at: DateTimeUtc,
+    order: &order::Model,
+    lines: &[order_line::Model],
+    customer: &user::Model,
+) -> Vec<u8> {
+    let mut doc = Document::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    doc.text(
+        MARGIN,
+        y,
+        20.0,
+        true,
+        &format!("Invoice {}", format_number(number)),
+    );
+    y -= 2.0 * LINE_HEIGHT;
+
+    let name = [customer.firstname.as_deref(), customer.lastname.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let details = [
+        ("Issued", issued_at.format("%Y-%m-%d").to_string()),
+        ("Order", format!("#{}", order.id)),
+        (
+            "Customer",
+            if name.is_empty() {
+                customer.email.to_owned()
+            } else {
+                name
+            },
+        ),
+        ("Email", customer.email.to_owned()),
+        ("Region", order.region.to_owned().unwrap_or_default()),
+        ("Currency", order.currency.to_owned()),
+    ];
+
+    for (label, value) in details {
+        doc.text(MARGIN, y, 10.0, true, label);
+        doc.text(MARGIN + 80.0, y, 10.0, false, &value);
+        y -= LINE_HEIGHT;
+    }
+
+    let columns = [
+        ("Item", MARGIN),
+        ("Qty", 300.0),
+        ("Unit", 340.0),
+        ("Discount", 400.0),
+        ("Tax", 460.0),
+        ("Total", PAGE_WIDTH - MARGIN - 50.0),
+    ];
+
+    let header = |doc: &mut Document, y: f32| {
+        for (label, x) in columns {
+            doc.text(x, y, 10.0, true, label);
+        }
+    };
+
+    y -= LINE_HEIGHT;
+    header(&mut doc, y);
+    y -= LINE_HEIGHT;
+
+    for line in lines {
+        if y < MARGIN + 5.0 * LINE_HEIGHT {
+            doc.add_page();
+            y = PAGE_HEIGHT - MARGIN;
+            header(&mut doc, y);
+            y -= LINE_HEIGHT;
+        }
+
+        let total = line.line_total - line.discount + line.tax_amount;
+        let tax = match line.tax_percent {
+            Some(p) => format!("{} ({}%)", money(line.tax_amount), p.normalize()),
+            None => money(line.tax_amount),
+        };
+
+        let cells = [
+            truncate(&line.title, 45),
+            line.quantity.to_string(),
+            money(line.unit_price),
+            money(line.discount),
+            tax,
+            money(total),
+        ];
+
+        for ((_, x), cell) in columns.iter().zip(cells) {
+            doc.text(*x, y, 9.0, false, &cell);
+        }
+
+        y -= LINE_HEIGHT;
+    }
+
+    y -= LINE_HEIGHT;
+
+    let totals = [
+        ("Subtotal", order.subtotal),
+        ("Discount", -order.discount_total),
+        ("Tax", order.tax_total),
+        ("Total", order.total),
+    ];
+
+    for (label, amount) in totals {
+        let bold = label == "Total";
+        doc.text(400.0, y, 10.0, bold, label);
+        doc.text(PAGE_WIDTH - MARGIN - 50.0, y, 10.0, bold, &money(amount));
+        y -= LINE_HEIGHT;
+    }
+
+    if let Some(reference) = &order.payment_reference {
+        y -= LINE_HEIGHT;
+        doc.text(
+            MARGIN,
+            y,
+            8.0,
+            false,
+            &format!("Payment reference: {}", reference),
+        );
+    }
+
+    doc.render()
+}
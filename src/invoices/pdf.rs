@@ -0,0 +1,201 @@
+//! Just enough of PDF 1.4 to lay out text on A4 pages with the standard
+//! Helvetica fonts, which every viewer ships, so nothing is embedded.
+
+pub const PAGE_WIDTH: f32 = 595.0;
+pub const PAGE_HEIGHT: f32 = 842.0;
+
+pub struct Text {
+    x: f32,
+    y: f32,
+    size: f32,
+    bold: bool,
+    value: String,
+}
+
+#[derive(Default)]
+pub struct Document {
+    pages: Vec<Vec<Text>>,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_page(&mut self) {
+        self.pages.push(vec![]);
+    }
+
+    /// Writes `value` on the current page with its baseline at `y` points
+    /// from the bottom.
+    pub fn text(&mut self, x: f32, y: f32, size: f32, bold: bool, value: &str) {
+        if self.pages.is_empty() {
+            self.add_page();
+        }
+
+        self.pages.last_mut().unwrap().push(Text {
+            x,
+            y,
+            size,
+            bold,
+            value: value.to_string(),
+        });
+    }
+
+    pub fn render(self) -> Vec<u8> {
+        let mut objects: Vec<Vec<u8>> = vec![];
+
+        // 1: catalog, 2: page tree, 3 and 4: fonts, then a page and its
+        // content stream for every page.
+        let page_ids = (0..self.pages.len()).map(|i| 5 + 2 * i).collect::<Vec<_>>();
+
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids
+                    .iter()
+                    .map(|id| format!("{} 0 R", id))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                page_ids.len()
+            )
+            .into_bytes(),
+        );
+        objects.push(
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        );
+        objects.push(
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        );
+
+        for (page, id) in self.pages.iter().zip(&page_ids) {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    PAGE_WIDTH,
+                    PAGE_HEIGHT,
+                    id + 1
+                )
+                .into_bytes(),
+            );
+
+            let mut content = vec![];
+
+            for text in page {
+                content.extend_from_slice(
+                    format!(
+                        "BT /{} {} Tf {} {} Td (",
+                        if text.bold { "F2" } else { "F1" },
+                        text.size,
+                        text.x,
+                        text.y
+                    )
+                    .as_bytes(),
+                );
+                content.extend(escape(&text.value));
+                content.extend_from_slice(b") Tj ET\n");
+            }
+
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend(content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = vec![];
+
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = out.len();
+        out.extend_from_slice(format!("xref\n0 {}\n", objects.len() + 1).as_bytes());
+        out.extend_from_slice(b"0000000000 65535 f \n");
+
+        for offset in offsets {
+            out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+
+        out.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .as_bytes(),
+        );
+
+        out
+    }
+}
+
+/// Encodes a string for a PDF literal. WinAnsi covers Latin-1, anything
+/// beyond that is replaced.
+fn escape(value: &str) -> Vec<u8> {
+    let mut out = vec![];
+
+    for c in value.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                out.push(c as u8);
+            }
+            ' '..='~' => out.push(c as u8),
+            '\u{a0}'..='\u{ff}' => out.extend_from_slice(format!("\\{:o}", c as u32).as_bytes()),
+            _ => out.push(b'?'),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_ascii_is_unchanged() {
+        assert_eq!(
+            escape("Invoice INV-000042: 3 x 9.99"),
+            b"Invoice INV-000042: 3 x 9.99"
+        );
+    }
+
+    #[test]
+    fn delimiters_are_escaped() {
+        assert_eq!(escape(r"a (b) \ c"), br"a \(b\) \\ c");
+    }
+
+    #[test]
+    fn latin1_is_written_as_octal() {
+        assert_eq!(escape("Müller"), br"M\374ller");
+        assert_eq!(escape("\u{a0}ÿ"), br"\240\377");
+    }
+
+    #[test]
+    fn other_characters_are_replaced() {
+        assert_eq!(escape("10 €"), b"10 ?");
+        assert_eq!(escape("a\nb\tc"), b"a?b?c");
+        assert_eq!(escape("東京"), b"??");
+    }
+
+    #[test]
+    fn text_is_escaped_in_the_document() {
+        let mut document = Document::new();
+        document.text(50.0, 800.0, 12.0, false, "Smith (Jr.)");
+
+        let pdf = String::from_utf8(document.render()).unwrap();
+
+        assert!(pdf.contains(r"(Smith \(Jr.\)) Tj"));
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+    }
+}
@@ -1,15 +1,27 @@
 #[macro_use]
 extern crate rocket;
 
+use auth::keyring::Keyring;
+use entities::sea_orm_active_enums::SigningAlgorithm;
 use fairings::{options, CORS};
+use mail::{Mailer, OutboxMailer, SmtpMailer};
 use migrator::{Migrator, MigratorTrait};
+use payments::{FakePaymentProvider, PaymentProvider};
+use storage::{BlobStorage, LocalStorage};
 
 mod auth;
 mod controllers;
+mod dates;
 mod db;
 mod entities;
+mod exports;
 mod fairings;
+mod invoices;
+mod mail;
 mod migrator;
+mod payments;
+mod promotions;
+mod storage;
 
 use controllers::{Response, SuccessResponse};
 use rocket::http::Status;
@@ -21,6 +33,17 @@ pub struct AppConfig {
     db_password: String,
     db_database: String,
     jwt_secret: String,
+    jwt_algorithm: SigningAlgorithm,
+    jwt_issuer: String,
+    jwt_audience: String,
+    jwt_rotation_days: i64,
+    storage_dir: String,
+    smtp_url: Option<String>,
+    mail_from: String,
+    outbox_dir: String,
+    require_verified_email: bool,
+    session_cookie: bool,
+    public_url: String,
 }
 
 impl Default for AppConfig {
@@ -34,6 +57,32 @@ impl Default for AppConfig {
             db_database: std::env::var("BOOKSTORE_DB_DATABASE").unwrap_or("bookstore".to_string()),
             jwt_secret: std::env::var("BOOKSTORE_JWT_SECRET")
                 .expect("Please set the BOOKSTORE_JWT_SECRET env. variable"),
+            jwt_algorithm: match std::env::var("BOOKSTORE_JWT_ALGORITHM").as_deref() {
+                Ok("RS256") => SigningAlgorithm::RS256,
+                Ok("EdDSA") | Err(_) => SigningAlgorithm::EdDSA,
+                Ok(_) => panic!("BOOKSTORE_JWT_ALGORITHM must be RS256 or EdDSA"),
+            },
+            jwt_issuer: std::env::var("BOOKSTORE_JWT_ISSUER").unwrap_or("bookstore".to_string()),
+            jwt_audience: std::env::var("BOOKSTORE_JWT_AUDIENCE")
+                .unwrap_or("bookstore-api".to_string()),
+            jwt_rotation_days: std::env::var("BOOKSTORE_JWT_ROTATION_DAYS")
+                .map(|v| {
+                    v.parse()
+                        .expect("BOOKSTORE_JWT_ROTATION_DAYS must be a number")
+                })
+                .unwrap_or(30),
+            storage_dir: std::env::var("BOOKSTORE_STORAGE_DIR").unwrap_or("storage".to_string()),
+            smtp_url: std::env::var("BOOKSTORE_SMTP_URL").ok(),
+            mail_from: std::env::var("BOOKSTORE_MAIL_FROM")
+                .unwrap_or("Bookstore <no-reply@localhost>".to_string()),
+            outbox_dir: std::env::var("BOOKSTORE_OUTBOX_DIR").unwrap_or("outbox".to_string()),
+            require_verified_email: std::env::var("BOOKSTORE_REQUIRE_VERIFIED_EMAIL")
+                .is_ok_and(|v| v == "true" || v == "1"),
+            session_cookie: std::env::var("BOOKSTORE_SESSION_COOKIE")
+                .is_ok_and(|v| v == "true" || v == "1"),
+            public_url: std::env::var("BOOKSTORE_PUBLIC_URL")
+                .map(|v| v.trim_end_matches('/').to_string())
+                .unwrap_or("http://localhost:8000".to_string()),
         }
     }
 }
@@ -52,20 +101,91 @@ async fn rocket() -> _ {
     let db = db::connect(&config).await.unwrap();
     Migrator::up(&db, None).await.unwrap();
 
+    let keyring = Keyring::new(config.jwt_algorithm, config.jwt_rotation_days);
+    keyring
+        .rotate(&db)
+        .await
+        .expect("Could not set up the JWT signing keys");
+    keyring.spawn_rotation(db.clone());
+
+    let from = config
+        .mail_from
+        .parse()
+        .expect("BOOKSTORE_MAIL_FROM must be a valid mailbox");
+    let mailer: Box<dyn Mailer> = match &config.smtp_url {
+        Some(url) => Box::new(SmtpMailer::new(url, from).expect("Invalid BOOKSTORE_SMTP_URL")),
+        None => Box::new(OutboxMailer::new(&config.outbox_dir, from)),
+    };
+
     rocket::build()
         .attach(CORS)
         .manage(db)
+        .manage::<Box<dyn PaymentProvider>>(Box::new(FakePaymentProvider))
+        .manage::<Box<dyn BlobStorage>>(Box::new(LocalStorage::new(&config.storage_dir)))
+        .manage(mailer)
+        .manage(keyring)
         .manage(config)
+        .register(
+            "/",
+            catchers![controllers::unauthorized, controllers::forbidden],
+        )
         .mount("/", routes![options])
         .mount("/", routes![index])
+        .mount(
+            "/.well-known",
+            routes![
+                controllers::well_known::jwks,
+                controllers::well_known::openid_configuration
+            ],
+        )
+        .mount(
+            "/oauth",
+            routes![
+                controllers::oauth::authorize,
+                controllers::oauth::consent,
+                controllers::oauth::token,
+                controllers::oauth::userinfo
+            ],
+        )
+        .mount(
+            "/oauth/clients",
+            routes![
+                controllers::oauth::clients,
+                controllers::oauth::register_client,
+                controllers::oauth::remove_client
+            ],
+        )
         .mount(
             "/auth",
             routes![
                 controllers::auth::sign_in,
+                controllers::auth::sign_out,
+                controllers::session::index,
+                controllers::session::revoke_others,
+                controllers::session::revoke,
                 controllers::auth::sign_up,
-                controllers::auth::me
+                controllers::auth::me,
+                controllers::auth::update_me,
+                controllers::account::change_email,
+                controllers::account::confirm_email,
+                controllers::account::export,
+                controllers::account::delete_account,
+                controllers::auth::verify_email,
+                controllers::auth::resend_verification,
+                controllers::auth::forgot_password,
+                controllers::auth::reset_password,
+                controllers::auth::change_password,
+                controllers::two_factor::enroll,
+                controllers::two_factor::confirm,
+                controllers::two_factor::regenerate_recovery_codes,
+                controllers::two_factor::disable,
+                controllers::two_factor::sign_in
             ],
         )
+        .mount(
+            "/lockouts",
+            routes![controllers::lockout::index, controllers::lockout::unlock],
+        )
         .mount(
             "/authors",
             routes![
@@ -76,6 +196,49 @@ async fn rocket() -> _ {
                 controllers::author::delete
             ],
         )
+        .mount(
+            "/publishers",
+            routes![
+                controllers::publisher::index,
+                controllers::publisher::create,
+                controllers::publisher::show,
+                controllers::publisher::imprints,
+                controllers::publisher::update,
+                controllers::publisher::delete
+            ],
+        )
+        .mount(
+            "/imprints",
+            routes![
+                controllers::imprint::index,
+                controllers::imprint::create,
+                controllers::imprint::show,
+                controllers::imprint::update,
+                controllers::imprint::delete
+            ],
+        )
+        .mount(
+            "/series",
+            routes![
+                controllers::series::index,
+                controllers::series::create,
+                controllers::series::show,
+                controllers::series::books,
+                controllers::series::update,
+                controllers::series::delete
+            ],
+        )
+        .mount(
+            "/works",
+            routes![
+                controllers::work::index,
+                controllers::work::create,
+                controllers::work::show,
+                controllers::work::editions,
+                controllers::work::update,
+                controllers::work::delete
+            ],
+        )
         .mount(
             "/books",
             routes![
@@ -83,7 +246,150 @@ async fn rocket() -> _ {
                 controllers::book::create,
                 controllers::book::show,
                 controllers::book::update,
-                controllers::book::delete
+                controllers::book::delete,
+                controllers::price::index,
+                controllers::price::update,
+                controllers::price::delete,
+                controllers::stock::show,
+                controllers::stock::threshold,
+                controllers::review::index,
+                controllers::review::create,
+                controllers::book_copy::index,
+                controllers::book_copy::create,
+                controllers::hold::place
+            ],
+        )
+        .mount(
+            "/loans",
+            routes![
+                controllers::loan::index,
+                controllers::loan::overdue,
+                controllers::loan::checkout,
+                controllers::loan::renew,
+                controllers::loan::give_back
+            ],
+        )
+        .mount(
+            "/holds",
+            routes![controllers::hold::index, controllers::hold::cancel],
+        )
+        .mount(
+            "/me/lists",
+            routes![
+                controllers::reading_list::index,
+                controllers::reading_list::create,
+                controllers::reading_list::show,
+                controllers::reading_list::update,
+                controllers::reading_list::delete,
+                controllers::reading_list::add_item,
+                controllers::reading_list::update_item,
+                controllers::reading_list::remove_item
+            ],
+        )
+        .mount(
+            "/me/api-keys",
+            routes![
+                controllers::api_key::index,
+                controllers::api_key::create,
+                controllers::api_key::revoke
+            ],
+        )
+        .mount(
+            "/lists",
+            routes![
+                controllers::reading_list::public,
+                controllers::reading_list::shared
+            ],
+        )
+        .mount(
+            "/reviews",
+            routes![
+                controllers::review::mine,
+                controllers::review::update,
+                controllers::review::delete,
+                controllers::review::queue,
+                controllers::review::moderate
+            ],
+        )
+        .mount(
+            "/genres",
+            routes![
+                controllers::genre::index,
+                controllers::genre::create,
+                controllers::genre::show,
+                controllers::genre::children,
+                controllers::genre::update,
+                controllers::genre::delete
+            ],
+        )
+        .mount(
+            "/tags",
+            routes![
+                controllers::tag::index,
+                controllers::tag::create,
+                controllers::tag::show,
+                controllers::tag::update,
+                controllers::tag::delete
+            ],
+        )
+        .mount(
+            "/cart",
+            routes![
+                controllers::cart::show,
+                controllers::cart::add,
+                controllers::cart::update,
+                controllers::cart::remove,
+                controllers::cart::apply_coupon,
+                controllers::cart::remove_coupon,
+                controllers::cart::clear
+            ],
+        )
+        .mount("/", routes![controllers::order::checkout])
+        .mount(
+            "/orders",
+            routes![
+                controllers::order::index,
+                controllers::order::show,
+                controllers::order::cancel,
+                controllers::order::update_status,
+                controllers::invoice::show
+            ],
+        )
+        .mount(
+            "/promotions",
+            routes![
+                controllers::promotion::index,
+                controllers::promotion::create,
+                controllers::promotion::show,
+                controllers::promotion::update,
+                controllers::promotion::delete
+            ],
+        )
+        .mount(
+            "/tax-rates",
+            routes![
+                controllers::tax_rate::index,
+                controllers::tax_rate::create,
+                controllers::tax_rate::update,
+                controllers::tax_rate::delete
+            ],
+        )
+        .mount(
+            "/locations",
+            routes![
+                controllers::location::index,
+                controllers::location::create,
+                controllers::location::show,
+                controllers::location::update,
+                controllers::location::delete
+            ],
+        )
+        .mount(
+            "/stock",
+            routes![
+                controllers::stock::movements,
+                controllers::stock::create_movement,
+                controllers::stock::low
             ],
         )
 }
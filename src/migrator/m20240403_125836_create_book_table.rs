@@ -61,7 +61,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(Iden)]
-enum Book {
+pub enum Book {
     Table,
     Id,
     UserId,
@@ -0,0 +1,201 @@
+use chrono::{Datelike, NaiveDate};
+use sea_orm::ConnectionTrait;
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+
+// Migrations keep their own copy of the date rules, so later changes to the
+// application's parser cannot change what this migration did.
+
+/// Parses `YYYY`, `YYYY-MM` or `YYYY-MM-DD` into the first day it covers and
+/// its `published_precision`.
+fn parse(value: &str) -> Option<(NaiveDate, &'static str)> {
+    let parts = value.trim().split('-').collect::<Vec<_>>();
+
+    let number = |s: &str, len: usize| -> Option<u32> {
+        if s.len() == len && s.chars().all(|c| c.is_ascii_digit()) {
+            s.parse().ok()
+        } else {
+            None
+        }
+    };
+
+    let year = number(parts[0], 4)? as i32;
+
+    match parts[1..] {
+        [] => Some((NaiveDate::from_ymd_opt(year, 1, 1)?, "year")),
+        [month] => Some((
+            NaiveDate::from_ymd_opt(year, number(month, 2)?, 1)?,
+            "month",
+        )),
+        [month, day] => Some((
+            NaiveDate::from_ymd_opt(year, number(month, 2)?, number(day, 2)?)?,
+            "day",
+        )),
+        _ => None,
+    }
+}
+
+/// The inverse of `parse`.
+fn format(date: NaiveDate, precision: &str) -> Option<String> {
+    match precision {
+        "year" => Some(format!("{:04}", date.year())),
+        "month" => Some(format!("{:04}-{:02}", date.year(), date.month())),
+        "day" => Some(date.format("%Y-%m-%d").to_string()),
+        _ => None,
+    }
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .rename_column(Book::Year, PublishedBook::LegacyYear)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .modify_column(ColumnDef::new(PublishedBook::LegacyYear).string().null())
+                    .add_column(ColumnDef::new(PublishedBook::PublishedOn).date().null())
+                    .add_column(
+                        ColumnDef::new(PublishedBook::PublishedPrecision)
+                            .string_len(5)
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        let rows = db
+            .query_all(
+                backend.build(
+                    Query::select()
+                        .columns([Book::Id.into_iden(), PublishedBook::LegacyYear.into_iden()])
+                        .from(Book::Table),
+                ),
+            )
+            .await?;
+
+        // Unparseable values stay in `legacy_year` so they can be fixed by hand.
+        for row in rows {
+            let id: i32 = row.try_get("", &Book::Id.to_string())?;
+            let year: String = row.try_get("", &PublishedBook::LegacyYear.to_string())?;
+
+            let (date, precision) = match parse(&year) {
+                Some(d) => d,
+                None => {
+                    eprintln!(
+                        "book {}: cannot parse publication date `{}`, kept in `legacy_year`",
+                        id, year
+                    );
+                    continue;
+                }
+            };
+
+            db.execute(
+                backend.build(
+                    Query::update()
+                        .table(Book::Table)
+                        .values([
+                            (PublishedBook::PublishedOn, date.into()),
+                            (PublishedBook::PublishedPrecision, precision.into()),
+                            (PublishedBook::LegacyYear, Option::<String>::None.into()),
+                        ])
+                        .and_where(Expr::col(Book::Id).eq(id)),
+                ),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        let rows = db
+            .query_all(
+                backend.build(
+                    Query::select()
+                        .columns([
+                            Book::Id.into_iden(),
+                            PublishedBook::PublishedOn.into_iden(),
+                            PublishedBook::PublishedPrecision.into_iden(),
+                        ])
+                        .from(Book::Table)
+                        .and_where(Expr::col(PublishedBook::PublishedOn).is_not_null()),
+                ),
+            )
+            .await?;
+
+        for row in rows {
+            let id: i32 = row.try_get("", &Book::Id.to_string())?;
+            let date: NaiveDate = row.try_get("", &PublishedBook::PublishedOn.to_string())?;
+            let precision: Option<String> =
+                row.try_get("", &PublishedBook::PublishedPrecision.to_string())?;
+
+            let year = precision.and_then(|p| format(date, &p)).unwrap_or_default();
+
+            db.execute(
+                backend.build(
+                    Query::update()
+                        .table(Book::Table)
+                        .value(PublishedBook::LegacyYear, year)
+                        .and_where(Expr::col(Book::Id).eq(id)),
+                ),
+            )
+            .await?;
+        }
+
+        db.execute(
+            backend.build(
+                Query::update()
+                    .table(Book::Table)
+                    .value(PublishedBook::LegacyYear, "")
+                    .and_where(Expr::col(PublishedBook::LegacyYear).is_null()),
+            ),
+        )
+        .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .rename_column(PublishedBook::LegacyYear, Book::Year)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .modify_column(ColumnDef::new(Book::Year).string().not_null())
+                    .drop_column(PublishedBook::PublishedOn)
+                    .drop_column(PublishedBook::PublishedPrecision)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum PublishedBook {
+    PublishedOn,
+    PublishedPrecision,
+    LegacyYear,
+}
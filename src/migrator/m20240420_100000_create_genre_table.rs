@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Genre::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Genre::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Genre::ParentId).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-genre-parent_id")
+                            .from(Genre::Table, Genre::ParentId)
+                            .to(Genre::Table, Genre::Id),
+                    )
+                    .col(ColumnDef::new(Genre::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(Genre::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Genre::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Genre::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Genre {
+    Table,
+    Id,
+    ParentId,
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
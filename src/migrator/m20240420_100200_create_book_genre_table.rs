@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240420_100000_create_genre_table::Genre;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BookGenre::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(BookGenre::BookId).integer().not_null())
+                    .col(ColumnDef::new(BookGenre::GenreId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(BookGenre::BookId)
+                            .col(BookGenre::GenreId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-book_genre-book_id")
+                            .from(BookGenre::Table, BookGenre::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-book_genre-genre_id")
+                            .from(BookGenre::Table, BookGenre::GenreId)
+                            .to(Genre::Table, Genre::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BookGenre::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BookGenre {
+    Table,
+    BookId,
+    GenreId,
+}
@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240420_100100_create_tag_table::Tag;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BookTag::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(BookTag::BookId).integer().not_null())
+                    .col(ColumnDef::new(BookTag::TagId).integer().not_null())
+                    .primary_key(Index::create().col(BookTag::BookId).col(BookTag::TagId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-book_tag-book_id")
+                            .from(BookTag::Table, BookTag::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-book_tag-tag_id")
+                            .from(BookTag::Table, BookTag::TagId)
+                            .to(Tag::Table, Tag::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BookTag::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BookTag {
+    Table,
+    BookId,
+    TagId,
+}
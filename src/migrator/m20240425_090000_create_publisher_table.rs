@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Publisher::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Publisher::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Publisher::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-publisher-user_id")
+                            .from(Publisher::Table, Publisher::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .col(ColumnDef::new(Publisher::Name).string().not_null())
+                    .col(ColumnDef::new(Publisher::Website).string().null())
+                    .col(
+                        ColumnDef::new(Publisher::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Publisher::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Publisher::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Publisher {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Website,
+    CreatedAt,
+    UpdatedAt,
+}
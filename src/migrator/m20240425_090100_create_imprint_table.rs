@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240425_090000_create_publisher_table::Publisher;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Imprint::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Imprint::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Imprint::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-imprint-user_id")
+                            .from(Imprint::Table, Imprint::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .col(ColumnDef::new(Imprint::PublisherId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-imprint-publisher_id")
+                            .from(Imprint::Table, Imprint::PublisherId)
+                            .to(Publisher::Table, Publisher::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Imprint::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(Imprint::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Imprint::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Imprint::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Imprint {
+    Table,
+    Id,
+    UserId,
+    PublisherId,
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
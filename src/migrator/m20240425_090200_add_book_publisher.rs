@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240425_090000_create_publisher_table::Publisher;
+use super::m20240425_090100_create_imprint_table::Imprint;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .add_column(ColumnDef::new(BookPublisher::PublisherId).integer().null())
+                    .add_column(ColumnDef::new(BookPublisher::ImprintId).integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-book-publisher_id")
+                            .from_tbl(Book::Table)
+                            .from_col(BookPublisher::PublisherId)
+                            .to_tbl(Publisher::Table)
+                            .to_col(Publisher::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-book-imprint_id")
+                            .from_tbl(Book::Table)
+                            .from_col(BookPublisher::ImprintId)
+                            .to_tbl(Imprint::Table)
+                            .to_col(Imprint::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .drop_foreign_key(Alias::new("fk-book-publisher_id"))
+                    .drop_foreign_key(Alias::new("fk-book-imprint_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .drop_column(BookPublisher::PublisherId)
+                    .drop_column(BookPublisher::ImprintId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BookPublisher {
+    PublisherId,
+    ImprintId,
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Series::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Series::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Series::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-series-user_id")
+                            .from(Series::Table, Series::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .col(ColumnDef::new(Series::Title).string().not_null())
+                    .col(ColumnDef::new(Series::Description).string().null())
+                    .col(
+                        ColumnDef::new(Series::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Series::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Series::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Series {
+    Table,
+    Id,
+    UserId,
+    Title,
+    Description,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240502_080000_create_series_table::Series;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .add_column(ColumnDef::new(BookSeries::SeriesId).integer().null())
+                    .add_column(
+                        ColumnDef::new(BookSeries::SeriesPosition)
+                            .decimal_len(6, 2)
+                            .null(),
+                    )
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-book-series_id")
+                            .from_tbl(Book::Table)
+                            .from_col(BookSeries::SeriesId)
+                            .to_tbl(Series::Table)
+                            .to_col(Series::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-book-series_position")
+                    .table(Book::Table)
+                    .col(BookSeries::SeriesId)
+                    .col(BookSeries::SeriesPosition)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .drop_foreign_key(Alias::new("fk-book-series_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-book-series_position")
+                    .table(Book::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .drop_column(BookSeries::SeriesId)
+                    .drop_column(BookSeries::SeriesPosition)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BookSeries {
+    SeriesId,
+    SeriesPosition,
+}
@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240403_124359_create_author_table::Author;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Work::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Work::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Work::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-work-user_id")
+                            .from(Work::Table, Work::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .col(ColumnDef::new(Work::AuthorId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-work-author_id")
+                            .from(Work::Table, Work::AuthorId)
+                            .to(Author::Table, Author::Id),
+                    )
+                    .col(ColumnDef::new(Work::Title).string().not_null())
+                    .col(
+                        ColumnDef::new(Work::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Work::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Work::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Work {
+    Table,
+    Id,
+    UserId,
+    AuthorId,
+    Title,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,142 @@
+use sea_orm::ConnectionTrait;
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240510_070000_create_work_table::Work;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .add_column(ColumnDef::new(Edition::WorkId).integer().null())
+                    .add_column(ColumnDef::new(Edition::Format).string_len(10).null())
+                    .add_column(ColumnDef::new(Edition::Isbn).string_len(13).null())
+                    .add_column(ColumnDef::new(Edition::PageCount).integer().null())
+                    .add_column(ColumnDef::new(Edition::DurationMinutes).integer().null())
+                    .add_column(ColumnDef::new(Edition::Language).string_len(35).null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every existing book becomes the single edition of its own work.
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        let rows = db
+            .query_all(
+                backend.build(
+                    Query::select()
+                        .columns([Book::Id, Book::UserId, Book::AuthorId, Book::Title])
+                        .from(Book::Table),
+                ),
+            )
+            .await?;
+
+        for row in rows {
+            let id: i32 = row.try_get("", &Book::Id.to_string())?;
+            let user_id: i32 = row.try_get("", &Book::UserId.to_string())?;
+            let author_id: i32 = row.try_get("", &Book::AuthorId.to_string())?;
+            let title: String = row.try_get("", &Book::Title.to_string())?;
+
+            let work_id = db
+                .execute(
+                    backend.build(
+                        Query::insert()
+                            .into_table(Work::Table)
+                            .columns([Work::UserId, Work::AuthorId, Work::Title])
+                            .values_panic([user_id.into(), author_id.into(), title.into()]),
+                    ),
+                )
+                .await?
+                .last_insert_id();
+
+            db.execute(
+                backend.build(
+                    Query::update()
+                        .table(Book::Table)
+                        .value(Edition::WorkId, work_id)
+                        .and_where(Expr::col(Book::Id).eq(id)),
+                ),
+            )
+            .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .modify_column(ColumnDef::new(Edition::WorkId).integer().not_null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-book-work_id")
+                            .from_tbl(Book::Table)
+                            .from_col(Edition::WorkId)
+                            .to_tbl(Work::Table)
+                            .to_col(Work::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-book-isbn")
+                    .table(Book::Table)
+                    .col(Edition::Isbn)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .drop_foreign_key(Alias::new("fk-book-work_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-book-isbn")
+                    .table(Book::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .drop_column(Edition::WorkId)
+                    .drop_column(Edition::Format)
+                    .drop_column(Edition::Isbn)
+                    .drop_column(Edition::PageCount)
+                    .drop_column(Edition::DurationMinutes)
+                    .drop_column(Edition::Language)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Edition {
+    WorkId,
+    Format,
+    Isbn,
+    PageCount,
+    DurationMinutes,
+    Language,
+}
@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Price::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Price::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Price::BookId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-price-book_id")
+                            .from(Price::Table, Price::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Price::Currency).char_len(3).not_null())
+                    .col(
+                        ColumnDef::new(Price::ListPrice)
+                            .decimal_len(12, 2)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Price::SalePrice).decimal_len(12, 2).null())
+                    .col(ColumnDef::new(Price::SaleStartsAt).timestamp().null())
+                    .col(ColumnDef::new(Price::SaleEndsAt).timestamp().null())
+                    .index(
+                        Index::create()
+                            .name("idx-price-book_id-currency")
+                            .col(Price::BookId)
+                            .col(Price::Currency)
+                            .unique(),
+                    )
+                    .col(
+                        ColumnDef::new(Price::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Price::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Price::Table).to_owned())
+            .await
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Iden)]
+pub enum Price {
+    Table,
+    Id,
+    BookId,
+    Currency,
+    ListPrice,
+    SalePrice,
+    SaleStartsAt,
+    SaleEndsAt,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Location::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Location::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Location::Code)
+                            .string_len(32)
+                            .unique_key()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Location::Name).string().not_null())
+                    .col(ColumnDef::new(Location::Address).string().null())
+                    .col(
+                        ColumnDef::new(Location::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Location::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Location::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Location {
+    Table,
+    Id,
+    Code,
+    Name,
+    Address,
+    CreatedAt,
+    UpdatedAt,
+}
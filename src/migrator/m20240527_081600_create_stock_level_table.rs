@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240527_081500_create_location_table::Location;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StockLevel::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(StockLevel::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(StockLevel::BookId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-stock_level-book_id")
+                            .from(StockLevel::Table, StockLevel::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(StockLevel::LocationId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-stock_level-location_id")
+                            .from(StockLevel::Table, StockLevel::LocationId)
+                            .to(Location::Table, Location::Id),
+                    )
+                    .col(
+                        ColumnDef::new(StockLevel::Quantity)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(StockLevel::LowStockThreshold)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx-stock_level-book_id-location_id")
+                            .col(StockLevel::BookId)
+                            .col(StockLevel::LocationId)
+                            .unique(),
+                    )
+                    .col(
+                        ColumnDef::new(StockLevel::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(StockLevel::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StockLevel::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum StockLevel {
+    Table,
+    Id,
+    BookId,
+    LocationId,
+    Quantity,
+    LowStockThreshold,
+    CreatedAt,
+    UpdatedAt,
+}
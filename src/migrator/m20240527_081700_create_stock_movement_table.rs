@@ -0,0 +1,98 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240527_081500_create_location_table::Location;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StockMovement::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(StockMovement::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(StockMovement::UserId).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-stock_movement-user_id")
+                            .from(StockMovement::Table, StockMovement::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .col(ColumnDef::new(StockMovement::BookId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-stock_movement-book_id")
+                            .from(StockMovement::Table, StockMovement::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(StockMovement::LocationId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-stock_movement-location_id")
+                            .from(StockMovement::Table, StockMovement::LocationId)
+                            .to(Location::Table, Location::Id),
+                    )
+                    .col(
+                        ColumnDef::new(StockMovement::CounterpartLocationId)
+                            .integer()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-stock_movement-counterpart_location_id")
+                            .from(StockMovement::Table, StockMovement::CounterpartLocationId)
+                            .to(Location::Table, Location::Id),
+                    )
+                    .col(
+                        ColumnDef::new(StockMovement::Kind)
+                            .string_len(10)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(StockMovement::Quantity).integer().not_null())
+                    .col(ColumnDef::new(StockMovement::Note).string().null())
+                    .col(
+                        ColumnDef::new(StockMovement::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StockMovement::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum StockMovement {
+    Table,
+    Id,
+    UserId,
+    BookId,
+    LocationId,
+    CounterpartLocationId,
+    Kind,
+    Quantity,
+    Note,
+    CreatedAt,
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Cart::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Cart::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Cart::UserId).integer().null().unique_key())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-cart-user_id")
+                            .from(Cart::Table, Cart::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(Cart::GuestToken)
+                            .string_len(64)
+                            .null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Cart::Currency).char_len(3).not_null())
+                    .col(
+                        ColumnDef::new(Cart::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Cart::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Cart::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Cart {
+    Table,
+    Id,
+    UserId,
+    GuestToken,
+    Currency,
+    CreatedAt,
+    UpdatedAt,
+}
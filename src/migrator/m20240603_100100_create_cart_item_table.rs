@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240603_100000_create_cart_table::Cart;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CartItem::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CartItem::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CartItem::CartId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-cart_item-cart_id")
+                            .from(CartItem::Table, CartItem::CartId)
+                            .to(Cart::Table, Cart::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(CartItem::BookId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-cart_item-book_id")
+                            .from(CartItem::Table, CartItem::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(CartItem::Quantity).integer().not_null())
+                    .col(
+                        ColumnDef::new(CartItem::UnitPrice)
+                            .decimal_len(12, 2)
+                            .not_null(),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx-cart_item-cart_id-book_id")
+                            .col(CartItem::CartId)
+                            .col(CartItem::BookId)
+                            .unique(),
+                    )
+                    .col(
+                        ColumnDef::new(CartItem::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(CartItem::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CartItem::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum CartItem {
+    Table,
+    Id,
+    CartId,
+    BookId,
+    Quantity,
+    UnitPrice,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Order::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Order::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Order::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-order-user_id")
+                            .from(Order::Table, Order::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .col(ColumnDef::new(Order::Status).string_len(10).not_null())
+                    .col(ColumnDef::new(Order::Currency).char_len(3).not_null())
+                    .col(
+                        ColumnDef::new(Order::Subtotal)
+                            .decimal_len(12, 2)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Order::Total).decimal_len(12, 2).not_null())
+                    .col(ColumnDef::new(Order::PaymentReference).string().null())
+                    .col(
+                        ColumnDef::new(Order::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Order::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Order::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Order {
+    Table,
+    Id,
+    UserId,
+    Status,
+    Currency,
+    Subtotal,
+    Total,
+    PaymentReference,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240610_090000_create_order_table::Order;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OrderLine::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OrderLine::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OrderLine::OrderId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-order_line-order_id")
+                            .from(OrderLine::Table, OrderLine::OrderId)
+                            .to(Order::Table, Order::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    // Orders outlive the books they were placed for.
+                    .col(ColumnDef::new(OrderLine::BookId).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-order_line-book_id")
+                            .from(OrderLine::Table, OrderLine::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(ColumnDef::new(OrderLine::Title).string().not_null())
+                    .col(ColumnDef::new(OrderLine::Quantity).integer().not_null())
+                    .col(
+                        ColumnDef::new(OrderLine::UnitPrice)
+                            .decimal_len(12, 2)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OrderLine::LineTotal)
+                            .decimal_len(12, 2)
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OrderLine::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum OrderLine {
+    Table,
+    Id,
+    OrderId,
+    BookId,
+    Title,
+    Quantity,
+    UnitPrice,
+    LineTotal,
+}
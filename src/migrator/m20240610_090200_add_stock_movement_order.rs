@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240527_081700_create_stock_movement_table::StockMovement;
+use super::m20240610_090000_create_order_table::Order;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StockMovement::Table)
+                    .add_column(ColumnDef::new(StockMovementOrder::OrderId).integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-stock_movement-order_id")
+                            .from_tbl(StockMovement::Table)
+                            .from_col(StockMovementOrder::OrderId)
+                            .to_tbl(Order::Table)
+                            .to_col(Order::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StockMovement::Table)
+                    .drop_foreign_key(Alias::new("fk-stock_movement-order_id"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StockMovement::Table)
+                    .drop_column(StockMovementOrder::OrderId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum StockMovementOrder {
+    OrderId,
+}
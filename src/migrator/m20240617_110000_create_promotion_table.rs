@@ -0,0 +1,114 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240403_124359_create_author_table::Author;
+use super::m20240420_100000_create_genre_table::Genre;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Promotion::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Promotion::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Promotion::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-promotion-user_id")
+                            .from(Promotion::Table, Promotion::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .col(ColumnDef::new(Promotion::Name).string().not_null())
+                    // Promotions without a code apply automatically.
+                    .col(
+                        ColumnDef::new(Promotion::Code)
+                            .string_len(32)
+                            .null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Promotion::Kind).string_len(12).not_null())
+                    .col(ColumnDef::new(Promotion::Value).decimal_len(12, 2).null())
+                    .col(ColumnDef::new(Promotion::Currency).char_len(3).null())
+                    .col(ColumnDef::new(Promotion::BuyQuantity).integer().null())
+                    .col(ColumnDef::new(Promotion::GetQuantity).integer().null())
+                    .col(ColumnDef::new(Promotion::AuthorId).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-promotion-author_id")
+                            .from(Promotion::Table, Promotion::AuthorId)
+                            .to(Author::Table, Author::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Promotion::GenreId).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-promotion-genre_id")
+                            .from(Promotion::Table, Promotion::GenreId)
+                            .to(Genre::Table, Genre::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Promotion::StartsAt).timestamp().null())
+                    .col(ColumnDef::new(Promotion::EndsAt).timestamp().null())
+                    .col(ColumnDef::new(Promotion::UsageLimit).integer().null())
+                    .col(ColumnDef::new(Promotion::PerUserLimit).integer().null())
+                    .col(
+                        ColumnDef::new(Promotion::Active)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(Promotion::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Promotion::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Promotion::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Promotion {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Code,
+    Kind,
+    Value,
+    Currency,
+    BuyQuantity,
+    GetQuantity,
+    AuthorId,
+    GenreId,
+    StartsAt,
+    EndsAt,
+    UsageLimit,
+    PerUserLimit,
+    Active,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240610_090000_create_order_table::Order;
+use super::m20240617_110000_create_promotion_table::Promotion;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PromotionRedemption::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PromotionRedemption::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PromotionRedemption::PromotionId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-promotion_redemption-promotion_id")
+                            .from(PromotionRedemption::Table, PromotionRedemption::PromotionId)
+                            .to(Promotion::Table, Promotion::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(PromotionRedemption::UserId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-promotion_redemption-user_id")
+                            .from(PromotionRedemption::Table, PromotionRedemption::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(PromotionRedemption::OrderId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-promotion_redemption-order_id")
+                            .from(PromotionRedemption::Table, PromotionRedemption::OrderId)
+                            .to(Order::Table, Order::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(PromotionRedemption::Discount)
+                            .decimal_len(12, 2)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PromotionRedemption::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PromotionRedemption::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum PromotionRedemption {
+    Table,
+    Id,
+    PromotionId,
+    UserId,
+    OrderId,
+    Discount,
+    CreatedAt,
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240603_100000_create_cart_table::Cart;
+use super::m20240610_090000_create_order_table::Order;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Cart::Table)
+                    .add_column(
+                        ColumnDef::new(PromotionTotals::CouponCode)
+                            .string_len(32)
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Order::Table)
+                    .add_column(
+                        ColumnDef::new(PromotionTotals::DiscountTotal)
+                            .decimal_len(12, 2)
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Order::Table)
+                    .drop_column(PromotionTotals::DiscountTotal)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Cart::Table)
+                    .drop_column(PromotionTotals::CouponCode)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum PromotionTotals {
+    CouponCode,
+    DiscountTotal,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TaxRate::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TaxRate::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TaxRate::Region).string_len(6).not_null())
+                    .col(ColumnDef::new(TaxRate::Category).string_len(10).not_null())
+                    .col(ColumnDef::new(TaxRate::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(TaxRate::Percent)
+                            .decimal_len(7, 4)
+                            .not_null(),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx-tax_rate-region-category")
+                            .col(TaxRate::Region)
+                            .col(TaxRate::Category)
+                            .unique(),
+                    )
+                    .col(
+                        ColumnDef::new(TaxRate::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(TaxRate::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TaxRate::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum TaxRate {
+    Table,
+    Id,
+    Region,
+    Category,
+    Name,
+    Percent,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240610_090000_create_order_table::Order;
+use super::m20240610_090100_create_order_line_table::OrderLine;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Order::Table)
+                    .add_column(ColumnDef::new(OrderTax::Region).string_len(6).null())
+                    .add_column(
+                        ColumnDef::new(OrderTax::TaxTotal)
+                            .decimal_len(12, 2)
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Orders placed before taxes were introduced keep null categories
+        // and rates, with no discount or tax on their lines.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderLine::Table)
+                    .add_column(
+                        ColumnDef::new(OrderTax::Discount)
+                            .decimal_len(12, 2)
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(ColumnDef::new(OrderTax::TaxCategory).string_len(10).null())
+                    .add_column(
+                        ColumnDef::new(OrderTax::TaxPercent)
+                            .decimal_len(7, 4)
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(OrderTax::TaxAmount)
+                            .decimal_len(12, 2)
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderLine::Table)
+                    .drop_column(OrderTax::Discount)
+                    .drop_column(OrderTax::TaxCategory)
+                    .drop_column(OrderTax::TaxPercent)
+                    .drop_column(OrderTax::TaxAmount)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Order::Table)
+                    .drop_column(OrderTax::Region)
+                    .drop_column(OrderTax::TaxTotal)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum OrderTax {
+    Region,
+    TaxTotal,
+    Discount,
+    TaxCategory,
+    TaxPercent,
+    TaxAmount,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240610_090000_create_order_table::Order;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Invoice::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Invoice::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Invoice::OrderId)
+                            .integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-invoice-order_id")
+                            .from(Invoice::Table, Invoice::OrderId)
+                            .to(Order::Table, Order::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(Invoice::Number)
+                            .integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Invoice::StorageKey).string().not_null())
+                    .col(
+                        ColumnDef::new(Invoice::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Invoice::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Invoice {
+    Table,
+    Id,
+    OrderId,
+    Number,
+    StorageKey,
+    CreatedAt,
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240403_125836_create_book_table::Book;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Review::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Review::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Review::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-review-user_id")
+                            .from(Review::Table, Review::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Review::BookId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-review-book_id")
+                            .from(Review::Table, Review::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx-review-user_id-book_id")
+                            .col(Review::UserId)
+                            .col(Review::BookId)
+                            .unique(),
+                    )
+                    .col(ColumnDef::new(Review::Rating).small_integer().not_null())
+                    .col(ColumnDef::new(Review::Body).text().null())
+                    .col(
+                        ColumnDef::new(Review::Status)
+                            .string_len(10)
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(ColumnDef::new(Review::ModerationNote).string().null())
+                    .col(
+                        ColumnDef::new(Review::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Review::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Review::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Review {
+    Table,
+    Id,
+    UserId,
+    BookId,
+    Rating,
+    Body,
+    Status,
+    ModerationNote,
+    CreatedAt,
+    UpdatedAt,
+}
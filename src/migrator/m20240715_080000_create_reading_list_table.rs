@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReadingList::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ReadingList::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ReadingList::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-reading_list-user_id")
+                            .from(ReadingList::Table, ReadingList::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(ReadingList::Name).string().not_null())
+                    .col(ColumnDef::new(ReadingList::Kind).string_len(20).not_null())
+                    .col(
+                        ColumnDef::new(ReadingList::Privacy)
+                            .string_len(10)
+                            .not_null()
+                            .default("private"),
+                    )
+                    .col(
+                        ColumnDef::new(ReadingList::ShareToken)
+                            .string_len(32)
+                            .null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ReadingList::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(ReadingList::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ReadingList::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum ReadingList {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Kind,
+    Privacy,
+    ShareToken,
+    CreatedAt,
+    UpdatedAt,
+}
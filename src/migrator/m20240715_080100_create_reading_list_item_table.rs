@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240715_080000_create_reading_list_table::ReadingList;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReadingListItem::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ReadingListItem::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ReadingListItem::ReadingListId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-reading_list_item-reading_list_id")
+                            .from(ReadingListItem::Table, ReadingListItem::ReadingListId)
+                            .to(ReadingList::Table, ReadingList::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(ReadingListItem::BookId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-reading_list_item-book_id")
+                            .from(ReadingListItem::Table, ReadingListItem::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx-reading_list_item-reading_list_id-book_id")
+                            .col(ReadingListItem::ReadingListId)
+                            .col(ReadingListItem::BookId)
+                            .unique(),
+                    )
+                    .col(
+                        ColumnDef::new(ReadingListItem::Position)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ReadingListItem::Note).text().null())
+                    .col(
+                        ColumnDef::new(ReadingListItem::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ReadingListItem::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum ReadingListItem {
+    Table,
+    Id,
+    ReadingListId,
+    BookId,
+    Position,
+    Note,
+    CreatedAt,
+}
@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240527_081500_create_location_table::Location;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BookCopy::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BookCopy::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BookCopy::BookId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-book_copy-book_id")
+                            .from(BookCopy::Table, BookCopy::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(BookCopy::LocationId).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-book_copy-location_id")
+                            .from(BookCopy::Table, BookCopy::LocationId)
+                            .to(Location::Table, Location::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(
+                        ColumnDef::new(BookCopy::Barcode)
+                            .string_len(32)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(BookCopy::Status)
+                            .string_len(10)
+                            .not_null()
+                            .default("available"),
+                    )
+                    .col(
+                        ColumnDef::new(BookCopy::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(BookCopy::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BookCopy::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BookCopy {
+    Table,
+    Id,
+    BookId,
+    LocationId,
+    Barcode,
+    Status,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240722_093000_create_book_copy_table::BookCopy;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Loan::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Loan::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Loan::BookCopyId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-loan-book_copy_id")
+                            .from(Loan::Table, Loan::BookCopyId)
+                            .to(BookCopy::Table, BookCopy::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Loan::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-loan-user_id")
+                            .from(Loan::Table, Loan::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Loan::DueAt).timestamp().not_null())
+                    .col(ColumnDef::new(Loan::ReturnedAt).timestamp().null())
+                    .col(
+                        ColumnDef::new(Loan::Renewals)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Loan::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Loan::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Loan {
+    Table,
+    Id,
+    BookCopyId,
+    UserId,
+    DueAt,
+    ReturnedAt,
+    Renewals,
+    CreatedAt,
+}
@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240722_093000_create_book_copy_table::BookCopy;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Hold::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Hold::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Hold::BookId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-hold-book_id")
+                            .from(Hold::Table, Hold::BookId)
+                            .to(Book::Table, Book::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Hold::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-hold-user_id")
+                            .from(Hold::Table, Hold::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Hold::BookCopyId).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-hold-book_copy_id")
+                            .from(Hold::Table, Hold::BookCopyId)
+                            .to(BookCopy::Table, BookCopy::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(
+                        ColumnDef::new(Hold::Status)
+                            .string_len(10)
+                            .not_null()
+                            .default("waiting"),
+                    )
+                    .col(ColumnDef::new(Hold::ReadyAt).timestamp().null())
+                    .col(
+                        ColumnDef::new(Hold::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Hold::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Hold::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Hold {
+    Table,
+    Id,
+    BookId,
+    UserId,
+    BookCopyId,
+    Status,
+    ReadyAt,
+    CreatedAt,
+    UpdatedAt,
+}
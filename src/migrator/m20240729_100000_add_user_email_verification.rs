@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(
+                        ColumnDef::new(EmailVerification::EmailVerifiedAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(EmailVerification::VerificationSentAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(EmailVerification::EmailVerifiedAt)
+                    .drop_column(EmailVerification::VerificationSentAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum EmailVerification {
+    EmailVerifiedAt,
+    VerificationSentAt,
+}
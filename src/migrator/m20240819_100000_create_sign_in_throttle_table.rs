@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SignInThrottle::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SignInThrottle::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SignInThrottle::Scope)
+                            .string_len(10)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SignInThrottle::Subject).string().not_null())
+                    .index(
+                        Index::create()
+                            .name("idx-sign_in_throttle-scope-subject")
+                            .col(SignInThrottle::Scope)
+                            .col(SignInThrottle::Subject)
+                            .unique(),
+                    )
+                    .col(
+                        ColumnDef::new(SignInThrottle::Failures)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(SignInThrottle::LastFailureAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(SignInThrottle::LockedUntil)
+                            .timestamp()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SignInThrottle::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum SignInThrottle {
+    Table,
+    Id,
+    Scope,
+    Subject,
+    Failures,
+    LastFailureAt,
+    LockedUntil,
+}
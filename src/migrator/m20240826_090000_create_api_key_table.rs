@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiKey::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ApiKey::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ApiKey::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-api_key-user_id")
+                            .from(ApiKey::Table, ApiKey::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(ApiKey::Name).string_len(100).not_null())
+                    .col(ColumnDef::new(ApiKey::Scope).string_len(20).not_null())
+                    .col(ColumnDef::new(ApiKey::Prefix).string_len(16).not_null())
+                    .col(
+                        ColumnDef::new(ApiKey::KeyHash)
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(ApiKey::LastUsedAt).timestamp().null())
+                    .col(ColumnDef::new(ApiKey::RevokedAt).timestamp().null())
+                    .col(
+                        ColumnDef::new(ApiKey::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiKey::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum ApiKey {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Scope,
+    Prefix,
+    KeyHash,
+    LastUsedAt,
+    RevokedAt,
+    CreatedAt,
+}
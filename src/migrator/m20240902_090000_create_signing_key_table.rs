@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SigningKey::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SigningKey::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SigningKey::Kid)
+                            .string_len(32)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SigningKey::Algorithm)
+                            .string_len(10)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SigningKey::PrivateKey).text().not_null())
+                    .col(ColumnDef::new(SigningKey::PublicJwk).text().not_null())
+                    .col(
+                        ColumnDef::new(SigningKey::ActivatesAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SigningKey::RetiresAt).timestamp().not_null())
+                    .col(ColumnDef::new(SigningKey::ExpiresAt).timestamp().not_null())
+                    .col(
+                        ColumnDef::new(SigningKey::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SigningKey::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum SigningKey {
+    Table,
+    Id,
+    Kid,
+    Algorithm,
+    PrivateKey,
+    PublicJwk,
+    ActivatesAt,
+    RetiresAt,
+    ExpiresAt,
+    CreatedAt,
+}
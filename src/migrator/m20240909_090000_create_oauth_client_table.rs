@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OauthClient::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OauthClient::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthClient::ClientId)
+                            .string_len(32)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(OauthClient::Name).string_len(100).not_null())
+                    .col(
+                        ColumnDef::new(OauthClient::SecretHash)
+                            .string_len(64)
+                            .null(),
+                    )
+                    .col(ColumnDef::new(OauthClient::RedirectUris).text().not_null())
+                    .col(
+                        ColumnDef::new(OauthClient::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OauthClient::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum OauthClient {
+    Table,
+    Id,
+    ClientId,
+    Name,
+    SecretHash,
+    RedirectUris,
+    CreatedAt,
+}
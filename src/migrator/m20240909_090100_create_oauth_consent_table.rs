@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240909_090000_create_oauth_client_table::OauthClient;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OauthConsent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OauthConsent::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OauthConsent::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-oauth_consent-user_id")
+                            .from(OauthConsent::Table, OauthConsent::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(OauthConsent::OauthClientId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-oauth_consent-oauth_client_id")
+                            .from(OauthConsent::Table, OauthConsent::OauthClientId)
+                            .to(OauthClient::Table, OauthClient::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx-oauth_consent-user_id-oauth_client_id")
+                            .col(OauthConsent::UserId)
+                            .col(OauthConsent::OauthClientId)
+                            .unique(),
+                    )
+                    .col(ColumnDef::new(OauthConsent::Scope).string().not_null())
+                    .col(
+                        ColumnDef::new(OauthConsent::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(OauthConsent::UpdatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OauthConsent::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum OauthConsent {
+    Table,
+    Id,
+    UserId,
+    OauthClientId,
+    Scope,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,125 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+use super::m20240909_090000_create_oauth_client_table::OauthClient;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OauthAuthorizationCode::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::CodeHash)
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::OauthClientId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-oauth_authorization_code-oauth_client_id")
+                            .from(
+                                OauthAuthorizationCode::Table,
+                                OauthAuthorizationCode::OauthClientId,
+                            )
+                            .to(OauthClient::Table, OauthClient::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::UserId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-oauth_authorization_code-user_id")
+                            .from(
+                                OauthAuthorizationCode::Table,
+                                OauthAuthorizationCode::UserId,
+                            )
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::RedirectUri)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::Scope)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::Nonce)
+                            .string()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::CodeChallenge)
+                            .string_len(128)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::ExpiresAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::UsedAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorizationCode::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(OauthAuthorizationCode::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum OauthAuthorizationCode {
+    Table,
+    Id,
+    CodeHash,
+    OauthClientId,
+    UserId,
+    RedirectUri,
+    Scope,
+    Nonce,
+    CodeChallenge,
+    ExpiresAt,
+    UsedAt,
+    CreatedAt,
+}
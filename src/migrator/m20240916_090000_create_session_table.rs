@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Session::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Session::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Session::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-session-user_id")
+                            .from(Session::Table, Session::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Session::DeviceName).string_len(100))
+                    .col(ColumnDef::new(Session::IpAddress).string_len(45))
+                    .col(ColumnDef::new(Session::UserAgent).string_len(255))
+                    .col(ColumnDef::new(Session::ExpiresAt).timestamp().not_null())
+                    .col(ColumnDef::new(Session::LastSeenAt).timestamp())
+                    .col(ColumnDef::new(Session::RevokedAt).timestamp())
+                    .col(
+                        ColumnDef::new(Session::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Session::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Session {
+    Table,
+    Id,
+    UserId,
+    DeviceName,
+    IpAddress,
+    UserAgent,
+    ExpiresAt,
+    LastSeenAt,
+    RevokedAt,
+    CreatedAt,
+}
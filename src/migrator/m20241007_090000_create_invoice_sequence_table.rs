@@ -0,0 +1,72 @@
+use sea_orm::ConnectionTrait;
+use sea_orm_migration::prelude::*;
+
+use super::m20240701_090000_create_invoice_table::Invoice;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(InvoiceSequence::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InvoiceSequence::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(InvoiceSequence::LastNumber)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The single counter row carries on from the invoices already issued.
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        db.execute(
+            backend.build(
+                Query::insert()
+                    .into_table(InvoiceSequence::Table)
+                    .columns([InvoiceSequence::Id, InvoiceSequence::LastNumber])
+                    .select_from(
+                        Query::select()
+                            .expr(Expr::val(1))
+                            .expr(Func::coalesce([
+                                Expr::col(Invoice::Number).max(),
+                                Expr::val(0).into(),
+                            ]))
+                            .from(Invoice::Table)
+                            .to_owned(),
+                    )
+                    .map_err(|e| DbErr::Custom(e.to_string()))?,
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(InvoiceSequence::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum InvoiceSequence {
+    Table,
+    Id,
+    LastNumber,
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_125836_create_book_table::Book;
+use super::m20240527_081700_create_stock_movement_table::StockMovement;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Points `fk-stock_movement-book_id` at `book` again with `on_delete`.
+async fn replace_book_key(
+    manager: &SchemaManager<'_>,
+    on_delete: ForeignKeyAction,
+) -> Result<(), DbErr> {
+    manager
+        .alter_table(
+            Table::alter()
+                .table(StockMovement::Table)
+                .drop_foreign_key(Alias::new("fk-stock_movement-book_id"))
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .alter_table(
+            Table::alter()
+                .table(StockMovement::Table)
+                .add_foreign_key(
+                    TableForeignKey::new()
+                        .name("fk-stock_movement-book_id")
+                        .from_tbl(StockMovement::Table)
+                        .from_col(StockMovement::BookId)
+                        .to_tbl(Book::Table)
+                        .to_col(Book::Id)
+                        .on_delete(on_delete),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // The ledger is append-only, so a book with stock movements can no
+    // longer be deleted.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        replace_book_key(manager, ForeignKeyAction::Restrict).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        replace_book_key(manager, ForeignKeyAction::Cascade).await
+    }
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240403_124359_create_author_table::Author;
+use super::m20240420_100000_create_genre_table::Genre;
+use super::m20240617_110000_create_promotion_table::Promotion;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Recreates the keys from a promotion to the author and genre it is
+/// limited to with `on_delete`.
+async fn replace_scope_keys(
+    manager: &SchemaManager<'_>,
+    on_delete: ForeignKeyAction,
+) -> Result<(), DbErr> {
+    manager
+        .alter_table(
+            Table::alter()
+                .table(Promotion::Table)
+                .drop_foreign_key(Alias::new("fk-promotion-author_id"))
+                .drop_foreign_key(Alias::new("fk-promotion-genre_id"))
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .alter_table(
+            Table::alter()
+                .table(Promotion::Table)
+                .add_foreign_key(
+                    TableForeignKey::new()
+                        .name("fk-promotion-author_id")
+                        .from_tbl(Promotion::Table)
+                        .from_col(Promotion::AuthorId)
+                        .to_tbl(Author::Table)
+                        .to_col(Author::Id)
+                        .on_delete(on_delete),
+                )
+                .add_foreign_key(
+                    TableForeignKey::new()
+                        .name("fk-promotion-genre_id")
+                        .from_tbl(Promotion::Table)
+                        .from_col(Promotion::GenreId)
+                        .to_tbl(Genre::Table)
+                        .to_col(Genre::Id)
+                        .on_delete(on_delete),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // Deleting an author or genre must not take promotions, and with them
+    // the redemptions their usage limits count, along with it.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        replace_scope_keys(manager, ForeignKeyAction::Restrict).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        replace_scope_keys(manager, ForeignKeyAction::Cascade).await
+    }
+}
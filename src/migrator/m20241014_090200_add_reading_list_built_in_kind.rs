@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use sea_orm::ConnectionTrait;
+use sea_orm_migration::prelude::*;
+
+use super::m20240715_080000_create_reading_list_table::ReadingList;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ReadingList::Table)
+                    .add_column(ColumnDef::new(BuiltIn::BuiltInKind).string_len(20).null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Only the oldest built-in list of each kind keeps its place; any
+        // duplicates left behind by earlier races stay as plain rows.
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        let rows = db
+            .query_all(
+                backend.build(
+                    Query::select()
+                        .columns([ReadingList::Id, ReadingList::UserId, ReadingList::Kind])
+                        .from(ReadingList::Table)
+                        .and_where(Expr::col(ReadingList::Kind).ne("custom"))
+                        .order_by(ReadingList::Id, Order::Asc),
+                ),
+            )
+            .await?;
+
+        let mut seen = HashSet::new();
+
+        for row in rows {
+            let id: i32 = row.try_get("", &ReadingList::Id.to_string())?;
+            let user_id: i32 = row.try_get("", &ReadingList::UserId.to_string())?;
+            let kind: String = row.try_get("", &ReadingList::Kind.to_string())?;
+
+            if !seen.insert((user_id, kind.clone())) {
+                continue;
+            }
+
+            db.execute(
+                backend.build(
+                    Query::update()
+                        .table(ReadingList::Table)
+                        .value(BuiltIn::BuiltInKind, kind)
+                        .and_where(Expr::col(ReadingList::Id).eq(id)),
+                ),
+            )
+            .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-reading_list-user_id-built_in_kind")
+                    .table(ReadingList::Table)
+                    .col(ReadingList::UserId)
+                    .col(BuiltIn::BuiltInKind)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-reading_list-user_id-built_in_kind")
+                    .table(ReadingList::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ReadingList::Table)
+                    .drop_column(BuiltIn::BuiltInKind)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BuiltIn {
+    BuiltInKind,
+}
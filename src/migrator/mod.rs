@@ -3,6 +3,57 @@ pub use sea_orm_migration::prelude::*;
 mod m20220101_000001_create_user_table;
 mod m20240403_124359_create_author_table;
 mod m20240403_125836_create_book_table;
+mod m20240415_090000_book_publication_date;
+mod m20240420_100000_create_genre_table;
+mod m20240420_100100_create_tag_table;
+mod m20240420_100200_create_book_genre_table;
+mod m20240420_100300_create_book_tag_table;
+mod m20240425_090000_create_publisher_table;
+mod m20240425_090100_create_imprint_table;
+mod m20240425_090200_add_book_publisher;
+mod m20240502_080000_create_series_table;
+mod m20240502_080100_add_book_series;
+mod m20240510_070000_create_work_table;
+mod m20240510_070100_add_book_edition;
+mod m20240518_093000_create_price_table;
+mod m20240527_081500_create_location_table;
+mod m20240527_081600_create_stock_level_table;
+mod m20240527_081700_create_stock_movement_table;
+mod m20240603_100000_create_cart_table;
+mod m20240603_100100_create_cart_item_table;
+mod m20240610_090000_create_order_table;
+mod m20240610_090100_create_order_line_table;
+mod m20240610_090200_add_stock_movement_order;
+mod m20240617_110000_create_promotion_table;
+mod m20240617_110100_create_promotion_redemption_table;
+mod m20240617_110200_add_promotion_totals;
+mod m20240624_140000_create_tax_rate_table;
+mod m20240624_140100_add_order_tax;
+mod m20240701_090000_create_invoice_table;
+mod m20240708_100000_add_user_role;
+mod m20240708_100100_create_review_table;
+mod m20240715_080000_create_reading_list_table;
+mod m20240715_080100_create_reading_list_item_table;
+mod m20240722_093000_create_book_copy_table;
+mod m20240722_093100_create_loan_table;
+mod m20240722_093200_create_hold_table;
+mod m20240729_100000_add_user_email_verification;
+mod m20240805_083000_add_user_session_version;
+mod m20240812_090000_add_user_totp;
+mod m20240812_090100_create_recovery_code_table;
+mod m20240819_100000_create_sign_in_throttle_table;
+mod m20240826_090000_create_api_key_table;
+mod m20240902_090000_create_signing_key_table;
+mod m20240909_090000_create_oauth_client_table;
+mod m20240909_090100_create_oauth_consent_table;
+mod m20240909_090200_create_oauth_authorization_code_table;
+mod m20240916_090000_create_session_table;
+mod m20240923_090000_add_user_deleted_at;
+mod m20240930_090000_add_user_totp_last_step;
+mod m20241007_090000_create_invoice_sequence_table;
+mod m20241014_090000_restrict_stock_movement_book;
+mod m20241014_090100_restrict_promotion_scope;
+mod m20241014_090200_add_reading_list_built_in_kind;
 
 pub struct Migrator;
 
@@ -13,6 +64,57 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000001_create_user_table::Migration),
             Box::new(m20240403_124359_create_author_table::Migration),
             Box::new(m20240403_125836_create_book_table::Migration),
+            Box::new(m20240415_090000_book_publication_date::Migration),
+            Box::new(m20240420_100000_create_genre_table::Migration),
+            Box::new(m20240420_100100_create_tag_table::Migration),
+            Box::new(m20240420_100200_create_book_genre_table::Migration),
+            Box::new(m20240420_100300_create_book_tag_table::Migration),
+            Box::new(m20240425_090000_create_publisher_table::Migration),
+            Box::new(m20240425_090100_create_imprint_table::Migration),
+            Box::new(m20240425_090200_add_book_publisher::Migration),
+            Box::new(m20240502_080000_create_series_table::Migration),
+            Box::new(m20240502_080100_add_book_series::Migration),
+            Box::new(m20240510_070000_create_work_table::Migration),
+            Box::new(m20240510_070100_add_book_edition::Migration),
+            Box::new(m20240518_093000_create_price_table::Migration),
+            Box::new(m20240527_081500_create_location_table::Migration),
+            Box::new(m20240527_081600_create_stock_level_table::Migration),
+            Box::new(m20240527_081700_create_stock_movement_table::Migration),
+            Box::new(m20240603_100000_create_cart_table::Migration),
+            Box::new(m20240603_100100_create_cart_item_table::Migration),
+            Box::new(m20240610_090000_create_order_table::Migration),
+            Box::new(m20240610_090100_create_order_line_table::Migration),
+            Box::new(m20240610_090200_add_stock_movement_order::Migration),
+            Box::new(m20240617_110000_create_promotion_table::Migration),
+            Box::new(m20240617_110100_create_promotion_redemption_table::Migration),
+            Box::new(m20240617_110200_add_promotion_totals::Migration),
+            Box::new(m20240624_140000_create_tax_rate_table::Migration),
+            Box::new(m20240624_140100_add_order_tax::Migration),
+            Box::new(m20240701_090000_create_invoice_table::Migration),
+            Box::new(m20240708_100000_add_user_role::Migration),
+            Box::new(m20240708_100100_create_review_table::Migration),
+            Box::new(m20240715_080000_create_reading_list_table::Migration),
+            Box::new(m20240715_080100_create_reading_list_item_table::Migration),
+            Box::new(m20240722_093000_create_book_copy_table::Migration),
+            Box::new(m20240722_093100_create_loan_table::Migration),
+            Box::new(m20240722_093200_create_hold_table::Migration),
+            Box::new(m20240729_100000_add_user_email_verification::Migration),
+            Box::new(m20240805_083000_add_user_session_version::Migration),
+            Box::new(m20240812_090000_add_user_totp::Migration),
+            Box::new(m20240812_090100_create_recovery_code_table::Migration),
+            Box::new(m20240819_100000_create_sign_in_throttle_table::Migration),
+            Box::new(m20240826_090000_create_api_key_table::Migration),
+            Box::new(m20240902_090000_create_signing_key_table::Migration),
+            Box::new(m20240909_090000_create_oauth_client_table::Migration),
+            Box::new(m20240909_090100_create_oauth_consent_table::Migration),
+            Box::new(m20240909_090200_create_oauth_authorization_code_table::Migration),
+            Box::new(m20240916_090000_create_session_table::Migration),
+            Box::new(m20240923_090000_add_user_deleted_at::Migration),
+            Box::new(m20240930_090000_add_user_totp_last_step::Migration),
+            Box::new(m20241007_090000_create_invoice_sequence_table::Migration),
+            Box::new(m20241014_090000_restrict_stock_movement_book::Migration),
+            Box::new(m20241014_090100_restrict_promotion_scope::Migration),
+            Box::new(m20241014_090200_add_reading_list_built_in_kind::Migration),
         ]
     }
 }
@@ -0,0 +1,61 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sea_orm::prelude::Decimal;
+
+/// A request to take payment for an order.
+pub struct Charge<'a> {
+    pub order_id: i32,
+    pub amount: Decimal,
+    pub currency: &'a str,
+    /// Opaque token the client obtained from the provider, e.g. a tokenized card.
+    pub payment_token: &'a str,
+}
+
+#[derive(Debug)]
+pub struct PaymentError(pub String);
+
+#[rocket::async_trait]
+pub trait PaymentProvider: Send + Sync {
+    /// Takes payment, returning the provider's reference for it.
+    async fn charge(&self, charge: Charge<'_>) -> Result<String, PaymentError>;
+
+    /// Returns `amount` of the payment identified by `reference`.
+    async fn refund(&self, reference: &str, amount: Decimal) -> Result<(), PaymentError>;
+}
+
+/// Settles everything locally so checkout works without a real provider.
+///
+/// Payments made with the `tok_decline` token are declined.
+pub struct FakePaymentProvider;
+
+#[rocket::async_trait]
+impl PaymentProvider for FakePaymentProvider {
+    async fn charge(&self, charge: Charge<'_>) -> Result<String, PaymentError> {
+        if charge.payment_token == "tok_decline" {
+            return Err(PaymentError("Payment declined".to_string()));
+        }
+
+        if charge.currency.len() != 3 {
+            return Err(PaymentError("Unsupported currency".to_string()));
+        }
+
+        if charge.amount.is_sign_negative() {
+            return Err(PaymentError("Cannot charge a negative amount".to_string()));
+        }
+
+        let id = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(24)
+            .map(char::from)
+            .collect::<String>();
+
+        Ok(format!("fake_{}_{}", charge.order_id, id))
+    }
+
+    async fn refund(&self, reference: &str, _amount: Decimal) -> Result<(), PaymentError> {
+        if !reference.starts_with("fake_") {
+            return Err(PaymentError("Unknown payment".to_string()));
+        }
+
+        Ok(())
+    }
+}
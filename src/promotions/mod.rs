@@ -0,0 +1,432 @@
+use std::time::SystemTime;
+
+use rocket::serde::Serialize;
+use sea_orm::{
+    prelude::{DateTimeUtc, Decimal},
+    ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+
+use crate::{
+    controllers::genre::subtree_ids,
+    entities::{
+        book, book_genre, cart_item, prelude::*, promotion, promotion_redemption,
+        sea_orm_active_enums::PromotionKind,
+    },
+};
+
+/// A cart line as seen by the promotion rules.
+pub struct Line {
+    pub author_id: i32,
+    pub genre_ids: Vec<i32>,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+}
+
+impl Line {
+    fn total(&self) -> Decimal {
+        self.unit_price * Decimal::from(self.quantity)
+    }
+}
+
+/// A promotion that fired, with the discount it gave and why.
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Applied {
+    pub promotion_id: i32,
+    pub name: String,
+    pub code: Option<String>,
+    pub discount: Decimal,
+    pub explanation: String,
+}
+
+pub struct Evaluation {
+    pub subtotal: Decimal,
+    pub discount: Decimal,
+    pub total: Decimal,
+    pub applied: Vec<Applied>,
+    /// Why the coupon on the cart, if any, gave no discount.
+    pub notes: Vec<String>,
+}
+
+/// Builds promotion lines from cart items and their books.
+pub async fn lines<C: ConnectionTrait>(
+    db: &C,
+    items: &[(cart_item::Model, Option<book::Model>)],
+) -> Result<Vec<Line>, DbErr> {
+    let book_ids = items.iter().map(|(i, _)| i.book_id).collect::<Vec<_>>();
+
+    let genres = BookGenre::find()
+        .filter(book_genre::Column::BookId.is_in(book_ids))
+        .all(db)
+        .await?;
+
+    Ok(items
+        .iter()
+        .filter_map(|(i, b)| b.as_ref().map(|b| (i, b)))
+        .map(|(i, b)| Line {
+            author_id: b.author_id,
+            genre_ids: genres
+                .iter()
+                .filter(|g| g.book_id == b.id)
+                .map(|g| g.genre_id)
+                .collect(),
+            quantity: i.quantity,
+            unit_price: i.unit_price,
+        })
+        .collect())
+}
+
+/// Why `promotion` cannot be used right now by `user_id`, if it cannot.
+async fn unavailable<C: ConnectionTrait>(
+    db: &C,
+    promotion: &promotion::Model,
+    user_id: Option<i32>,
+    now: DateTimeUtc,
+) -> Result<Option<&'static str>, DbErr> {
+    if !promotion.active {
+        return Ok(Some("is no longer active"));
+    }
+
+    if promotion.starts_at.is_some_and(|s| s > now) {
+        return Ok(Some("has not started yet"));
+    }
+
+    if promotion.ends_at.is_some_and(|e| e <= now) {
+        return Ok(Some("has expired"));
+    }
+
+    if let Some(limit) = promotion.usage_limit {
+        let used = PromotionRedemption::find()
+            .filter(promotion_redemption::Column::PromotionId.eq(promotion.id))
+            .count(db)
+            .await?;
+
+        if used >= limit as u64 {
+            return Ok(Some("has been fully redeemed"));
+        }
+    }
+
+    if let (Some(limit), Some(user_id)) = (promotion.per_user_limit, user_id) {
+        let used = PromotionRedemption::find()
+            .filter(promotion_redemption::Column::PromotionId.eq(promotion.id))
+            .filter(promotion_redemption::Column::UserId.eq(user_id))
+            .count(db)
+            .await?;
+
+        if used >= limit as u64 {
+            return Ok(Some("has already been used the maximum number of times"));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Applies one promotion to the lines it is scoped to. `genre_ids` is the
+/// genre subtree the promotion is limited to, if any.
+fn apply(
+    promotion: &promotion::Model,
+    lines: &[Line],
+    currency: &str,
+    genre_ids: Option<&[i32]>,
+) -> Result<(Decimal, String), String> {
+    let eligible = lines
+        .iter()
+        .filter(|l| promotion.author_id.is_none_or(|a| a == l.author_id))
+        .filter(|l| genre_ids.is_none_or(|ids| l.genre_ids.iter().any(|g| ids.contains(g))))
+        .collect::<Vec<_>>();
+
+    if eligible.is_empty() {
+        return Err("does not apply to any book in the cart".to_string());
+    }
+
+    let units = eligible.iter().map(|l| i64::from(l.quantity)).sum::<i64>();
+    let subtotal = eligible.iter().map(|l| l.total()).sum::<Decimal>();
+    let value = promotion.value.unwrap_or_default();
+
+    match promotion.kind {
+        PromotionKind::Percent => Ok((
+            (subtotal * value / Decimal::from(100)).round_dp(2),
+            format!("{}% off {} eligible item(s)", value.normalize(), units),
+        )),
+        PromotionKind::Fixed => {
+            if promotion.currency.as_deref() != Some(currency) {
+                return Err(format!("is not valid for {} carts", currency));
+            }
+
+            Ok((
+                value.min(subtotal),
+                format!("{} {} off {} eligible item(s)", value, currency, units),
+            ))
+        }
+        PromotionKind::BuyXGetY => {
+            let buy = i64::from(promotion.buy_quantity.unwrap_or(1));
+            let get = i64::from(promotion.get_quantity.unwrap_or(1));
+
+            // The cheapest items of each complete group are the free ones.
+            // Lines are walked from the cheapest up rather than expanded
+            // into single units, so large quantities cost nothing extra.
+            let mut by_price = eligible
+                .iter()
+                .map(|l| (l.unit_price, i64::from(l.quantity)))
+                .collect::<Vec<_>>();
+            by_price.sort_unstable_by_key(|(price, _)| *price);
+
+            let free = units / (buy + get) * get;
+
+            if free == 0 {
+                return Err(format!("needs {} eligible items", buy + get));
+            }
+
+            let mut remaining = free;
+            let mut discount = Decimal::ZERO;
+
+            for (price, quantity) in by_price {
+                let taken = quantity.min(remaining);
+                discount += price * Decimal::from(taken);
+                remaining -= taken;
+
+                if remaining == 0 {
+                    break;
+                }
+            }
+
+            Ok((
+                discount,
+                format!("Buy {} get {} free: {} free item(s)", buy, get, free),
+            ))
+        }
+    }
+}
+
+/// Works out the discounts on a cart. Automatic promotions always take part;
+/// coded ones only when `coupon_code` names them. Discounts never exceed the
+/// subtotal.
+///
+/// At checkout, `lock` holds the promotions until the transaction ends, so
+/// concurrent checkouts cannot both take the last redemption.
+pub async fn evaluate<C: ConnectionTrait>(
+    db: &C,
+    lines: &[Line],
+    currency: &str,
+    user_id: Option<i32>,
+    coupon_code: Option<&str>,
+    lock: bool,
+) -> Result<Evaluation, DbErr> {
+    let now = DateTimeUtc::from(SystemTime::now());
+    let subtotal = lines.iter().map(|l| l.total()).sum::<Decimal>();
+
+    let mut codes = Condition::any().add(promotion::Column::Code.is_null());
+
+    if let Some(code) = coupon_code {
+        codes = codes.add(promotion::Column::Code.eq(code));
+    }
+
+    let mut query = Promotion::find()
+        .filter(promotion::Column::Active.eq(true))
+        .filter(codes)
+        .order_by_asc(promotion::Column::Id);
+
+    if lock {
+        query = query.lock_exclusive();
+    }
+
+    let promotions = query.all(db).await?;
+
+    let mut applied = vec![];
+    let mut notes = vec![];
+    let mut remaining = subtotal;
+
+    if let Some(code) = coupon_code {
+        if !promotions.iter().any(|p| p.code.as_deref() == Some(code)) {
+            notes.push(format!("Coupon {} is no longer active", code));
+        }
+    }
+
+    for promotion in promotions {
+        // Only coupons get an explanation; automatic promotions that do not
+        // fire are of no interest to the customer.
+        let mut explain = |reason: &str| {
+            if let Some(code) = &promotion.code {
+                notes.push(format!("Coupon {} {}", code, reason));
+            }
+        };
+
+        if let Some(reason) = unavailable(db, &promotion, user_id, now).await? {
+            explain(reason);
+            continue;
+        }
+
+        let genre_ids = match promotion.genre_id {
+            Some(g) => Some(subtree_ids(db, g).await?),
+            None => None,
+        };
+
+        let (discount, explanation) = match apply(&promotion, lines, currency, genre_ids.as_deref())
+        {
+            Ok((discount, explanation)) => (discount.min(remaining), explanation),
+            Err(reason) => {
+                explain(&reason);
+                continue;
+            }
+        };
+
+        if discount.is_zero() {
+            explain("gives no further discount");
+            continue;
+        }
+
+        remaining -= discount;
+
+        applied.push(Applied {
+            promotion_id: promotion.id,
+            name: promotion.name,
+            code: promotion.code,
+            discount,
+            explanation,
+        });
+    }
+
+    Ok(Evaluation {
+        subtotal,
+        discount: subtotal - remaining,
+        total: remaining,
+        applied,
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn promotion(kind: PromotionKind) -> promotion::Model {
+        promotion::Model {
+            id: 1,
+            user_id: 1,
+            name: "Test".to_string(),
+            code: None,
+            kind,
+            value: None,
+            currency: None,
+            buy_quantity: None,
+            get_quantity: None,
+            author_id: None,
+            genre_id: None,
+            starts_at: None,
+            ends_at: None,
+            usage_limit: None,
+            per_user_limit: None,
+            active: true,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn line(author_id: i32, genre_ids: &[i32], quantity: i32, cents: i64) -> Line {
+        Line {
+            author_id,
+            genre_ids: genre_ids.to_vec(),
+            quantity,
+            unit_price: Decimal::new(cents, 2),
+        }
+    }
+
+    fn discount(
+        promotion: &promotion::Model,
+        lines: &[Line],
+        genre_ids: Option<&[i32]>,
+    ) -> Result<Decimal, String> {
+        apply(promotion, lines, "EUR", genre_ids).map(|(discount, _)| discount)
+    }
+
+    #[test]
+    fn percent_is_taken_off_the_eligible_subtotal() {
+        let mut p = promotion(PromotionKind::Percent);
+        p.value = Some(Decimal::from(15));
+
+        let lines = [line(1, &[], 2, 1000), line(1, &[], 1, 999)];
+
+        assert_eq!(discount(&p, &lines, None), Ok(Decimal::new(450, 2)));
+    }
+
+    #[test]
+    fn fixed_never_exceeds_the_subtotal() {
+        let mut p = promotion(PromotionKind::Fixed);
+        p.value = Some(Decimal::from(20));
+        p.currency = Some("EUR".to_string());
+
+        assert_eq!(
+            discount(&p, &[line(1, &[], 1, 1250)], None),
+            Ok(Decimal::new(1250, 2))
+        );
+    }
+
+    #[test]
+    fn fixed_needs_the_cart_currency() {
+        let mut p = promotion(PromotionKind::Fixed);
+        p.value = Some(Decimal::from(5));
+        p.currency = Some("USD".to_string());
+
+        assert!(discount(&p, &[line(1, &[], 1, 1000)], None).is_err());
+    }
+
+    #[test]
+    fn buy_x_get_y_gives_the_cheapest_items_away() {
+        let mut p = promotion(PromotionKind::BuyXGetY);
+        p.buy_quantity = Some(2);
+        p.get_quantity = Some(1);
+
+        // Seven items make two complete groups, so the two cheapest are free.
+        let lines = [
+            line(1, &[], 3, 1500),
+            line(1, &[], 1, 500),
+            line(1, &[], 3, 800),
+        ];
+
+        assert_eq!(discount(&p, &lines, None), Ok(Decimal::new(1300, 2)));
+    }
+
+    #[test]
+    fn buy_x_get_y_needs_a_complete_group() {
+        let mut p = promotion(PromotionKind::BuyXGetY);
+        p.buy_quantity = Some(2);
+        p.get_quantity = Some(1);
+
+        assert!(discount(&p, &[line(1, &[], 2, 1000)], None).is_err());
+    }
+
+    #[test]
+    fn buy_x_get_y_handles_large_quantities() {
+        let mut p = promotion(PromotionKind::BuyXGetY);
+        p.buy_quantity = Some(1);
+        p.get_quantity = Some(1);
+
+        assert_eq!(
+            discount(&p, &[line(1, &[], i32::MAX, 100)], None),
+            Ok(Decimal::from(i32::MAX / 2))
+        );
+    }
+
+    #[test]
+    fn buy_x_get_y_handles_large_groups() {
+        let mut p = promotion(PromotionKind::BuyXGetY);
+        p.buy_quantity = Some(i32::MAX);
+        p.get_quantity = Some(i32::MAX);
+
+        assert!(discount(&p, &[line(1, &[], 99, 100)], None).is_err());
+    }
+
+    #[test]
+    fn only_lines_in_scope_count() {
+        let mut p = promotion(PromotionKind::Percent);
+        p.value = Some(Decimal::from(50));
+        p.author_id = Some(1);
+
+        let lines = [line(1, &[10], 1, 1000), line(2, &[10], 1, 4000)];
+
+        assert_eq!(discount(&p, &lines, None), Ok(Decimal::from(5)));
+        assert_eq!(discount(&p, &lines, Some(&[10, 11])), Ok(Decimal::from(5)));
+        assert!(discount(&p, &lines, Some(&[11])).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::{Component, Path, PathBuf};
+
+use rocket::tokio::{fs, io};
+
+#[derive(Debug)]
+pub struct StorageError(pub String);
+
+impl From<io::Error> for StorageError {
+    fn from(err: io::Error) -> Self {
+        StorageError(err.to_string())
+    }
+}
+
+/// Where generated documents and uploads are kept. Keys are `/`-separated
+/// relative paths such as `invoices/INV-000001.pdf`.
+#[rocket::async_trait]
+pub trait BlobStorage: Send + Sync {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), StorageError>;
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+}
+
+/// Stores blobs as files under a directory on the local disk.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        let relative = Path::new(key);
+
+        if key.is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(StorageError(format!("Invalid storage key {:?}", key)));
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+#[rocket::async_trait]
+impl BlobStorage for LocalStorage {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        fs::write(path, data).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}